@@ -2,7 +2,8 @@
 fn main() {
     use sapling_crypto::bellman::pairing::bn256::Bn256;
     let merkle_depth = 32usize;
-    test_keys::export::<Bn256>(merkle_depth);
+    let message_limit = 1u64;
+    test_keys::export::<Bn256>(merkle_depth, message_limit);
 }
 
 #[cfg(target_arch = "wasm32")]
@@ -13,11 +14,12 @@ fn main() {
 #[cfg(not(target_arch = "wasm32"))]
 mod test_keys {
     use sapling_crypto::bellman::pairing::Engine;
-    pub fn export<E: Engine>(merkle_depth: usize) {
+    pub fn export<E: Engine>(merkle_depth: usize, message_limit: u64) {
         use rand::{SeedableRng, XorShiftRng};
         use rln::circuit::poseidon::PoseidonCircuit;
         use rln::circuit::rln::{RLNCircuit, RLNInputs};
         use rln::poseidon::PoseidonParams;
+        use rln::public::write_circuit_parameters;
         use sapling_crypto::bellman::groth16::generate_random_parameters;
         use std::fs::File;
 
@@ -27,12 +29,19 @@ mod test_keys {
         let circuit = RLNCircuit::<E> {
             inputs: RLNInputs::<E>::empty(merkle_depth),
             hasher: hasher.clone(),
+            message_limit,
         };
         let parameters = generate_random_parameters(circuit, &mut rng).unwrap();
         let mut file_vk = File::create("verifier.key").unwrap();
         let vk = parameters.vk.clone();
         vk.write(&mut file_vk).unwrap();
         let mut file_paramaters = File::create("parameters.key").unwrap();
-        parameters.write(&mut file_paramaters).unwrap();
+        write_circuit_parameters(
+            &mut file_paramaters,
+            merkle_depth,
+            message_limit,
+            &parameters,
+        )
+        .unwrap();
     }
 }
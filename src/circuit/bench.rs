@@ -1,4 +1,5 @@
 use crate::circuit::rln::{RLNCircuit, RLNInputs};
+use crate::error::RlnError;
 use crate::merkle::MerkleTree;
 use crate::poseidon::{Poseidon as PoseidonHasher, PoseidonParams};
 use crate::{circuit::poseidon::PoseidonCircuit, public::RLNSignal};
@@ -37,6 +38,38 @@ pub fn run_rln_prover_bench<E: Engine>(
     RLNTest::new(merkle_depth, Some(poseidon_params)).run_prover_bench()
 }
 
+/// times `rounds` authentication paths on the sequential and the parallel path,
+/// for the first leaf of a tree of `merkle_depth` with `pending` leaves spread over it and not flushed
+/// * returns the elapsed seconds of the sequential and the parallel path in order
+#[cfg(feature = "parallel")]
+pub fn run_witness_bench<E: Engine>(
+    merkle_depth: usize,
+    poseidon_params: PoseidonParams<E>,
+    pending: usize,
+    rounds: usize,
+) -> (f64, f64) {
+    let mut rng = XorShiftRng::from_seed([0x3dbe6258, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+    let mut tree = MerkleTree::empty(PoseidonHasher::new(poseidon_params), merkle_depth);
+    let stride = tree.set_size() / pending;
+    for i in 0..pending {
+        tree.set_leaf(i * stride, E::Fr::rand(&mut rng)).unwrap();
+    }
+
+    let now = Instant::now();
+    for _ in 0..rounds {
+        tree.sequential_witness(0);
+    }
+    let sequential_time = now.elapsed().as_micros() as f64 / 1000000.0;
+
+    let now = Instant::now();
+    for _ in 0..rounds {
+        tree.parallel_witness(0);
+    }
+    let parallel_time = now.elapsed().as_micros() as f64 / 1000000.0;
+
+    (sequential_time, parallel_time)
+}
+
 pub struct RLNTest<E>
 where
     E: Engine,
@@ -69,9 +102,13 @@ where
         0
     }
 
+    fn message_limit() -> u64 {
+        1
+    }
+
     pub fn new(merkle_depth: usize, poseidon_params: Option<PoseidonParams<E>>) -> RLNTest<E> {
         let mut rln_test = RLNTest {
-            rln: RLN::new(merkle_depth, poseidon_params),
+            rln: RLN::new(merkle_depth, Self::message_limit(), poseidon_params),
             merkle_depth,
         };
         rln_test.insert_public_key();
@@ -83,6 +120,10 @@ where
     }
 
     pub fn valid_inputs(&self) -> RLNInputs<E> {
+        self.valid_inputs_with_message_id(0)
+    }
+
+    pub fn valid_inputs_with_message_id(&self, message_id: u64) -> RLNInputs<E> {
         let mut rng = Self::rng();
         let hasher = self.rln.hasher();
 
@@ -111,6 +152,8 @@ where
 
         // get current epoch
         let epoch = E::Fr::rand(&mut rng);
+        let message_id = E::Fr::from_str(&message_id.to_string()).unwrap();
+        let external_nullifier: E::Fr = hasher.hash(vec![epoch, message_id]);
 
         let signal_hash = E::Fr::rand(&mut rng);
         // evaluation point is the signal_hash
@@ -118,7 +161,7 @@ where
 
         // calculate current line equation
         let a_0 = secret_key.clone();
-        let a_1: E::Fr = hasher.hash(vec![a_0, epoch]);
+        let a_1: E::Fr = hasher.hash(vec![a_0, external_nullifier]);
 
         // evaluate line equation
         let mut share_y = a_1.clone();
@@ -137,6 +180,7 @@ where
             nullifier: Some(nullifier),
             root: Some(membership_tree.get_root()),
             id_key: Some(secret_key),
+            message_id: Some(message_id),
             auth_path: auth_path.into_iter().map(|w| Some(w)).collect(),
         };
 
@@ -149,6 +193,7 @@ where
         let circuit = RLNCircuit::<E> {
             inputs: inputs.clone(),
             hasher: hasher.clone(),
+            message_limit: self.rln.message_limit(),
         };
 
         let mut cs = TestConstraintSystem::<E>::new();
@@ -182,6 +227,7 @@ where
         let mut rng = Self::rng();
 
         let epoch = E::Fr::rand(&mut rng);
+        let message_id = E::Fr::zero();
         let signal = b"rln signal test xyz abc";
 
         let mut input_data: Vec<u8> = Vec::new();
@@ -191,6 +237,7 @@ where
             .unwrap();
 
         epoch.into_repr().write_le(&mut input_data).unwrap();
+        message_id.into_repr().write_le(&mut input_data).unwrap();
         input_data
             .write_u64::<LittleEndian>(signal.len() as u64)
             .unwrap();
@@ -225,7 +272,19 @@ where
         }
     }
 
-    pub fn export_circuit_parameters<W: Write>(&self, w: W) -> io::Result<()> {
+    pub fn num_constraints(&self) -> usize {
+        self.rln.num_constraints()
+    }
+
+    pub fn param_sizes(&self) -> (usize, usize) {
+        self.rln.param_sizes()
+    }
+
+    pub fn export_circuit_parameters<W: Write>(&self, w: W) -> Result<(), RlnError> {
         self.rln.export_circuit_parameters(w)
     }
+
+    pub fn export_verifying_key<W: Write>(&self, w: W) -> Result<(), RlnError> {
+        self.rln.export_verifying_key(w)
+    }
 }
@@ -1,6 +1,9 @@
+pub mod counter;
 mod polynomial;
 pub mod poseidon;
+mod range;
 pub mod rln;
 
+// test and benchmark helpers, not compiled into production builds
 #[cfg(any(test, feature = "bench"))]
 pub mod bench;
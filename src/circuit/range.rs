@@ -0,0 +1,72 @@
+use sapling_crypto::bellman::pairing::ff::{Field, PrimeField, PrimeFieldRepr};
+use sapling_crypto::bellman::pairing::Engine;
+use sapling_crypto::bellman::{ConstraintSystem, LinearCombination, SynthesisError};
+use sapling_crypto::circuit::{boolean, num};
+
+// number of bits required to represent values in [0, limit)
+fn bit_len(limit: u64) -> usize {
+    (64 - (limit - 1).leading_zeros()) as usize
+}
+
+// allocates the lowest n bits of the value
+// and returns their weighted sum
+fn allocate_bits<CS, E>(
+    mut cs: CS,
+    value: Option<E::Fr>,
+    n: usize,
+) -> Result<LinearCombination<E>, SynthesisError>
+where
+    E: Engine,
+    CS: ConstraintSystem<E>,
+{
+    let low = value.map(|v| v.into_repr().as_ref()[0]);
+    let mut lc = LinearCombination::<E>::zero();
+    let mut coeff = E::Fr::one();
+    for i in 0..n {
+        let bit = boolean::AllocatedBit::alloc(
+            cs.namespace(|| format!("bit {}", i)),
+            low.map(|v| (v >> i) & 1 == 1),
+        )?;
+        lc = lc + (coeff, bit.get_variable());
+        coeff.double();
+    }
+    Ok(lc)
+}
+
+// enforces 0 <= a < limit
+// both a and limit - 1 - a must fit in the bit length of limit - 1
+pub fn enforce_less_than<CS, E>(
+    mut cs: CS,
+    a: &num::AllocatedNum<E>,
+    limit: u64,
+) -> Result<(), SynthesisError>
+where
+    E: Engine,
+    CS: ConstraintSystem<E>,
+{
+    assert!(limit > 0);
+    let n = bit_len(limit);
+    let max = E::Fr::from_repr(<E::Fr as PrimeField>::Repr::from(limit - 1)).unwrap();
+
+    let a_bits = allocate_bits(cs.namespace(|| "a bits"), a.get_value(), n)?;
+    cs.enforce(
+        || "a decomposition",
+        |_| a_bits,
+        |lc| lc + CS::one(),
+        |lc| lc + a.get_variable(),
+    );
+
+    let diff = a.get_value().map(|a| {
+        let mut diff = max;
+        diff.sub_assign(&a);
+        diff
+    });
+    let diff_bits = allocate_bits(cs.namespace(|| "diff bits"), diff, n)?;
+    cs.enforce(
+        || "diff decomposition",
+        |_| diff_bits,
+        |lc| lc + CS::one(),
+        |lc| lc + (max, CS::one()) - a.get_variable(),
+    );
+    Ok(())
+}
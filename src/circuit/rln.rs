@@ -1,5 +1,7 @@
 use crate::circuit::polynomial::allocate_add_with_coeff;
 use crate::circuit::poseidon::PoseidonCircuit;
+use crate::circuit::range::enforce_less_than;
+use crate::error::non_canonical_field;
 use crate::poseidon::{Poseidon as PoseidonHasher, PoseidonParams};
 use sapling_crypto::bellman::pairing::ff::{Field, PrimeField, PrimeFieldRepr};
 use sapling_crypto::bellman::pairing::Engine;
@@ -41,7 +43,12 @@ where
     // a secret line equation together with the epoch
     pub id_key: Option<E::Fr>,
 
-    // authentication path of the member
+    // message_id is the index of the message in the epoch,
+    // it must be below the message limit of the circuit
+    pub message_id: Option<E::Fr>,
+
+    // authentication path of the member,
+    // `arity - 1` siblings per level each flagged if it is right of the node on the path
     pub auth_path: Vec<Option<(E::Fr, bool)>>,
 }
 
@@ -59,11 +66,19 @@ where
         ]
     }
 
+    /// length of the authentication path, the merkle depth for a binary tree
     pub fn merkle_depth(&self) -> usize {
         self.auth_path.len()
     }
 
+    /// unassigned inputs for a binary membership tree of `merkle_depth`
     pub fn empty(merkle_depth: usize) -> RLNInputs<E> {
+        Self::empty_with_arity(merkle_depth, 2)
+    }
+
+    /// unassigned inputs for a membership tree of `merkle_depth` and `arity`,
+    /// the arity of the tree hashed with a poseidon of width `arity + 1`
+    pub fn empty_with_arity(merkle_depth: usize, arity: usize) -> RLNInputs<E> {
         RLNInputs::<E> {
             share_x: None,
             share_y: None,
@@ -71,7 +86,8 @@ where
             nullifier: None,
             root: None,
             id_key: None,
-            auth_path: vec![None; merkle_depth],
+            message_id: None,
+            auth_path: vec![None; merkle_depth * (arity - 1)],
         }
     }
 
@@ -79,25 +95,20 @@ where
         let mut buf = <E::Fr as PrimeField>::Repr::default();
 
         buf.read_le(&mut reader)?;
-        let share_x =
-            E::Fr::from_repr(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let share_x = E::Fr::from_repr(buf).map_err(|_| non_canonical_field())?;
 
-            
         buf.read_le(&mut reader)?;
-        let share_y =
-            E::Fr::from_repr(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let share_y = E::Fr::from_repr(buf).map_err(|_| non_canonical_field())?;
         buf.read_le(&mut reader)?;
-        let epoch =
-            E::Fr::from_repr(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let epoch = E::Fr::from_repr(buf).map_err(|_| non_canonical_field())?;
         buf.read_le(&mut reader)?;
-        let nullifier =
-            E::Fr::from_repr(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let nullifier = E::Fr::from_repr(buf).map_err(|_| non_canonical_field())?;
         buf.read_le(&mut reader)?;
-        let root =
-            E::Fr::from_repr(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let root = E::Fr::from_repr(buf).map_err(|_| non_canonical_field())?;
         buf.read_le(&mut reader)?;
-        let id_key =
-            E::Fr::from_repr(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let id_key = E::Fr::from_repr(buf).map_err(|_| non_canonical_field())?;
+        buf.read_le(&mut reader)?;
+        let message_id = E::Fr::from_repr(buf).map_err(|_| non_canonical_field())?;
         let auth_path = Self::decode_auth_path(&mut reader)?;
         Ok(RLNInputs {
             share_x: Some(share_x),
@@ -106,6 +117,7 @@ where
             nullifier: Some(nullifier),
             root: Some(root),
             id_key: Some(id_key),
+            message_id: Some(message_id),
             auth_path,
         })
     }
@@ -141,6 +153,11 @@ where
             .into_repr()
             .write_le(&mut writer)
             .unwrap();
+        self.message_id
+            .unwrap()
+            .into_repr()
+            .write_le(&mut writer)
+            .unwrap();
         Self::encode_auth_path(&mut writer, self.auth_path.clone()).unwrap();
         Ok(())
     }
@@ -148,20 +165,15 @@ where
     pub fn read_public_inputs<R: Read>(mut reader: R) -> io::Result<Vec<E::Fr>> {
         let mut buf = <E::Fr as PrimeField>::Repr::default();
         buf.read_le(&mut reader)?;
-        let root =
-            E::Fr::from_repr(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let root = E::Fr::from_repr(buf).map_err(|_| non_canonical_field())?;
         buf.read_le(&mut reader)?;
-        let epoch =
-            E::Fr::from_repr(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let epoch = E::Fr::from_repr(buf).map_err(|_| non_canonical_field())?;
         buf.read_le(&mut reader)?;
-        let share_x =
-            E::Fr::from_repr(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let share_x = E::Fr::from_repr(buf).map_err(|_| non_canonical_field())?;
         buf.read_le(&mut reader)?;
-        let share_y =
-            E::Fr::from_repr(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let share_y = E::Fr::from_repr(buf).map_err(|_| non_canonical_field())?;
         buf.read_le(&mut reader)?;
-        let nullifier =
-            E::Fr::from_repr(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let nullifier = E::Fr::from_repr(buf).map_err(|_| non_canonical_field())?;
         Ok(vec![root, epoch, share_x, share_y, nullifier])
     }
 
@@ -217,14 +229,94 @@ where
                 }
             };
             el_buf.read_le(&mut reader)?;
-            let node = E::Fr::from_repr(el_buf)
-                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            let node = E::Fr::from_repr(el_buf).map_err(|_| non_canonical_field())?;
             auth_path.push(Some((node, path_dir)));
         }
         Ok(auth_path)
     }
 }
 
+// orders the node `acc` on the path and its siblings at a level of the authentication path
+// as the children of their parent
+// * flags may only turn on along the level, so that the node has a single position
+// * child m is s_m + t_m - t_(m-1) with t_j = f_j * (acc - s_j) for sibling s_j and flag f_j,
+// terms out of range are dropped and the last child starts from `acc` instead of a sibling
+fn level_children<E: Engine, CS: ConstraintSystem<E>>(
+    mut cs: CS,
+    acc: &num::AllocatedNum<E>,
+    level: &[Option<(E::Fr, bool)>],
+) -> Result<Vec<num::AllocatedNum<E>>, SynthesisError> {
+    let mut flags: Vec<boolean::AllocatedBit> = Vec::with_capacity(level.len());
+    let mut siblings: Vec<num::AllocatedNum<E>> = Vec::with_capacity(level.len());
+    let mut terms: Vec<num::AllocatedNum<E>> = Vec::with_capacity(level.len());
+    for (j, e) in level.iter().copied().enumerate() {
+        let cs = &mut cs.namespace(|| format!("sibling {}", j));
+        let flag = boolean::AllocatedBit::alloc(cs.namespace(|| "position bit"), e.map(|e| e.1))?;
+        let sibling = num::AllocatedNum::alloc(cs.namespace(|| "path element"), || Ok(e.get()?.0))?;
+        let term = num::AllocatedNum::alloc(cs.namespace(|| "term"), || {
+            if !*flag.get_value().get()? {
+                return Ok(E::Fr::zero());
+            }
+            let mut value = *acc.get_value().get()?;
+            value.sub_assign(sibling.get_value().get()?);
+            Ok(value)
+        })?;
+        cs.enforce(
+            || "term",
+            |lc| lc + flag.get_variable(),
+            |lc| lc + acc.get_variable() - sibling.get_variable(),
+            |lc| lc + term.get_variable(),
+        );
+        if let Some(previous) = flags.last() {
+            cs.enforce(
+                || "flags turn on once",
+                |lc| lc + previous.get_variable(),
+                |lc| lc + CS::one() - flag.get_variable(),
+                |lc| lc,
+            );
+        }
+        flags.push(flag);
+        siblings.push(sibling);
+        terms.push(term);
+    }
+
+    let mut children: Vec<num::AllocatedNum<E>> = Vec::with_capacity(level.len() + 1);
+    for m in 0..=level.len() {
+        let child = num::AllocatedNum::alloc(cs.namespace(|| format!("child {}", m)), || {
+            let mut value = match siblings.get(m) {
+                Some(sibling) => {
+                    let mut value = *sibling.get_value().get()?;
+                    value.add_assign(terms[m].get_value().get()?);
+                    value
+                }
+                None => *acc.get_value().get()?,
+            };
+            if m > 0 {
+                value.sub_assign(terms[m - 1].get_value().get()?);
+            }
+            Ok(value)
+        })?;
+        cs.enforce(
+            || format!("child {} is ordered", m),
+            |lc| {
+                let lc = match siblings.get(m) {
+                    Some(sibling) => lc + sibling.get_variable() + terms[m].get_variable(),
+                    None => lc + acc.get_variable(),
+                };
+                if m > 0 {
+                    lc - terms[m - 1].get_variable()
+                } else {
+                    lc
+                }
+            },
+            |lc| lc + CS::one(),
+            |lc| lc + child.get_variable(),
+        );
+        children.push(child);
+    }
+    Ok(children)
+}
+
 #[derive(Clone)]
 pub struct RLNCircuit<E>
 where
@@ -232,6 +324,8 @@ where
 {
     pub inputs: RLNInputs<E>,
     pub hasher: PoseidonCircuit<E>,
+    // number of messages a member can send in an epoch
+    pub message_limit: u64,
 }
 
 impl<E> Circuit<E> for RLNCircuit<E>
@@ -267,25 +361,34 @@ where
         // ascend the tree
 
         let auth_path_witness = self.inputs.auth_path.clone();
-        for (i, e) in auth_path_witness.into_iter().enumerate() {
+        // a node hashes as many children as a single permutation absorbs,
+        // the two children of a binary tree are ordered by a conditional reversal
+        let arity = self.hasher.width() - 1;
+        for (i, level) in auth_path_witness.chunks(arity - 1).enumerate() {
             let cs = &mut cs.namespace(|| format!("auth path {}", i));
-            let position = boolean::Boolean::from(boolean::AllocatedBit::alloc(
-                cs.namespace(|| "position bit"),
-                e.map(|e| e.1),
-            )?);
-            let path_element =
-                num::AllocatedNum::alloc(cs.namespace(|| "path element"), || Ok(e.get()?.0))?;
-
-            let (xr, xl) = num::AllocatedNum::conditionally_reverse(
-                cs.namespace(|| "conditional reversal of preimage"),
-                &acc,
-                &path_element,
-                &position,
-            )?;
+            let children = if arity == 2 {
+                let e = level[0];
+                let position = boolean::Boolean::from(boolean::AllocatedBit::alloc(
+                    cs.namespace(|| "position bit"),
+                    e.map(|e| e.1),
+                )?);
+                let path_element =
+                    num::AllocatedNum::alloc(cs.namespace(|| "path element"), || Ok(e.get()?.0))?;
+
+                let (xr, xl) = num::AllocatedNum::conditionally_reverse(
+                    cs.namespace(|| "conditional reversal of preimage"),
+                    &acc,
+                    &path_element,
+                    &position,
+                )?;
+                vec![xl, xr]
+            } else {
+                level_children(cs.namespace(|| "children"), &acc, level)?
+            };
 
             acc = self
                 .hasher
-                .alloc(cs.namespace(|| "hash couple"), vec![xl, xr])?;
+                .alloc(cs.namespace(|| "hash couple"), children)?;
         }
 
         // see if it is a member
@@ -299,7 +402,7 @@ where
 
         // 2. Part
         // Line Equation Constaints
-        // a_1 = hash(a_0, epoch)
+        // a_1 = hash(a_0, hash(epoch, message_id))
         // share_y == a_0 + a_1 * share_x
 
         let epoch = num::AllocatedNum::alloc(cs.namespace(|| "epoch"), || {
@@ -308,13 +411,33 @@ where
         })?;
         epoch.inputize(cs.namespace(|| "epoch is public"))?;
 
+        // message_id < message_limit
+
+        let message_id = num::AllocatedNum::alloc(cs.namespace(|| "message id"), || {
+            let value = self.inputs.message_id.clone();
+            Ok(*value.get()?)
+        })?;
+        enforce_less_than(
+            cs.namespace(|| "message id range"),
+            &message_id,
+            self.message_limit,
+        )?;
+
+        // each message of the epoch gets its own line
+
+        let external_nullifier = self.hasher.alloc(
+            cs.namespace(|| "external nullifier"),
+            vec![epoch, message_id],
+        )?;
+
         let a_0 = preimage.clone();
 
-        // a_1 == h(a_0, epoch)
+        // a_1 == h(a_0, h(epoch, message_id))
 
-        let a_1 = self
-            .hasher
-            .alloc(cs.namespace(|| "a_1"), vec![a_0.clone(), epoch])?;
+        let a_1 = self.hasher.alloc(
+            cs.namespace(|| "a_1"),
+            vec![a_0.clone(), external_nullifier],
+        )?;
 
         let share_x = num::AllocatedNum::alloc(cs.namespace(|| "share x"), || {
             let value = self.inputs.share_x.clone();
@@ -345,7 +468,7 @@ where
         // Nullifier constraints
 
         // hashing secret twice with epoch ingredient
-        // a_1 == hash(a_0, epoch) is already constrained
+        // a_1 == hash(a_0, hash(epoch, message_id)) is already constrained
 
         // nullifier == hash(a_1)
 
@@ -440,6 +563,26 @@ mod test {
         }
     }
 
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parallel_witness_bench() {
+        use sapling_crypto::bellman::pairing::bn256::Bn256;
+        let merkle_depth = 20;
+        let (sequential_time, parallel_time) = bench::run_witness_bench::<Bn256>(
+            merkle_depth,
+            PoseidonParams::new(8, 55, 3, None, None, None),
+            1 << 10,
+            10,
+        );
+        println!(
+            "bn256, merkle depth: {}, pending leaves: {}",
+            merkle_depth,
+            1 << 10
+        );
+        println!("sequential witness time:\t{}", sequential_time);
+        println!("parallel witness time:\t{}", parallel_time);
+    }
+
     #[test]
     fn test_input_serialization() {
         use sapling_crypto::bellman::pairing::bn256::{Bn256, Fr};
@@ -450,6 +593,7 @@ mod test {
         let nullifier = Fr::from_str("4").unwrap();
         let root = Fr::from_str("5").unwrap();
         let id_key = Fr::from_str("6").unwrap();
+        let message_id = Fr::from_str("7").unwrap();
         let auth_path = vec![
             Some((Fr::from_str("20").unwrap(), false)),
             Some((Fr::from_str("21").unwrap(), true)),
@@ -463,6 +607,7 @@ mod test {
             nullifier: Some(nullifier),
             root: Some(root),
             id_key: Some(id_key),
+            message_id: Some(message_id),
             auth_path,
         };
         let mut raw_inputs: Vec<u8> = Vec::new();
@@ -475,6 +620,29 @@ mod test {
         assert_eq!(input0.nullifier, input1.nullifier);
         assert_eq!(input0.root, input1.root);
         assert_eq!(input0.id_key, input1.id_key);
+        assert_eq!(input0.message_id, input1.message_id);
         assert_eq!(input0.auth_path, input1.auth_path);
     }
+
+    #[test]
+    fn test_message_limit() {
+        use super::RLNCircuit;
+        use crate::circuit::poseidon::PoseidonCircuit;
+        use sapling_crypto::bellman::Circuit;
+        use sapling_crypto::circuit::test::TestConstraintSystem;
+
+        let poseidon_params = PoseidonParams::<Bn256>::new(8, 55, 3, None, None, None);
+        let rln_test = bench::RLNTest::<Bn256>::new(3, Some(poseidon_params.clone()));
+        let message_limit = 4u64;
+        for message_id in 0..6u64 {
+            let circuit = RLNCircuit::<Bn256> {
+                inputs: rln_test.valid_inputs_with_message_id(message_id),
+                hasher: PoseidonCircuit::new(poseidon_params.clone()),
+                message_limit,
+            };
+            let mut cs = TestConstraintSystem::<Bn256>::new();
+            circuit.synthesize(&mut cs).unwrap();
+            assert_eq!(message_id < message_limit, cs.is_satisfied());
+        }
+    }
 }
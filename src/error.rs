@@ -0,0 +1,86 @@
+use bellman::SynthesisError;
+use std::{error, fmt, io};
+
+/// failures of the public api
+#[derive(Debug)]
+pub enum RlnError {
+    /// the membership tree has no empty slot left
+    TreeFull,
+    /// a member index is not below the set size
+    IndexOutOfRange { index: usize, set_size: usize },
+    /// a field element is not below the modulus
+    NonCanonicalField,
+    /// a proof is generated against a root that is not accepted
+    RootMismatch,
+    /// a signal is meant for an application other than the one of the instance
+    IdentifierMismatch,
+    /// malformed input and failures of the underlying reader or writer
+    Io(io::Error),
+    /// failures of the proving system
+    Proving(SynthesisError),
+}
+
+impl fmt::Display for RlnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RlnError::TreeFull => write!(f, "membership tree is full"),
+            RlnError::IndexOutOfRange { index, set_size } => write!(
+                f,
+                "member index {} is out of range, indices must be below {}",
+                index, set_size
+            ),
+            RlnError::NonCanonicalField => write!(f, "field element is not canonical"),
+            RlnError::RootMismatch => write!(f, "root mismatch"),
+            RlnError::IdentifierMismatch => write!(f, "rln identifier mismatch"),
+            RlnError::Io(e) => write!(f, "{}", e),
+            RlnError::Proving(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl error::Error for RlnError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            RlnError::Io(e) => Some(e),
+            RlnError::Proving(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+// serialization helpers report through `io::Error`,
+// an `RlnError` carried inside is recovered as is
+impl From<io::Error> for RlnError {
+    fn from(e: io::Error) -> Self {
+        if !e.get_ref().map_or(false, |inner| inner.is::<RlnError>()) {
+            return RlnError::Io(e);
+        }
+        // checked above
+        *e.into_inner().unwrap().downcast::<RlnError>().unwrap()
+    }
+}
+
+impl From<SynthesisError> for RlnError {
+    fn from(e: SynthesisError) -> Self {
+        RlnError::Proving(e)
+    }
+}
+
+impl From<RlnError> for io::Error {
+    fn from(e: RlnError) -> Self {
+        let kind = match e {
+            RlnError::Io(e) => return e,
+            RlnError::TreeFull | RlnError::Proving(_) => io::ErrorKind::Other,
+            RlnError::IndexOutOfRange { .. }
+            | RlnError::RootMismatch
+            | RlnError::IdentifierMismatch => io::ErrorKind::InvalidInput,
+            RlnError::NonCanonicalField => io::ErrorKind::InvalidData,
+        };
+        io::Error::new(kind, e)
+    }
+}
+
+/// the error of reading a field element that is not below the modulus
+pub(crate) fn non_canonical_field() -> io::Error {
+    RlnError::NonCanonicalField.into()
+}
@@ -1,5 +1,15 @@
-use crate::{circuit::rln, public::RLN};
+use crate::{
+    circuit::rln,
+    error::RlnError,
+    public::{self, RLNVerifier, RLN},
+    utils::read_fr,
+};
+use bellman::pairing::bls12_381::Bls12;
 use bellman::pairing::bn256::Bn256;
+use std::convert::TryFrom;
+use std::fs::File;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr::NonNull;
 use std::slice;
 
 /// Buffer struct is taken from
@@ -14,8 +24,15 @@ pub struct Buffer {
 
 impl From<&[u8]> for Buffer {
     fn from(src: &[u8]) -> Self {
+        // an empty buffer never points to memory of the slice
+        if src.is_empty() {
+            return Self {
+                ptr: NonNull::<u8>::dangling().as_ptr(),
+                len: 0,
+            };
+        }
         Self {
-            ptr: &src[0] as *const u8,
+            ptr: src.as_ptr(),
             len: src.len(),
         }
     }
@@ -23,9 +40,89 @@ impl From<&[u8]> for Buffer {
 
 impl<'a> From<&Buffer> for &'a [u8] {
     fn from(src: &Buffer) -> &'a [u8] {
+        // the pointer of an empty buffer is never dereferenced
+        if src.ptr.is_null() || src.len == 0 {
+            return &[];
+        }
         unsafe { slice::from_raw_parts(src.ptr, src.len) }
     }
 }
+
+/// Hands `output_data` over to the caller through `output_buffer`.
+/// The allocation must be released with `free_buffer`.
+fn set_output_buffer(output_buffer: *mut Buffer, output_data: Vec<u8>) {
+    let output_data = output_data.into_boxed_slice();
+    unsafe { *output_buffer = Buffer::from(&output_data[..]) };
+    std::mem::forget(output_data);
+}
+
+// frames field elements serialized in 32 bytes each as [ count<4> | element<32> * count ],
+// the count is little-endian, none if it does not fit in 4 bytes
+fn frame_elements(count: usize, elements: Vec<u8>) -> Option<Vec<u8>> {
+    let count = u32::try_from(count).ok()?;
+    let mut framed = Vec::with_capacity(4 + elements.len());
+    framed.extend_from_slice(&count.to_le_bytes());
+    framed.extend(elements);
+    Some(framed)
+}
+
+// serialized size of a field element
+const FIELD_BYTES: usize = 32;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RlnStatus {
+    Ok = 0,
+    NullPointer = 1,
+    DeserializeError = 2,
+    ProvingError = 3,
+    VerificationError = 4,
+    IoError = 5,
+    Panic = 6,
+    TreeFull = 7,
+}
+
+impl RlnStatus {
+    /// maps an error of the public api onto a status code,
+    /// `fallback` is used for the failures that are not caused by malformed input
+    fn from_error(e: &RlnError, fallback: RlnStatus) -> RlnStatus {
+        match e {
+            RlnError::TreeFull => RlnStatus::TreeFull,
+            RlnError::IndexOutOfRange { .. }
+            | RlnError::NonCanonicalField
+            | RlnError::IdentifierMismatch => RlnStatus::DeserializeError,
+            RlnError::RootMismatch => RlnStatus::VerificationError,
+            RlnError::Io(e) => RlnStatus::from_io_error(e, fallback),
+            RlnError::Proving(_) => fallback,
+        }
+    }
+
+    fn from_io_error(e: &io::Error, fallback: RlnStatus) -> RlnStatus {
+        match e.kind() {
+            io::ErrorKind::InvalidData
+            | io::ErrorKind::InvalidInput
+            | io::ErrorKind::UnexpectedEof => RlnStatus::DeserializeError,
+            io::ErrorKind::Other => fallback,
+            _ => RlnStatus::IoError,
+        }
+    }
+}
+
+// a panic must not unwind across the FFI boundary,
+// entry points run their bodies through one of the following
+
+fn catch_status<F: FnOnce() -> RlnStatus>(f: F) -> RlnStatus {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(RlnStatus::Panic)
+}
+
+fn catch_bool<F: FnOnce() -> bool>(f: F) -> bool {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(false)
+}
+
+fn catch_unit<F: FnOnce()>(f: F) {
+    let _ = panic::catch_unwind(AssertUnwindSafe(f));
+}
+
 #[repr(C)]
 #[derive(Clone, Debug, PartialEq)]
 pub struct Auth {
@@ -43,48 +140,498 @@ impl Auth {
 #[no_mangle]
 pub extern "C" fn new_circuit_from_params(
     merkle_depth: usize,
+    message_limit: u64,
     parameters_buffer: *const Buffer,
     ctx: *mut *mut RLN<Bn256>,
-) -> bool {
-    let buffer = <&[u8]>::from(unsafe { &*parameters_buffer });
-    let rln = match RLN::<Bn256>::new_with_raw_params(merkle_depth, buffer, None) {
-        Ok(rln) => rln,
-        Err(_) => return false,
-    };
-    unsafe { *ctx = Box::into_raw(Box::new(rln)) };
-    true
+) -> RlnStatus {
+    new_circuit_from_params_impl(merkle_depth, message_limit, parameters_buffer, ctx)
+}
+
+/// Functions suffixed with `_bls12_381` operate on an instance over BLS12-381 instead of BN256.
+/// Field elements are 32 bytes little-endian on both curves,
+/// the uncompressed proof is 384 bytes on BLS12-381 instead of 256 bytes.
+#[no_mangle]
+pub extern "C" fn new_circuit_from_params_bls12_381(
+    merkle_depth: usize,
+    message_limit: u64,
+    parameters_buffer: *const Buffer,
+    ctx: *mut *mut RLN<Bls12>,
+) -> RlnStatus {
+    new_circuit_from_params_impl(merkle_depth, message_limit, parameters_buffer, ctx)
+}
+
+fn new_circuit_from_params_impl<E: Engine>(
+    merkle_depth: usize,
+    message_limit: u64,
+    parameters_buffer: *const Buffer,
+    ctx: *mut *mut RLN<E>,
+) -> RlnStatus {
+    catch_status(|| {
+        if parameters_buffer.is_null() || ctx.is_null() {
+            return RlnStatus::NullPointer;
+        }
+        let buffer = <&[u8]>::from(unsafe { &*parameters_buffer });
+        let rln = match RLN::<E>::new_with_raw_params(merkle_depth, message_limit, buffer, None) {
+            Ok(rln) => rln,
+            Err(e) => return RlnStatus::from_error(&e, RlnStatus::IoError),
+        };
+        unsafe { *ctx = Box::into_raw(Box::new(rln)) };
+        RlnStatus::Ok
+    })
+}
+
+/// Creates an instance from circuit parameters stored in the file at `path_ptr`,
+/// serialized as the output of `export_circuit_parameters`.
+/// The path is `path_len` bytes of UTF-8 and the file is read without buffering it whole.
+#[no_mangle]
+pub extern "C" fn new_circuit_from_file(
+    merkle_depth: usize,
+    message_limit: u64,
+    path_ptr: *const u8,
+    path_len: usize,
+    ctx: *mut *mut RLN<Bn256>,
+) -> RlnStatus {
+    catch_status(|| {
+        if path_ptr.is_null() || ctx.is_null() {
+            return RlnStatus::NullPointer;
+        }
+        let path = <&[u8]>::from(&Buffer {
+            ptr: path_ptr,
+            len: path_len,
+        });
+        let path = match std::str::from_utf8(path) {
+            Ok(path) => path,
+            Err(_) => return RlnStatus::DeserializeError,
+        };
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(_) => return RlnStatus::IoError,
+        };
+        let rln = match RLN::<Bn256>::new_with_reader(
+            merkle_depth,
+            message_limit,
+            io::BufReader::new(file),
+            None,
+        ) {
+            Ok(rln) => rln,
+            Err(e) => return RlnStatus::from_error(&e, RlnStatus::IoError),
+        };
+        unsafe { *ctx = Box::into_raw(Box::new(rln)) };
+        RlnStatus::Ok
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn free_rln(ctx: *mut RLN<Bn256>) {
+    free_rln_impl(ctx)
+}
+
+#[no_mangle]
+pub extern "C" fn free_rln_bls12_381(ctx: *mut RLN<Bls12>) {
+    free_rln_impl(ctx)
+}
+
+fn free_rln_impl<E: Engine>(ctx: *mut RLN<E>) {
+    catch_unit(|| {
+        if ctx.is_null() {
+            return;
+        }
+        drop(unsafe { Box::from_raw(ctx) });
+    })
+}
+
+/// Creates a verify-only context from a verifying key exported with `export_verifying_key`.
+#[no_mangle]
+pub extern "C" fn new_verifier_from_vk(
+    vk_buffer: *const Buffer,
+    ctx: *mut *mut RLNVerifier<Bn256>,
+) -> RlnStatus {
+    catch_status(|| {
+        if vk_buffer.is_null() || ctx.is_null() {
+            return RlnStatus::NullPointer;
+        }
+        let buffer = <&[u8]>::from(unsafe { &*vk_buffer });
+        let verifier = match RLNVerifier::<Bn256>::from_raw_vk(buffer) {
+            Ok(verifier) => verifier,
+            Err(e) => return RlnStatus::from_error(&e, RlnStatus::IoError),
+        };
+        unsafe { *ctx = Box::into_raw(Box::new(verifier)) };
+        RlnStatus::Ok
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn free_verifier(ctx: *mut RLNVerifier<Bn256>) {
+    catch_unit(|| {
+        if ctx.is_null() {
+            return;
+        }
+        drop(unsafe { Box::from_raw(ctx) });
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn export_verifying_key(
+    ctx: *const RLN<Bn256>,
+    output_buffer: *mut Buffer,
+) -> RlnStatus {
+    catch_status(|| {
+        if ctx.is_null() || output_buffer.is_null() {
+            return RlnStatus::NullPointer;
+        }
+        let rln = unsafe { &*ctx };
+        let mut output_data: Vec<u8> = Vec::new();
+        if let Err(e) = rln.export_verifying_key(&mut output_data) {
+            return RlnStatus::from_error(&e, RlnStatus::IoError);
+        }
+        set_output_buffer(output_buffer, output_data);
+        RlnStatus::Ok
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn set_root_history_capacity(ctx: *mut RLN<Bn256>, capacity: usize) -> RlnStatus {
+    catch_status(|| {
+        if ctx.is_null() {
+            return RlnStatus::NullPointer;
+        }
+        let rln = unsafe { &mut *ctx };
+        rln.set_root_history_capacity(capacity);
+        RlnStatus::Ok
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn reset_tree(ctx: *mut RLN<Bn256>) -> RlnStatus {
+    catch_status(|| {
+        if ctx.is_null() {
+            return RlnStatus::NullPointer;
+        }
+        let rln = unsafe { &mut *ctx };
+        rln.reset_tree();
+        RlnStatus::Ok
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn root_history_capacity(
+    ctx: *const RLN<Bn256>,
+    result_ptr: *mut usize,
+) -> RlnStatus {
+    catch_status(|| {
+        if ctx.is_null() || result_ptr.is_null() {
+            return RlnStatus::NullPointer;
+        }
+        let rln = unsafe { &*ctx };
+        unsafe { *result_ptr = rln.root_history_capacity() };
+        RlnStatus::Ok
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn get_merkle_depth(ctx: *const RLN<Bn256>, result_ptr: *mut usize) -> RlnStatus {
+    catch_status(|| {
+        if ctx.is_null() || result_ptr.is_null() {
+            return RlnStatus::NullPointer;
+        }
+        let rln = unsafe { &*ctx };
+        unsafe { *result_ptr = rln.merkle_depth() };
+        RlnStatus::Ok
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn tree_capacity(ctx: *const RLN<Bn256>, result_ptr: *mut usize) -> RlnStatus {
+    catch_status(|| {
+        if ctx.is_null() || result_ptr.is_null() {
+            return RlnStatus::NullPointer;
+        }
+        let rln = unsafe { &*ctx };
+        unsafe { *result_ptr = rln.capacity() };
+        RlnStatus::Ok
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn tree_len(ctx: *const RLN<Bn256>, result_ptr: *mut usize) -> RlnStatus {
+    catch_status(|| {
+        if ctx.is_null() || result_ptr.is_null() {
+            return RlnStatus::NullPointer;
+        }
+        let rln = unsafe { &*ctx };
+        unsafe { *result_ptr = rln.len() };
+        RlnStatus::Ok
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn verify_with_recent_root(
+    ctx: *const RLN<Bn256>,
+    proof_buffer: *const Buffer,
+    result_ptr: *mut u32,
+) -> RlnStatus {
+    catch_status(|| {
+        if ctx.is_null() || proof_buffer.is_null() || result_ptr.is_null() {
+            return RlnStatus::NullPointer;
+        }
+        let rln = unsafe { &*ctx };
+        let proof_data = <&[u8]>::from(unsafe { &*proof_buffer });
+        if match rln.verify_with_recent_root(proof_data) {
+            Ok(verified) => verified,
+            Err(RlnError::RootMismatch) => false,
+            Err(e) => return RlnStatus::from_error(&e, RlnStatus::VerificationError),
+        } {
+            unsafe { *result_ptr = 0 };
+        } else {
+            unsafe { *result_ptr = 1 };
+        };
+        RlnStatus::Ok
+    })
+}
+
+/// Same as `verify` with a verify-only context.
+#[no_mangle]
+pub extern "C" fn verifier_verify(
+    ctx: *const RLNVerifier<Bn256>,
+    proof_buffer: *const Buffer,
+    result_ptr: *mut u32,
+) -> RlnStatus {
+    catch_status(|| {
+        if ctx.is_null() || proof_buffer.is_null() || result_ptr.is_null() {
+            return RlnStatus::NullPointer;
+        }
+        let verifier = unsafe { &*ctx };
+        let proof_data = <&[u8]>::from(unsafe { &*proof_buffer });
+        if match verifier.verify(proof_data) {
+            Ok(verified) => verified,
+            Err(e) => return RlnStatus::from_error(&e, RlnStatus::VerificationError),
+        } {
+            unsafe { *result_ptr = 0 };
+        } else {
+            unsafe { *result_ptr = 1 };
+        };
+        RlnStatus::Ok
+    })
+}
+
+/// Releases a buffer filled by one of the functions of this module.
+/// Must be called exactly once for every buffer produced by this crate.
+#[no_mangle]
+pub extern "C" fn free_buffer(buffer: *mut Buffer) {
+    catch_unit(|| {
+        if buffer.is_null() {
+            return;
+        }
+        let buffer = unsafe { &mut *buffer };
+        if buffer.ptr.is_null() {
+            return;
+        }
+        drop(unsafe { Vec::from_raw_parts(buffer.ptr as *mut u8, buffer.len, buffer.len) });
+        buffer.ptr = std::ptr::null();
+        buffer.len = 0;
+    })
 }
 
 #[no_mangle]
 pub extern "C" fn get_root(ctx: *const RLN<Bn256>, output_buffer: *mut Buffer) -> bool {
-    let rln = unsafe { &*ctx };
-    let mut output_data: Vec<u8> = Vec::new();
-    match rln.get_root(&mut output_data) {
-        Ok(_) => true,
-        Err(_) => false,
-    };
-    unsafe { *output_buffer = Buffer::from(&output_data[..]) };
-    std::mem::forget(output_data);
-    true
+    get_root_impl(ctx, output_buffer)
 }
 
 #[no_mangle]
-pub extern "C" fn update_next_member(ctx: *mut RLN<Bn256>, input_buffer: *const Buffer) -> bool {
-    let rln = unsafe { &mut *ctx };
-    let input_data = <&[u8]>::from(unsafe { &*input_buffer });
-    match rln.update_next_member(input_data) {
-        Ok(_) => true,
-        Err(_) => false,
-    }
+pub extern "C" fn get_root_bls12_381(ctx: *const RLN<Bls12>, output_buffer: *mut Buffer) -> bool {
+    get_root_impl(ctx, output_buffer)
+}
+
+fn get_root_impl<E: Engine>(ctx: *const RLN<E>, output_buffer: *mut Buffer) -> bool {
+    catch_bool(|| {
+        if ctx.is_null() || output_buffer.is_null() {
+            return false;
+        }
+        let rln = unsafe { &*ctx };
+        let mut output_data: Vec<u8> = Vec::new();
+        match rln.get_root(&mut output_data) {
+            Ok(_) => (),
+            Err(_) => return false,
+        };
+        set_output_buffer(output_buffer, output_data);
+        true
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn update_next_member(
+    ctx: *mut RLN<Bn256>,
+    input_buffer: *const Buffer,
+) -> RlnStatus {
+    update_next_member_impl(ctx, input_buffer)
+}
+
+#[no_mangle]
+pub extern "C" fn update_next_member_bls12_381(
+    ctx: *mut RLN<Bls12>,
+    input_buffer: *const Buffer,
+) -> RlnStatus {
+    update_next_member_impl(ctx, input_buffer)
+}
+
+fn update_next_member_impl<E: Engine>(ctx: *mut RLN<E>, input_buffer: *const Buffer) -> RlnStatus {
+    catch_status(|| {
+        if ctx.is_null() || input_buffer.is_null() {
+            return RlnStatus::NullPointer;
+        }
+        let rln = unsafe { &mut *ctx };
+        let input_data = <&[u8]>::from(unsafe { &*input_buffer });
+        match rln.update_next_member(input_data) {
+            Ok(_) => RlnStatus::Ok,
+            Err(e) => RlnStatus::from_error(&e, RlnStatus::IoError),
+        }
+    })
+}
+
+/// same as `update_next_member` and writes the index assigned to the member to `output_index`
+#[no_mangle]
+pub extern "C" fn update_next_member_indexed(
+    ctx: *mut RLN<Bn256>,
+    input_buffer: *const Buffer,
+    output_index: *mut usize,
+) -> RlnStatus {
+    catch_status(|| {
+        if ctx.is_null() || input_buffer.is_null() || output_index.is_null() {
+            return RlnStatus::NullPointer;
+        }
+        let rln = unsafe { &mut *ctx };
+        let input_data = <&[u8]>::from(unsafe { &*input_buffer });
+        match rln.update_next_member(input_data) {
+            Ok(index) => {
+                unsafe { *output_index = index };
+                RlnStatus::Ok
+            }
+            Err(e) => RlnStatus::from_error(&e, RlnStatus::IoError),
+        }
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn update_next_batch(
+    ctx: *mut RLN<Bn256>,
+    input_buffer: *const Buffer,
+    count: usize,
+) -> RlnStatus {
+    catch_status(|| {
+        if ctx.is_null() || input_buffer.is_null() {
+            return RlnStatus::NullPointer;
+        }
+        let rln = unsafe { &mut *ctx };
+        let input_data = <&[u8]>::from(unsafe { &*input_buffer });
+        match rln.update_next_batch(input_data, count) {
+            Ok(_) => RlnStatus::Ok,
+            Err(e) => RlnStatus::from_error(&e, RlnStatus::IoError),
+        }
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn set_leaf(
+    ctx: *mut RLN<Bn256>,
+    index: usize,
+    input_buffer: *const Buffer,
+) -> RlnStatus {
+    catch_status(|| {
+        if ctx.is_null() || input_buffer.is_null() {
+            return RlnStatus::NullPointer;
+        }
+        let rln = unsafe { &mut *ctx };
+        let input_data = <&[u8]>::from(unsafe { &*input_buffer });
+        match rln.set_leaf(index, input_data) {
+            Ok(_) => RlnStatus::Ok,
+            Err(e) => RlnStatus::from_error(&e, RlnStatus::IoError),
+        }
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn get_leaf(
+    ctx: *const RLN<Bn256>,
+    index: usize,
+    output_buffer: *mut Buffer,
+) -> RlnStatus {
+    catch_status(|| {
+        if ctx.is_null() || output_buffer.is_null() {
+            return RlnStatus::NullPointer;
+        }
+        let rln = unsafe { &*ctx };
+        let mut output_data: Vec<u8> = Vec::new();
+        if let Err(e) = rln.get_leaf(index, &mut output_data) {
+            return RlnStatus::from_error(&e, RlnStatus::IoError);
+        }
+        set_output_buffer(output_buffer, output_data);
+        RlnStatus::Ok
+    })
 }
 
 #[no_mangle]
 pub extern "C" fn delete_member(ctx: *mut RLN<Bn256>, index: usize) -> bool {
-    let rln = unsafe { &mut *ctx };
-    match rln.delete_member(index) {
-        Ok(_) => true,
-        Err(_) => false,
-    }
+    delete_member_impl(ctx, index)
+}
+
+#[no_mangle]
+pub extern "C" fn delete_member_bls12_381(ctx: *mut RLN<Bls12>, index: usize) -> bool {
+    delete_member_impl(ctx, index)
+}
+
+fn delete_member_impl<E: Engine>(ctx: *mut RLN<E>, index: usize) -> bool {
+    catch_bool(|| {
+        if ctx.is_null() {
+            return false;
+        }
+        let rln = unsafe { &mut *ctx };
+        match rln.delete_member(index) {
+            Ok(_) => true,
+            Err(_) => false,
+        }
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn compute_nullifier(
+    ctx: *const RLN<Bn256>,
+    input_buffer: *const Buffer,
+    output_buffer: *mut Buffer,
+) -> RlnStatus {
+    catch_status(|| {
+        if ctx.is_null() || input_buffer.is_null() || output_buffer.is_null() {
+            return RlnStatus::NullPointer;
+        }
+        let rln = unsafe { &*ctx };
+        let input_data = <&[u8]>::from(unsafe { &*input_buffer });
+        let mut output_data: Vec<u8> = Vec::new();
+        if let Err(e) = rln.compute_nullifier(input_data, &mut output_data) {
+            return RlnStatus::from_error(&e, RlnStatus::IoError);
+        }
+        set_output_buffer(output_buffer, output_data);
+        RlnStatus::Ok
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn epoch_from_timestamp(
+    ctx: *const RLN<Bn256>,
+    unix_seconds: u64,
+    window_seconds: u64,
+    output_buffer: *mut Buffer,
+) -> RlnStatus {
+    catch_status(|| {
+        if ctx.is_null() || output_buffer.is_null() {
+            return RlnStatus::NullPointer;
+        }
+        let rln = unsafe { &*ctx };
+        let mut output_data: Vec<u8> = Vec::new();
+        if let Err(e) = rln.epoch_from_timestamp(unix_seconds, window_seconds, &mut output_data) {
+            return RlnStatus::from_error(&e, RlnStatus::IoError);
+        }
+        set_output_buffer(output_buffer, output_data);
+        RlnStatus::Ok
+    })
 }
 
 #[no_mangle]
@@ -92,37 +639,316 @@ pub extern "C" fn generate_proof(
     ctx: *const RLN<Bn256>,
     input_buffer: *const Buffer,
     output_buffer: *mut Buffer,
-) -> bool {
-    let rln = unsafe { &*ctx };
-    let input_data = <&[u8]>::from(unsafe { &*input_buffer });
-    let mut output_data: Vec<u8> = Vec::new();
-
-    match rln.generate_proof(input_data, &mut output_data) {
-        Ok(proof_data) => proof_data,
-        Err(_) => return false,
-    };
-    unsafe { *output_buffer = Buffer::from(&output_data[..]) };
-    std::mem::forget(output_data);
-    true
+) -> RlnStatus {
+    generate_proof_impl(ctx, input_buffer, output_buffer)
+}
+
+#[no_mangle]
+pub extern "C" fn generate_proof_bls12_381(
+    ctx: *const RLN<Bls12>,
+    input_buffer: *const Buffer,
+    output_buffer: *mut Buffer,
+) -> RlnStatus {
+    generate_proof_impl(ctx, input_buffer, output_buffer)
+}
+
+fn generate_proof_impl<E: Engine>(
+    ctx: *const RLN<E>,
+    input_buffer: *const Buffer,
+    output_buffer: *mut Buffer,
+) -> RlnStatus {
+    catch_status(|| {
+        if ctx.is_null() || input_buffer.is_null() || output_buffer.is_null() {
+            return RlnStatus::NullPointer;
+        }
+        let rln = unsafe { &*ctx };
+        let input_data = <&[u8]>::from(unsafe { &*input_buffer });
+        let mut output_data: Vec<u8> = Vec::new();
+
+        match rln.generate_proof(input_data, &mut output_data) {
+            Ok(proof_data) => proof_data,
+            Err(e) => return RlnStatus::from_error(&e, RlnStatus::ProvingError),
+        };
+        set_output_buffer(output_buffer, output_data);
+        RlnStatus::Ok
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn generate_proof_at(
+    ctx: *const RLN<Bn256>,
+    index: usize,
+    input_buffer: *const Buffer,
+    output_buffer: *mut Buffer,
+) -> RlnStatus {
+    catch_status(|| {
+        if ctx.is_null() || input_buffer.is_null() || output_buffer.is_null() {
+            return RlnStatus::NullPointer;
+        }
+        let rln = unsafe { &*ctx };
+        let input_data = <&[u8]>::from(unsafe { &*input_buffer });
+        let mut output_data: Vec<u8> = Vec::new();
+        if let Err(e) = rln.generate_proof_at(index, input_data, &mut output_data) {
+            return RlnStatus::from_error(&e, RlnStatus::ProvingError);
+        }
+        set_output_buffer(output_buffer, output_data);
+        RlnStatus::Ok
+    })
 }
 
+/// Verifies proof data followed by the signal, see `RLN::verify`.
+/// `result_ptr` is set to `0` for a valid proof and `1` for a well formed proof that does not verify,
+/// malformed data is reported as `DeserializeError` and leaves `result_ptr` as is.
 #[no_mangle]
 pub extern "C" fn verify(
     ctx: *const RLN<Bn256>,
     proof_buffer: *const Buffer,
     result_ptr: *mut u32,
-) -> bool {
-    let rln = unsafe { &*ctx };
-    let proof_data = <&[u8]>::from(unsafe { &*proof_buffer });
-    if match rln.verify(proof_data) {
-        Ok(verified) => verified,
-        Err(_) => return false,
-    } {
-        unsafe { *result_ptr = 0 };
-    } else {
-        unsafe { *result_ptr = 1 };
-    };
-    true
+) -> RlnStatus {
+    verify_impl(ctx, proof_buffer, result_ptr)
+}
+
+#[no_mangle]
+pub extern "C" fn verify_bls12_381(
+    ctx: *const RLN<Bls12>,
+    proof_buffer: *const Buffer,
+    result_ptr: *mut u32,
+) -> RlnStatus {
+    verify_impl(ctx, proof_buffer, result_ptr)
+}
+
+fn verify_impl<E: Engine>(
+    ctx: *const RLN<E>,
+    proof_buffer: *const Buffer,
+    result_ptr: *mut u32,
+) -> RlnStatus {
+    catch_status(|| {
+        if ctx.is_null() || proof_buffer.is_null() || result_ptr.is_null() {
+            return RlnStatus::NullPointer;
+        }
+        let rln = unsafe { &*ctx };
+        let proof_data = <&[u8]>::from(unsafe { &*proof_buffer });
+        if match rln.verify(proof_data) {
+            Ok(verified) => verified,
+            Err(e) => return RlnStatus::from_error(&e, RlnStatus::VerificationError),
+        } {
+            unsafe { *result_ptr = 0 };
+        } else {
+            unsafe { *result_ptr = 1 };
+        };
+        RlnStatus::Ok
+    })
+}
+
+/// Verifies a proof against public inputs passed apart from it, see `RLN::verify_with_inputs`.
+/// `proof_buffer` holds the uncompressed proof [ proof<256> ] and `public_inputs_buffer`
+/// [ root<32> | epoch<32> | share_x<32> | share_y<32> | nullifier<32> ] as in proof data.
+#[no_mangle]
+pub extern "C" fn verify_split(
+    ctx: *const RLN<Bn256>,
+    proof_buffer: *const Buffer,
+    public_inputs_buffer: *const Buffer,
+    result_ptr: *mut u32,
+) -> RlnStatus {
+    catch_status(|| {
+        if ctx.is_null()
+            || proof_buffer.is_null()
+            || public_inputs_buffer.is_null()
+            || result_ptr.is_null()
+        {
+            return RlnStatus::NullPointer;
+        }
+        let rln = unsafe { &*ctx };
+        let proof_data = <&[u8]>::from(unsafe { &*proof_buffer });
+        let public_inputs_data = <&[u8]>::from(unsafe { &*public_inputs_buffer });
+        if public_inputs_data.len() != 5 * FIELD_BYTES {
+            return RlnStatus::DeserializeError;
+        }
+        let inputs = match read_fr::<_, Bn256>(public_inputs_data, 5) {
+            Ok(inputs) => inputs,
+            Err(e) => return RlnStatus::from_error(&e.into(), RlnStatus::IoError),
+        };
+        if match rln.verify_with_inputs(
+            proof_data, inputs[0], inputs[1], inputs[4], inputs[2], inputs[3],
+        ) {
+            Ok(verified) => verified,
+            Err(e) => return RlnStatus::from_error(&e, RlnStatus::VerificationError),
+        } {
+            unsafe { *result_ptr = 0 };
+        } else {
+            unsafe { *result_ptr = 1 };
+        };
+        RlnStatus::Ok
+    })
+}
+
+/// Verifies `count` concatenated inputs of `verify`,
+/// `output_buffer` holds a byte per proof, `0` if valid and `1` otherwise.
+#[no_mangle]
+pub extern "C" fn verify_batch(
+    ctx: *const RLN<Bn256>,
+    proofs_buffer: *const Buffer,
+    count: usize,
+    output_buffer: *mut Buffer,
+) -> RlnStatus {
+    catch_status(|| {
+        if ctx.is_null() || proofs_buffer.is_null() || output_buffer.is_null() {
+            return RlnStatus::NullPointer;
+        }
+        let rln = unsafe { &*ctx };
+        let proofs_data = <&[u8]>::from(unsafe { &*proofs_buffer });
+        let results = match rln.verify_batch(proofs_data, count) {
+            Ok(results) => results,
+            Err(e) => return RlnStatus::from_error(&e, RlnStatus::VerificationError),
+        };
+        let output_data: Vec<u8> = results.into_iter().map(|r| !r as u8).collect();
+        set_output_buffer(output_buffer, output_data);
+        RlnStatus::Ok
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn verify_with_root(
+    ctx: *const RLN<Bn256>,
+    proof_buffer: *const Buffer,
+    root_buffer: *const Buffer,
+    result_ptr: *mut u32,
+) -> RlnStatus {
+    catch_status(|| {
+        if ctx.is_null() || proof_buffer.is_null() || root_buffer.is_null() || result_ptr.is_null()
+        {
+            return RlnStatus::NullPointer;
+        }
+        let rln = unsafe { &*ctx };
+        let proof_data = <&[u8]>::from(unsafe { &*proof_buffer });
+        let root_data = <&[u8]>::from(unsafe { &*root_buffer });
+        if match rln.verify_with_root(proof_data, root_data) {
+            Ok(verified) => verified,
+            Err(RlnError::RootMismatch) => false,
+            Err(e) => return RlnStatus::from_error(&e, RlnStatus::VerificationError),
+        } {
+            unsafe { *result_ptr = 0 };
+        } else {
+            unsafe { *result_ptr = 1 };
+        };
+        RlnStatus::Ok
+    })
+}
+
+/// Same as `verify_with_root` taking the root as `root_hex_len` bytes of a hex string,
+/// see `public::root_from_hex`.
+#[no_mangle]
+pub extern "C" fn verify_with_root_hex(
+    ctx: *const RLN<Bn256>,
+    proof_buffer: *const Buffer,
+    root_hex_ptr: *const u8,
+    root_hex_len: usize,
+    result_ptr: *mut u32,
+) -> RlnStatus {
+    catch_status(|| {
+        if ctx.is_null() || proof_buffer.is_null() || root_hex_ptr.is_null() || result_ptr.is_null()
+        {
+            return RlnStatus::NullPointer;
+        }
+        let root_hex = <&[u8]>::from(&Buffer {
+            ptr: root_hex_ptr,
+            len: root_hex_len,
+        });
+        let root_data = match std::str::from_utf8(root_hex).map(public::root_from_hex) {
+            Ok(Ok(root_data)) => root_data,
+            _ => return RlnStatus::DeserializeError,
+        };
+        let root_buffer = Buffer::from(root_data.as_slice());
+        verify_with_root(ctx, proof_buffer, &root_buffer, result_ptr)
+    })
+}
+
+/// hashes `n_inputs` field elements to one
+/// * the output is framed as [ count<4> | element<32> * count ] with a little-endian count of one
+#[no_mangle]
+pub extern "C" fn hash(
+    ctx: *const RLN<Bn256>,
+    input_buffer: *const Buffer,
+    n_inputs: usize,
+    output_buffer: *mut Buffer,
+) -> RlnStatus {
+    hash_n(ctx, input_buffer, n_inputs, 1, output_buffer)
+}
+
+/// hashes `n_inputs` field elements to `n_outputs` with the poseidon sponge
+/// * the output is framed as [ count<4> | element<32> * count ] with a little-endian count
+#[no_mangle]
+pub extern "C" fn hash_n(
+    ctx: *const RLN<Bn256>,
+    input_buffer: *const Buffer,
+    n_inputs: usize,
+    n_outputs: usize,
+    output_buffer: *mut Buffer,
+) -> RlnStatus {
+    catch_status(|| {
+        if ctx.is_null() || input_buffer.is_null() || output_buffer.is_null() {
+            return RlnStatus::NullPointer;
+        }
+        let rln = unsafe { &*ctx };
+        let input_data = <&[u8]>::from(unsafe { &*input_buffer });
+        // the inputs must fit in the buffer
+        match n_inputs.checked_mul(FIELD_BYTES) {
+            Some(len) if len <= input_data.len() => {}
+            _ => return RlnStatus::DeserializeError,
+        }
+        let mut output_data: Vec<u8> = Vec::new();
+        if let Err(e) = rln.hash_n(input_data, n_inputs, n_outputs, &mut output_data) {
+            return RlnStatus::from_error(&e, RlnStatus::IoError);
+        }
+        let output_data = match frame_elements(n_outputs, output_data) {
+            Some(output_data) => output_data,
+            None => return RlnStatus::DeserializeError,
+        };
+        set_output_buffer(output_buffer, output_data);
+        RlnStatus::Ok
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn hash_message(
+    ctx: *const RLN<Bn256>,
+    input_buffer: *const Buffer,
+    output_buffer: *mut Buffer,
+) -> RlnStatus {
+    catch_status(|| {
+        if ctx.is_null() || input_buffer.is_null() || output_buffer.is_null() {
+            return RlnStatus::NullPointer;
+        }
+        let rln = unsafe { &*ctx };
+        let message = <&[u8]>::from(unsafe { &*input_buffer });
+        let mut output_data: Vec<u8> = Vec::new();
+        if let Err(e) = rln.hash_message(message, &mut output_data) {
+            return RlnStatus::from_error(&e, RlnStatus::IoError);
+        }
+        set_output_buffer(output_buffer, output_data);
+        RlnStatus::Ok
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn id_commitment(
+    ctx: *const RLN<Bn256>,
+    input_buffer: *const Buffer,
+    output_buffer: *mut Buffer,
+) -> RlnStatus {
+    catch_status(|| {
+        if ctx.is_null() || input_buffer.is_null() || output_buffer.is_null() {
+            return RlnStatus::NullPointer;
+        }
+        let rln = unsafe { &*ctx };
+        let id_key = <&[u8]>::from(unsafe { &*input_buffer });
+        let mut output_data: Vec<u8> = Vec::new();
+        if let Err(e) = rln.id_commitment(id_key, &mut output_data) {
+            return RlnStatus::from_error(&e, RlnStatus::IoError);
+        }
+        set_output_buffer(output_buffer, output_data);
+        RlnStatus::Ok
+    })
 }
 
 #[no_mangle]
@@ -131,30 +957,69 @@ pub extern "C" fn signal_to_field(
     inputs_buffer: *const Buffer,
     output_buffer: *mut Buffer,
 ) -> bool {
-    let rln = unsafe { &*ctx };
-    let input_data = <&[u8]>::from(unsafe { &*inputs_buffer });
-
-    let mut output_data: Vec<u8> = Vec::new();
-    match rln.signal_to_field(input_data, &mut output_data) {
-        Ok(output_data) => output_data,
-        Err(_) => return false,
-    };
-    unsafe { *output_buffer = Buffer::from(&output_data[..]) };
-    std::mem::forget(output_data);
-    true
+    catch_bool(|| {
+        if ctx.is_null() || inputs_buffer.is_null() || output_buffer.is_null() {
+            return false;
+        }
+        let rln = unsafe { &*ctx };
+        let input_data = <&[u8]>::from(unsafe { &*inputs_buffer });
+
+        let mut output_data: Vec<u8> = Vec::new();
+        match rln.signal_to_field(input_data, &mut output_data) {
+            Ok(output_data) => output_data,
+            Err(_) => return false,
+        };
+        set_output_buffer(output_buffer, output_data);
+        true
+    })
 }
 
 #[no_mangle]
 pub extern "C" fn key_gen(ctx: *const RLN<Bn256>, input_buffer: *mut Buffer) -> bool {
-    let rln = unsafe { &*ctx };
-    let mut output_data: Vec<u8> = Vec::new();
-    match rln.key_gen(&mut output_data) {
-        Ok(_) => (),
-        Err(_) => return false,
-    }
-    unsafe { *input_buffer = Buffer::from(&output_data[..]) };
-    std::mem::forget(output_data);
-    true
+    key_gen_impl(ctx, input_buffer)
+}
+
+#[no_mangle]
+pub extern "C" fn key_gen_bls12_381(ctx: *const RLN<Bls12>, input_buffer: *mut Buffer) -> bool {
+    key_gen_impl(ctx, input_buffer)
+}
+
+fn key_gen_impl<E: Engine>(ctx: *const RLN<E>, input_buffer: *mut Buffer) -> bool {
+    catch_bool(|| {
+        if ctx.is_null() || input_buffer.is_null() {
+            return false;
+        }
+        let rln = unsafe { &*ctx };
+        let mut output_data: Vec<u8> = Vec::new();
+        match rln.key_gen(&mut output_data) {
+            Ok(_) => (),
+            Err(_) => return false,
+        }
+        set_output_buffer(input_buffer, output_data);
+        true
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn key_gen_from_seed(
+    ctx: *const RLN<Bn256>,
+    seed_buffer: *const Buffer,
+    output_buffer: *mut Buffer,
+) -> RlnStatus {
+    catch_status(|| {
+        if ctx.is_null() || seed_buffer.is_null() || output_buffer.is_null() {
+            return RlnStatus::NullPointer;
+        }
+        let rln = unsafe { &*ctx };
+        let seed_data = <&[u8]>::from(unsafe { &*seed_buffer });
+        let mut output_data: Vec<u8> = Vec::new();
+        match rln.key_gen_from_seed(seed_data, &mut output_data) {
+            Ok(_) => (),
+            Err(e) => return RlnStatus::from_error(&e, RlnStatus::IoError),
+        }
+        set_output_buffer(output_buffer, output_data);
+        RlnStatus::Ok
+    })
 }
 
 use sapling_crypto::bellman::pairing::ff::{Field, PrimeField, PrimeFieldRepr};
@@ -164,10 +1029,11 @@ use std::io::{self, Read, Write};
 #[cfg(test)]
 mod tests {
     use crate::hash_to_field::hash_to_field;
+    use crate::merkle::MerkleTree;
     use crate::{circuit::bench, public::RLNSignal};
     use crate::{poseidon::PoseidonParams, public};
     use bellman::pairing::bn256::{Bn256, Fr};
-    use byteorder::{LittleEndian, WriteBytesExt};
+    use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
     use rand::{Rand, SeedableRng, XorShiftRng};
 
     use super::*;
@@ -181,6 +1047,10 @@ mod tests {
         13usize
     }
 
+    fn message_limit() -> u64 {
+        1u64
+    }
+
     fn rln_test() -> bench::RLNTest<Bn256> {
         let merkle_depth = merkle_depth();
         let poseidon_params = PoseidonParams::<Bn256>::new(8, 55, 3, None, None, None);
@@ -193,12 +1063,13 @@ mod tests {
         let merkle_depth = merkle_depth();
         let circuit_parameters_buffer = &Buffer::from(circuit_parameters.as_ref());
         let mut rln_pointer = MaybeUninit::<*mut RLN<Bn256>>::uninit();
-        let success = new_circuit_from_params(
+        let status = new_circuit_from_params(
             merkle_depth,
+            message_limit(),
             circuit_parameters_buffer,
             rln_pointer.as_mut_ptr(),
         );
-        assert!(success, "cannot init rln instance");
+        assert_eq!(RlnStatus::Ok, status, "cannot init rln instance");
 
         rln_pointer
     }
@@ -247,8 +1118,8 @@ mod tests {
             new_member.into_repr().write_le(&mut input_data).unwrap();
             let input_buffer = &Buffer::from(input_data.as_ref());
 
-            let success = update_next_member(rln_pointer, input_buffer);
-            assert!(success, "update with new pubkey call failed");
+            let status = update_next_member(rln_pointer, input_buffer);
+            assert_eq!(RlnStatus::Ok, status, "update with new pubkey call failed");
         }
 
         let mut gen_proof_and_verify = |rln_pointer: *const RLN<Bn256>, fail: bool| {
@@ -256,60 +1127,365 @@ mod tests {
             let epoch = Fr::rand(&mut rng);
             let signal = b"rln signal test xyz abc";
 
-            // serialize input
+            // serialize input
+            let mut input_data: Vec<u8> = Vec::new();
+            id_key.into_repr().write_le(&mut input_data).unwrap();
+            if fail {
+                input_data
+                    .write_u64::<LittleEndian>(index as u64 - 1)
+                    .unwrap();
+            } else {
+                input_data.write_u64::<LittleEndian>(index as u64).unwrap();
+            }
+            epoch.into_repr().write_le(&mut input_data).unwrap();
+            Fr::zero().into_repr().write_le(&mut input_data).unwrap();
+            input_data
+                .write_u64::<LittleEndian>(signal.len() as u64)
+                .unwrap();
+            input_data.write(&signal[..]).unwrap();
+
+            let input_buffer = &Buffer::from(input_data.as_ref());
+
+            // generate proof
+            let mut proof_buffer = MaybeUninit::<Buffer>::uninit();
+            let status = generate_proof(rln_pointer, input_buffer, proof_buffer.as_mut_ptr());
+            assert_eq!(RlnStatus::Ok, status, "proof generation call failed");
+            let proof_buffer = unsafe { proof_buffer.assume_init() };
+
+            let input_data = <&[u8]>::from(&proof_buffer);
+            let mut input_data = input_data.to_vec();
+            let mut proof_buffer = proof_buffer;
+            free_buffer(&mut proof_buffer);
+            assert!(proof_buffer.ptr.is_null());
+            assert_eq!(0, proof_buffer.len);
+            input_data
+                .write_u64::<LittleEndian>(signal.len() as u64)
+                .unwrap();
+
+            input_data.write(&signal[..]).unwrap();
+
+            // verify proof
+            let input_buffer = &Buffer::from(input_data.as_ref());
+            let mut result = 0u32;
+            let result_ptr = &mut result as *mut u32;
+            let status = verify(rln_pointer, input_buffer, result_ptr);
+            assert_eq!(RlnStatus::Ok, status, "verification call failed");
+            if fail {
+                assert_eq!(1, result);
+            } else {
+                assert_eq!(0, result);
+            }
+        };
+
+        gen_proof_and_verify(rln_pointer, false);
+        gen_proof_and_verify(rln_pointer, true);
+
+        // delete 0th member
+        let success = delete_member(rln_pointer, 0);
+        assert!(success, "deletion call failed");
+
+        gen_proof_and_verify(rln_pointer, false);
+        gen_proof_and_verify(rln_pointer, true);
+    }
+
+    #[test]
+    fn test_proof_bls12_381_ffi() {
+        use bellman::pairing::bls12_381::{Bls12, Fr};
+        let mut rng = XorShiftRng::from_seed([0x3dbe6258, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+
+        let poseidon_params = PoseidonParams::<Bls12>::new(8, 55, 3, None, None, None);
+        let rln_test = bench::RLNTest::<Bls12>::new(merkle_depth(), Some(poseidon_params));
+        let mut circuit_parameters: Vec<u8> = Vec::new();
+        rln_test
+            .export_circuit_parameters(&mut circuit_parameters)
+            .unwrap();
+        let circuit_parameters_buffer = &Buffer::from(circuit_parameters.as_ref());
+        let mut rln_pointer = MaybeUninit::<*mut RLN<Bls12>>::uninit();
+        let status = new_circuit_from_params_bls12_381(
+            merkle_depth(),
+            message_limit(),
+            circuit_parameters_buffer,
+            rln_pointer.as_mut_ptr(),
+        );
+        assert_eq!(RlnStatus::Ok, status, "cannot init rln instance");
+        let rln_pointer = unsafe { rln_pointer.assume_init() };
+
+        let mut keypair_buffer = MaybeUninit::<Buffer>::uninit();
+        let success = key_gen_bls12_381(rln_pointer, keypair_buffer.as_mut_ptr());
+        assert!(success, "key generation call failed");
+        let mut keypair_buffer = unsafe { keypair_buffer.assume_init() };
+        let keypair_data = <&[u8]>::from(&keypair_buffer).to_vec();
+        free_buffer(&mut keypair_buffer);
+        assert_eq!(64, keypair_data.len());
+        let mut buf = <Fr as PrimeField>::Repr::default();
+        buf.read_le(&keypair_data[..32]).unwrap();
+        let id_key = Fr::from_repr(buf).unwrap();
+
+        let index = 3;
+        for i in 0..index + 1 {
+            let input_data = if i == index {
+                keypair_data[32..].to_vec()
+            } else {
+                let mut input_data: Vec<u8> = Vec::new();
+                Fr::rand(&mut rng)
+                    .into_repr()
+                    .write_le(&mut input_data)
+                    .unwrap();
+                input_data
+            };
+            let input_buffer = &Buffer::from(input_data.as_ref());
+            let status = update_next_member_bls12_381(rln_pointer, input_buffer);
+            assert_eq!(RlnStatus::Ok, status, "update with new pubkey call failed");
+        }
+        let mut root_buffer = MaybeUninit::<Buffer>::uninit();
+        assert!(get_root_bls12_381(rln_pointer, root_buffer.as_mut_ptr()));
+        let mut root_buffer = unsafe { root_buffer.assume_init() };
+        assert_eq!(32, root_buffer.len);
+        free_buffer(&mut root_buffer);
+
+        let mut gen_proof_and_verify = |id_index: usize| -> u32 {
+            let signal = b"rln signal test xyz abc";
+            let mut input_data: Vec<u8> = Vec::new();
+            id_key.into_repr().write_le(&mut input_data).unwrap();
+            input_data
+                .write_u64::<LittleEndian>(id_index as u64)
+                .unwrap();
+            Fr::rand(&mut rng)
+                .into_repr()
+                .write_le(&mut input_data)
+                .unwrap();
+            Fr::zero().into_repr().write_le(&mut input_data).unwrap();
+            input_data
+                .write_u64::<LittleEndian>(signal.len() as u64)
+                .unwrap();
+            input_data.write_all(&signal[..]).unwrap();
+            let input_buffer = &Buffer::from(input_data.as_ref());
+
+            let mut proof_buffer = MaybeUninit::<Buffer>::uninit();
+            let status =
+                generate_proof_bls12_381(rln_pointer, input_buffer, proof_buffer.as_mut_ptr());
+            assert_eq!(RlnStatus::Ok, status, "proof generation call failed");
+            let mut proof_buffer = unsafe { proof_buffer.assume_init() };
+            let mut input_data = <&[u8]>::from(&proof_buffer).to_vec();
+            free_buffer(&mut proof_buffer);
+            assert_eq!(5 + 384 + 5 * 32, input_data.len());
+            input_data
+                .write_u64::<LittleEndian>(signal.len() as u64)
+                .unwrap();
+            input_data.write_all(&signal[..]).unwrap();
+
+            let input_buffer = &Buffer::from(input_data.as_ref());
+            let mut result = 0u32;
+            let status = verify_bls12_381(rln_pointer, input_buffer, &mut result as *mut u32);
+            assert_eq!(RlnStatus::Ok, status, "verification call failed");
+            result
+        };
+        assert_eq!(0, gen_proof_and_verify(index));
+        assert_eq!(1, gen_proof_and_verify(index - 1));
+
+        assert!(delete_member_bls12_381(rln_pointer, 0));
+        assert_eq!(0, gen_proof_and_verify(index));
+
+        free_rln_bls12_381(rln_pointer);
+    }
+
+    #[test]
+    fn test_free_rln_ffi() {
+        let rln_test = rln_test();
+        let mut circuit_parameters: Vec<u8> = Vec::new();
+        rln_test
+            .export_circuit_parameters(&mut circuit_parameters)
+            .unwrap();
+
+        for _ in 0..4 {
+            let rln_pointer = rln_pointer(circuit_parameters.clone());
+            let rln_pointer = unsafe { rln_pointer.assume_init() };
+            let mut result_buffer = MaybeUninit::<Buffer>::uninit();
+            let success = get_root(rln_pointer, result_buffer.as_mut_ptr());
+            assert!(success, "get root call failed");
+            free_rln(rln_pointer);
+        }
+
+        // freeing a null context is a no-op
+        free_rln(std::ptr::null_mut());
+    }
+
+    #[test]
+    fn test_get_root_ffi() {
+        let mut rng = XorShiftRng::from_seed([0x3dbe6258, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let rln_test = rln_test();
+        let mut circuit_parameters: Vec<u8> = Vec::new();
+        rln_test
+            .export_circuit_parameters(&mut circuit_parameters)
+            .unwrap();
+        let rln_pointer = rln_pointer(circuit_parameters);
+        let rln_pointer = unsafe { &mut *rln_pointer.assume_init() };
+
+        let mut expected_tree = MerkleTree::empty(rln_test.hasher(), merkle_depth());
+        for i in 0..5 {
+            let new_member = Fr::rand(&mut rng);
+            expected_tree.update(i, new_member).unwrap();
+            let mut input_data: Vec<u8> = Vec::new();
+            new_member.into_repr().write_le(&mut input_data).unwrap();
+            let input_buffer = &Buffer::from(input_data.as_ref());
+            let status = update_next_member(rln_pointer, input_buffer);
+            assert_eq!(RlnStatus::Ok, status, "update with new pubkey call failed");
+        }
+
+        let mut root_buffer = MaybeUninit::<Buffer>::uninit();
+        let success = get_root(rln_pointer, root_buffer.as_mut_ptr());
+        assert!(success, "get root call failed");
+        let mut root_buffer = unsafe { root_buffer.assume_init() };
+        let mut root_data = <&[u8]>::from(&root_buffer);
+
+        let mut buf = <Fr as PrimeField>::Repr::default();
+        buf.read_le(&mut root_data).unwrap();
+        assert_eq!(expected_tree.get_root(), Fr::from_repr(buf).unwrap());
+        free_buffer(&mut root_buffer);
+    }
+
+    #[test]
+    fn test_hash_framing_ffi() {
+        let mut rng = XorShiftRng::from_seed([0x3dbe6258, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let rln_test = rln_test();
+        let mut circuit_parameters: Vec<u8> = Vec::new();
+        rln_test
+            .export_circuit_parameters(&mut circuit_parameters)
+            .unwrap();
+        let rln_pointer = rln_pointer(circuit_parameters);
+        let rln_pointer = unsafe { &*rln_pointer.assume_init() };
+
+        let inputs: Vec<Fr> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+        let mut input_data: Vec<u8> = Vec::new();
+        for input in inputs.iter() {
+            input.into_repr().write_le(&mut input_data).unwrap();
+        }
+        let input_buffer = &Buffer::from(input_data.as_ref());
+        let hasher = rln_test.hasher();
+
+        let read_framed = |output_buffer: &Buffer| -> Vec<Fr> {
+            let mut output_data = <&[u8]>::from(output_buffer);
+            let count = output_data.read_u32::<LittleEndian>().unwrap() as usize;
+            let elements = read_fr::<_, Bn256>(&mut output_data, count).unwrap();
+            assert!(output_data.is_empty());
+            elements
+        };
+
+        let mut output_buffer = MaybeUninit::<Buffer>::uninit();
+        let status = hash(rln_pointer, input_buffer, 2, output_buffer.as_mut_ptr());
+        assert_eq!(RlnStatus::Ok, status);
+        let mut output_buffer = unsafe { output_buffer.assume_init() };
+        assert_eq!(
+            read_framed(&output_buffer),
+            vec![hasher.hash(inputs[..2].to_vec())]
+        );
+        free_buffer(&mut output_buffer);
+
+        let mut output_buffer = MaybeUninit::<Buffer>::uninit();
+        let status = hash_n(rln_pointer, input_buffer, 3, 4, output_buffer.as_mut_ptr());
+        assert_eq!(RlnStatus::Ok, status);
+        let mut output_buffer = unsafe { output_buffer.assume_init() };
+        assert_eq!(read_framed(&output_buffer), hasher.hash_n(inputs, 4));
+        free_buffer(&mut output_buffer);
+    }
+
+    #[test]
+    fn test_get_leaf_ffi() {
+        let mut rng = XorShiftRng::from_seed([0x3dbe6258, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let rln_test = rln_test();
+        let mut circuit_parameters: Vec<u8> = Vec::new();
+        rln_test
+            .export_circuit_parameters(&mut circuit_parameters)
+            .unwrap();
+        let rln_pointer = rln_pointer(circuit_parameters);
+        let rln_pointer = unsafe { &mut *rln_pointer.assume_init() };
+
+        let mut input_data: Vec<u8> = Vec::new();
+        Fr::rand(&mut rng)
+            .into_repr()
+            .write_le(&mut input_data)
+            .unwrap();
+        let input_buffer = &Buffer::from(input_data.as_ref());
+        let status = update_next_member(rln_pointer, input_buffer);
+        assert_eq!(RlnStatus::Ok, status);
+
+        let mut leaf_buffer = MaybeUninit::<Buffer>::uninit();
+        let status = get_leaf(rln_pointer, 0, leaf_buffer.as_mut_ptr());
+        assert_eq!(RlnStatus::Ok, status);
+        let mut leaf_buffer = unsafe { leaf_buffer.assume_init() };
+        assert_eq!(input_data.as_slice(), <&[u8]>::from(&leaf_buffer));
+        free_buffer(&mut leaf_buffer);
+
+        let mut leaf_buffer = MaybeUninit::<Buffer>::uninit();
+        let status = get_leaf(rln_pointer, 1, leaf_buffer.as_mut_ptr());
+        assert_eq!(RlnStatus::IoError, status);
+    }
+
+    #[test]
+    fn test_update_next_member_indexed_ffi() {
+        let mut rng = XorShiftRng::from_seed([0x3dbe6258, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let rln_test = rln_test();
+        let mut circuit_parameters: Vec<u8> = Vec::new();
+        rln_test
+            .export_circuit_parameters(&mut circuit_parameters)
+            .unwrap();
+        let rln_pointer = rln_pointer(circuit_parameters);
+        let rln_pointer = unsafe { &mut *rln_pointer.assume_init() };
+
+        for expected in 0..3 {
             let mut input_data: Vec<u8> = Vec::new();
-            id_key.into_repr().write_le(&mut input_data).unwrap();
-            if fail {
-                input_data
-                    .write_u64::<LittleEndian>(index as u64 - 1)
-                    .unwrap();
-            } else {
-                input_data.write_u64::<LittleEndian>(index as u64).unwrap();
-            }
-            epoch.into_repr().write_le(&mut input_data).unwrap();
-            input_data
-                .write_u64::<LittleEndian>(signal.len() as u64)
+            Fr::rand(&mut rng)
+                .into_repr()
+                .write_le(&mut input_data)
                 .unwrap();
-            input_data.write(&signal[..]).unwrap();
-
             let input_buffer = &Buffer::from(input_data.as_ref());
+            let mut index = usize::MAX;
+            let status = update_next_member_indexed(rln_pointer, input_buffer, &mut index);
+            assert_eq!(RlnStatus::Ok, status);
+            assert_eq!(expected, index);
+        }
 
-            // generate proof
-            let mut proof_buffer = MaybeUninit::<Buffer>::uninit();
-            let success = generate_proof(rln_pointer, input_buffer, proof_buffer.as_mut_ptr());
-            assert!(success, "proof generation call failed");
-            let proof_buffer = unsafe { proof_buffer.assume_init() };
-
-            let input_data = <&[u8]>::from(&proof_buffer);
-            let mut input_data = input_data.to_vec();
-            input_data
-                .write_u64::<LittleEndian>(signal.len() as u64)
-                .unwrap();
+        let mut len = 0usize;
+        assert_eq!(RlnStatus::Ok, tree_len(rln_pointer, &mut len));
+        assert_eq!(3, len);
+        let mut capacity = 0usize;
+        assert_eq!(RlnStatus::Ok, tree_capacity(rln_pointer, &mut capacity));
+        assert_eq!(1 << merkle_depth(), capacity);
+        let mut depth = 0usize;
+        assert_eq!(RlnStatus::Ok, get_merkle_depth(rln_pointer, &mut depth));
+        assert_eq!(merkle_depth(), depth);
+    }
 
-            input_data.write(&signal[..]).unwrap();
+    #[test]
+    fn test_status_codes_ffi() {
+        let rln_test = rln_test();
+        let mut circuit_parameters: Vec<u8> = Vec::new();
+        rln_test
+            .export_circuit_parameters(&mut circuit_parameters)
+            .unwrap();
 
-            // verify proof
-            let input_buffer = &Buffer::from(input_data.as_ref());
-            let mut result = 0u32;
-            let result_ptr = &mut result as *mut u32;
-            let success = verify(rln_pointer, input_buffer, result_ptr);
-            assert!(success, "verification call failed");
-            if fail {
-                assert_eq!(1, result);
-            } else {
-                assert_eq!(0, result);
-            }
-        };
+        // truncated parameters
+        let truncated_buffer = &Buffer::from(&circuit_parameters[..64]);
+        let mut truncated_pointer = MaybeUninit::<*mut RLN<Bn256>>::uninit();
+        let status = new_circuit_from_params(
+            merkle_depth(),
+            message_limit(),
+            truncated_buffer,
+            truncated_pointer.as_mut_ptr(),
+        );
+        assert_eq!(RlnStatus::DeserializeError, status);
 
-        gen_proof_and_verify(rln_pointer, false);
-        gen_proof_and_verify(rln_pointer, true);
+        let rln_pointer = rln_pointer(circuit_parameters);
+        let rln_pointer = unsafe { &mut *rln_pointer.assume_init() };
 
-        // delete 0th member
-        let success = delete_member(rln_pointer, 0);
-        assert!(success, "deletion call failed");
+        // a value above the field modulus is not a valid member
+        let input_data = vec![0xffu8; 32];
+        let input_buffer = &Buffer::from(input_data.as_ref());
+        let status = update_next_member(rln_pointer, input_buffer);
+        assert_eq!(RlnStatus::DeserializeError, status);
 
-        gen_proof_and_verify(rln_pointer, false);
-        gen_proof_and_verify(rln_pointer, true);
+        let status = update_next_member(std::ptr::null_mut(), input_buffer);
+        assert_eq!(RlnStatus::NullPointer, status);
     }
 
     #[test]
@@ -346,6 +1522,200 @@ mod tests {
         assert_eq!(expected_data.as_slice(), result_data);
     }
 
+    #[test]
+    fn test_verifier_ffi() {
+        let mut rng = XorShiftRng::from_seed([0x3dbe6258, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let mut rln_test = rln_test();
+        let mut circuit_parameters: Vec<u8> = Vec::new();
+        rln_test
+            .export_circuit_parameters(&mut circuit_parameters)
+            .unwrap();
+        let mut vk_data: Vec<u8> = Vec::new();
+        rln_test.export_verifying_key(&mut vk_data).unwrap();
+        assert!(vk_data.len() < circuit_parameters.len());
+
+        // vk exported over ffi matches
+        let rln_pointer = rln_pointer(circuit_parameters);
+        let rln_pointer = unsafe { rln_pointer.assume_init() };
+        let mut vk_buffer = MaybeUninit::<Buffer>::uninit();
+        let status = export_verifying_key(rln_pointer, vk_buffer.as_mut_ptr());
+        assert_eq!(RlnStatus::Ok, status);
+        let mut vk_buffer = unsafe { vk_buffer.assume_init() };
+        assert_eq!(vk_data.as_slice(), <&[u8]>::from(&vk_buffer));
+        free_buffer(&mut vk_buffer);
+        free_rln(rln_pointer);
+
+        let vk_buffer = &Buffer::from(vk_data.as_ref());
+        let mut verifier_pointer = MaybeUninit::<*mut RLNVerifier<Bn256>>::uninit();
+        let status = new_verifier_from_vk(vk_buffer, verifier_pointer.as_mut_ptr());
+        assert_eq!(RlnStatus::Ok, status);
+        let verifier_pointer = unsafe { verifier_pointer.assume_init() };
+
+        // prove with the full parameters
+        let id_key = Fr::rand(&mut rng);
+        let public_key: Fr = rln_test.hasher().hash(vec![id_key]);
+        let mut input_data: Vec<u8> = Vec::new();
+        public_key.into_repr().write_le(&mut input_data).unwrap();
+        rln_test
+            .rln
+            .update_next_member(input_data.as_slice())
+            .unwrap();
+
+        let epoch = Fr::rand(&mut rng);
+        let signal = b"rln signal test xyz abc";
+        let mut input_data: Vec<u8> = Vec::new();
+        id_key.into_repr().write_le(&mut input_data).unwrap();
+        input_data.write_u64::<LittleEndian>(1).unwrap();
+        epoch.into_repr().write_le(&mut input_data).unwrap();
+        Fr::zero().into_repr().write_le(&mut input_data).unwrap();
+        input_data
+            .write_u64::<LittleEndian>(signal.len() as u64)
+            .unwrap();
+        input_data.write_all(&signal[..]).unwrap();
+        let mut proof_data: Vec<u8> = Vec::new();
+        rln_test
+            .rln
+            .generate_proof(input_data.as_slice(), &mut proof_data)
+            .unwrap();
+        proof_data
+            .write_u64::<LittleEndian>(signal.len() as u64)
+            .unwrap();
+        proof_data.write_all(&signal[..]).unwrap();
+
+        let proof_buffer = &Buffer::from(proof_data.as_ref());
+        let mut result = 1u32;
+        let status = verifier_verify(verifier_pointer, proof_buffer, &mut result as *mut u32);
+        assert_eq!(RlnStatus::Ok, status);
+        assert_eq!(0, result);
+
+        // bad verifying key
+        let vk_buffer = &Buffer::from(&vk_data[..32]);
+        let mut bad_pointer = MaybeUninit::<*mut RLNVerifier<Bn256>>::uninit();
+        let status = new_verifier_from_vk(vk_buffer, bad_pointer.as_mut_ptr());
+        assert_eq!(RlnStatus::DeserializeError, status);
+
+        free_verifier(verifier_pointer);
+        free_verifier(std::ptr::null_mut());
+    }
+
+    #[test]
+    fn test_null_pointers_ffi() {
+        let rln_test = rln_test();
+        let mut circuit_parameters: Vec<u8> = Vec::new();
+        rln_test
+            .export_circuit_parameters(&mut circuit_parameters)
+            .unwrap();
+        let rln_pointer = rln_pointer(circuit_parameters);
+        let rln_pointer = unsafe { rln_pointer.assume_init() };
+
+        let null_rln = std::ptr::null_mut::<RLN<Bn256>>();
+        let null_buffer = std::ptr::null_mut::<Buffer>();
+        let data = vec![0u8; 32];
+        let input_buffer = &Buffer::from(data.as_ref());
+        let mut output_buffer = MaybeUninit::<Buffer>::uninit();
+        let mut result = 0u32;
+
+        assert!(!get_root(null_rln, output_buffer.as_mut_ptr()));
+        assert!(!get_root(rln_pointer, null_buffer));
+        assert!(!delete_member(null_rln, 0));
+        assert!(!signal_to_field(
+            null_rln,
+            input_buffer,
+            output_buffer.as_mut_ptr()
+        ));
+        assert!(!signal_to_field(
+            rln_pointer,
+            null_buffer,
+            output_buffer.as_mut_ptr()
+        ));
+        assert!(!key_gen(null_rln, output_buffer.as_mut_ptr()));
+        assert!(!key_gen(rln_pointer, null_buffer));
+
+        assert_eq!(
+            RlnStatus::NullPointer,
+            new_circuit_from_params(
+                merkle_depth(),
+                message_limit(),
+                null_buffer,
+                std::ptr::null_mut()
+            )
+        );
+        assert_eq!(
+            RlnStatus::NullPointer,
+            update_next_member(null_rln, input_buffer)
+        );
+        assert_eq!(
+            RlnStatus::NullPointer,
+            update_next_batch(rln_pointer, null_buffer, 1)
+        );
+        assert_eq!(
+            RlnStatus::NullPointer,
+            set_leaf(rln_pointer, 0, null_buffer)
+        );
+        assert_eq!(
+            RlnStatus::NullPointer,
+            generate_proof(rln_pointer, input_buffer, null_buffer)
+        );
+        assert_eq!(
+            RlnStatus::NullPointer,
+            verify(rln_pointer, input_buffer, std::ptr::null_mut())
+        );
+        assert_eq!(
+            RlnStatus::NullPointer,
+            verify_with_root(null_rln, input_buffer, input_buffer, &mut result)
+        );
+        assert_eq!(
+            RlnStatus::NullPointer,
+            key_gen_from_seed(rln_pointer, null_buffer, output_buffer.as_mut_ptr())
+        );
+        assert_eq!(
+            RlnStatus::NullPointer,
+            export_verifying_key(null_rln, output_buffer.as_mut_ptr())
+        );
+        assert_eq!(
+            RlnStatus::NullPointer,
+            verifier_verify(std::ptr::null(), input_buffer, &mut result)
+        );
+        free_buffer(null_buffer);
+
+        // corrupt buffers
+        let empty_buffer = &Buffer {
+            ptr: std::ptr::null(),
+            len: 32,
+        };
+        assert_eq!(
+            RlnStatus::DeserializeError,
+            update_next_member(rln_pointer, empty_buffer)
+        );
+        assert_eq!(
+            RlnStatus::DeserializeError,
+            generate_proof(rln_pointer, empty_buffer, output_buffer.as_mut_ptr())
+        );
+        let garbage = vec![0xabu8; 600];
+        let garbage_buffer = &Buffer::from(garbage.as_ref());
+        assert_eq!(
+            RlnStatus::DeserializeError,
+            verify(rln_pointer, garbage_buffer, &mut result)
+        );
+        let empty: Vec<u8> = Vec::new();
+        let empty_buffer = &Buffer::from(empty.as_ref());
+        assert_eq!(
+            RlnStatus::DeserializeError,
+            verify(rln_pointer, empty_buffer, &mut result)
+        );
+
+        free_rln(rln_pointer);
+    }
+
+    #[test]
+    fn test_catch_panic_ffi() {
+        assert_eq!(RlnStatus::Panic, catch_status(|| panic!("status")));
+        assert_eq!(RlnStatus::Ok, catch_status(|| RlnStatus::Ok));
+        assert!(!catch_bool(|| panic!("bool")));
+        assert!(catch_bool(|| true));
+        catch_unit(|| panic!("unit"));
+    }
+
     #[test]
     fn test_keygen_ffi() {
         let rln_test = rln_test();
@@ -385,11 +1755,373 @@ mod tests {
         let merkle_depth = merkle_depth();
         let circuit_parameters_buffer = &Buffer::from(data.as_ref());
         let mut rln_pointer = MaybeUninit::<*mut RLN<Bn256>>::uninit();
-        let success = new_circuit_from_params(
+        let status = new_circuit_from_params(
             merkle_depth,
+            message_limit(),
             circuit_parameters_buffer,
             rln_pointer.as_mut_ptr(),
         );
-        assert!(success, "creating failed");
+        assert_eq!(RlnStatus::Ok, status, "creating failed");
+    }
+
+    #[test]
+    fn test_empty_buffers() {
+        let empty: &[u8] = &[];
+        let buffer = Buffer::from(empty);
+        assert_eq!(0, buffer.len);
+        assert!(!buffer.ptr.is_null());
+        assert!(<&[u8]>::from(&buffer).is_empty());
+        assert!(<&[u8]>::from(&Buffer {
+            ptr: std::ptr::null(),
+            len: 0
+        })
+        .is_empty());
+
+        let rln_test = rln_test();
+        let mut circuit_parameters: Vec<u8> = Vec::new();
+        rln_test
+            .export_circuit_parameters(&mut circuit_parameters)
+            .unwrap();
+        let rln_pointer = rln_pointer(circuit_parameters);
+        let rln_pointer = unsafe { &*rln_pointer.assume_init() };
+
+        // a caller may pass any pointer with an empty buffer
+        let inputs = [
+            buffer,
+            Buffer {
+                ptr: std::ptr::null(),
+                len: 0,
+            },
+            Buffer {
+                ptr: 1 as *const u8,
+                len: 0,
+            },
+        ];
+        for input_buffer in inputs.iter() {
+            let mut output_buffer = MaybeUninit::<Buffer>::uninit();
+            let status = hash(rln_pointer, input_buffer, 1, output_buffer.as_mut_ptr());
+            assert_eq!(RlnStatus::DeserializeError, status);
+
+            let mut output_buffer = MaybeUninit::<Buffer>::uninit();
+            let status = generate_proof(rln_pointer, input_buffer, output_buffer.as_mut_ptr());
+            assert_eq!(RlnStatus::DeserializeError, status);
+        }
+    }
+
+    #[test]
+    fn test_new_circuit_from_file() {
+        let rln_test = rln_test();
+        let mut circuit_parameters: Vec<u8> = Vec::new();
+        rln_test
+            .export_circuit_parameters(&mut circuit_parameters)
+            .unwrap();
+        let path = std::env::temp_dir().join(format!("rln_params_{}", std::process::id()));
+        std::fs::write(&path, &circuit_parameters).unwrap();
+        let path = path.to_str().unwrap().to_owned();
+
+        let mut rln_pointer = MaybeUninit::<*mut RLN<Bn256>>::uninit();
+        let status = new_circuit_from_file(
+            merkle_depth(),
+            message_limit(),
+            path.as_ptr(),
+            path.len(),
+            rln_pointer.as_mut_ptr(),
+        );
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(RlnStatus::Ok, status);
+        let rln_pointer = unsafe { rln_pointer.assume_init() };
+        let mut result = 0usize;
+        assert_eq!(RlnStatus::Ok, get_merkle_depth(rln_pointer, &mut result));
+        assert_eq!(merkle_depth(), result);
+        free_rln(rln_pointer);
+
+        // the file is gone
+        let mut rln_pointer = MaybeUninit::<*mut RLN<Bn256>>::uninit();
+        let status = new_circuit_from_file(
+            merkle_depth(),
+            message_limit(),
+            path.as_ptr(),
+            path.len(),
+            rln_pointer.as_mut_ptr(),
+        );
+        assert_eq!(RlnStatus::IoError, status);
+    }
+
+    #[test]
+    fn test_verify_with_root_hex_ffi() {
+        let mut rng = XorShiftRng::from_seed([0x3dbe6258, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let rln_test = rln_test();
+        let mut circuit_parameters: Vec<u8> = Vec::new();
+        rln_test
+            .export_circuit_parameters(&mut circuit_parameters)
+            .unwrap();
+        let rln_pointer = rln_pointer(circuit_parameters);
+        let rln = unsafe { &mut *rln_pointer.assume_init() };
+
+        let id_key = Fr::rand(&mut rng);
+        let mut public_key: Vec<u8> = Vec::new();
+        rln.hasher()
+            .hash(vec![id_key])
+            .into_repr()
+            .write_le(&mut public_key)
+            .unwrap();
+        let id_index = rln.update_next_member(public_key.as_slice()).unwrap();
+
+        let signal = b"rln signal";
+        let mut input_data: Vec<u8> = Vec::new();
+        id_key.into_repr().write_le(&mut input_data).unwrap();
+        input_data
+            .write_u64::<LittleEndian>(id_index as u64)
+            .unwrap();
+        Fr::rand(&mut rng)
+            .into_repr()
+            .write_le(&mut input_data)
+            .unwrap();
+        Fr::zero().into_repr().write_le(&mut input_data).unwrap();
+        input_data
+            .write_u64::<LittleEndian>(signal.len() as u64)
+            .unwrap();
+        input_data.write_all(signal).unwrap();
+        let mut proof_data: Vec<u8> = Vec::new();
+        rln.generate_proof(input_data.as_slice(), &mut proof_data)
+            .unwrap();
+        proof_data
+            .write_u64::<LittleEndian>(signal.len() as u64)
+            .unwrap();
+        proof_data.write_all(signal).unwrap();
+        let proof_buffer = &Buffer::from(proof_data.as_ref());
+
+        let mut root_data: Vec<u8> = Vec::new();
+        rln.get_root(&mut root_data).unwrap();
+        let root_hex = hex::encode(&root_data);
+        let mut result = 1u32;
+        let status = verify_with_root_hex(
+            rln,
+            proof_buffer,
+            root_hex.as_ptr(),
+            root_hex.len(),
+            &mut result,
+        );
+        assert_eq!(RlnStatus::Ok, status);
+        assert_eq!(0, result);
+
+        // another root
+        let other_root_hex = hex::encode([0u8; 32]);
+        let status = verify_with_root_hex(
+            rln,
+            proof_buffer,
+            other_root_hex.as_ptr(),
+            other_root_hex.len(),
+            &mut result,
+        );
+        assert_eq!(RlnStatus::Ok, status);
+        assert_eq!(1, result);
+
+        for malformed in ["zz", "abc", &root_hex[..62]].iter() {
+            let status = verify_with_root_hex(
+                rln,
+                proof_buffer,
+                malformed.as_ptr(),
+                malformed.len(),
+                &mut result,
+            );
+            assert_eq!(RlnStatus::DeserializeError, status);
+        }
+    }
+
+    #[test]
+    fn test_hash_input_len_ffi() {
+        let rln_test = rln_test();
+        let mut circuit_parameters: Vec<u8> = Vec::new();
+        rln_test
+            .export_circuit_parameters(&mut circuit_parameters)
+            .unwrap();
+        let rln_pointer = rln_pointer(circuit_parameters);
+        let rln_pointer = unsafe { &*rln_pointer.assume_init() };
+
+        let input_data = vec![0u8; 2 * FIELD_BYTES];
+        let input_buffer = &Buffer::from(input_data.as_ref());
+        for n_inputs in [3, usize::MAX / 2, usize::MAX].iter() {
+            let mut output_buffer = MaybeUninit::<Buffer>::uninit();
+            let status = hash(
+                rln_pointer,
+                input_buffer,
+                *n_inputs,
+                output_buffer.as_mut_ptr(),
+            );
+            assert_eq!(RlnStatus::DeserializeError, status);
+        }
+
+        let mut output_buffer = MaybeUninit::<Buffer>::uninit();
+        let status = hash(rln_pointer, input_buffer, 2, output_buffer.as_mut_ptr());
+        assert_eq!(RlnStatus::Ok, status);
+        let mut output_buffer = unsafe { output_buffer.assume_init() };
+        free_buffer(&mut output_buffer);
+    }
+
+    #[test]
+    fn test_verify_split_ffi() {
+        let mut rng = XorShiftRng::from_seed([0x3dbe6258, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let rln_test = rln_test();
+        let mut circuit_parameters: Vec<u8> = Vec::new();
+        rln_test
+            .export_circuit_parameters(&mut circuit_parameters)
+            .unwrap();
+        let rln_pointer = rln_pointer(circuit_parameters);
+        let rln = unsafe { &mut *rln_pointer.assume_init() };
+
+        let id_key = Fr::rand(&mut rng);
+        let mut public_key: Vec<u8> = Vec::new();
+        rln.hasher()
+            .hash(vec![id_key])
+            .into_repr()
+            .write_le(&mut public_key)
+            .unwrap();
+        let id_index = rln.update_next_member(public_key.as_slice()).unwrap();
+
+        let signal = b"rln signal";
+        let mut input_data: Vec<u8> = Vec::new();
+        id_key.into_repr().write_le(&mut input_data).unwrap();
+        input_data
+            .write_u64::<LittleEndian>(id_index as u64)
+            .unwrap();
+        Fr::rand(&mut rng)
+            .into_repr()
+            .write_le(&mut input_data)
+            .unwrap();
+        Fr::zero().into_repr().write_le(&mut input_data).unwrap();
+        input_data
+            .write_u64::<LittleEndian>(signal.len() as u64)
+            .unwrap();
+        input_data.write_all(signal).unwrap();
+        let mut proof_data: Vec<u8> = Vec::new();
+        rln.generate_proof(input_data.as_slice(), &mut proof_data)
+            .unwrap();
+
+        // [ magic<4> | version<1> | proof<256> | public_inputs<160> ]
+        let proof_buffer = &Buffer::from(&proof_data[5..261]);
+        let mut public_inputs = proof_data[261..].to_vec();
+        let mut result = 1u32;
+        let status = verify_split(
+            rln,
+            proof_buffer,
+            &Buffer::from(public_inputs.as_ref()),
+            &mut result,
+        );
+        assert_eq!(RlnStatus::Ok, status);
+        assert_eq!(0, result);
+
+        // tampered nullifier
+        public_inputs[4 * FIELD_BYTES] ^= 1;
+        let status = verify_split(
+            rln,
+            proof_buffer,
+            &Buffer::from(public_inputs.as_ref()),
+            &mut result,
+        );
+        assert_eq!(RlnStatus::Ok, status);
+        assert_eq!(1, result);
+
+        let status = verify_split(
+            rln,
+            proof_buffer,
+            &Buffer::from(&public_inputs[..FIELD_BYTES]),
+            &mut result,
+        );
+        assert_eq!(RlnStatus::DeserializeError, status);
+    }
+
+    #[test]
+    fn test_verify_malformed_ffi() {
+        let mut rng = XorShiftRng::from_seed([0x3dbe6258, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
+        let rln_test = rln_test();
+        let mut circuit_parameters: Vec<u8> = Vec::new();
+        rln_test
+            .export_circuit_parameters(&mut circuit_parameters)
+            .unwrap();
+        let rln_pointer = rln_pointer(circuit_parameters);
+        let rln = unsafe { &mut *rln_pointer.assume_init() };
+
+        let id_key = Fr::rand(&mut rng);
+        let mut public_key: Vec<u8> = Vec::new();
+        rln.hasher()
+            .hash(vec![id_key])
+            .into_repr()
+            .write_le(&mut public_key)
+            .unwrap();
+        let id_index = rln.update_next_member(public_key.as_slice()).unwrap();
+
+        let signal = b"rln signal";
+        let mut input_data: Vec<u8> = Vec::new();
+        id_key.into_repr().write_le(&mut input_data).unwrap();
+        input_data
+            .write_u64::<LittleEndian>(id_index as u64)
+            .unwrap();
+        Fr::rand(&mut rng)
+            .into_repr()
+            .write_le(&mut input_data)
+            .unwrap();
+        Fr::zero().into_repr().write_le(&mut input_data).unwrap();
+        input_data
+            .write_u64::<LittleEndian>(signal.len() as u64)
+            .unwrap();
+        input_data.write_all(signal).unwrap();
+        let mut verify_data: Vec<u8> = Vec::new();
+        rln.generate_proof(input_data.as_slice(), &mut verify_data)
+            .unwrap();
+        verify_data
+            .write_u64::<LittleEndian>(signal.len() as u64)
+            .unwrap();
+        verify_data.write_all(signal).unwrap();
+
+        let mut result = 1u32;
+        let status = verify(rln, &Buffer::from(verify_data.as_ref()), &mut result);
+        assert_eq!(RlnStatus::Ok, status);
+        assert_eq!(0, result);
+
+        // tampered share_y
+        let mut tampered = verify_data.clone();
+        tampered[5 + 256 + 3 * FIELD_BYTES] ^= 1;
+        let status = verify(rln, &Buffer::from(tampered.as_ref()), &mut result);
+        assert_eq!(RlnStatus::Ok, status);
+        assert_eq!(1, result);
+
+        let mut result = 2u32;
+        let truncated = &Buffer::from(&verify_data[..verify_data.len() - 1]);
+        let status = verify(rln, truncated, &mut result);
+        assert_eq!(RlnStatus::DeserializeError, status);
+        assert_eq!(2, result);
+    }
+
+    #[test]
+    fn test_hash_output_count_ffi() {
+        let rln_test = rln_test();
+        let mut circuit_parameters: Vec<u8> = Vec::new();
+        rln_test
+            .export_circuit_parameters(&mut circuit_parameters)
+            .unwrap();
+        let rln_pointer = rln_pointer(circuit_parameters);
+        let rln_pointer = unsafe { &*rln_pointer.assume_init() };
+
+        let input_data = vec![0u8; 2 * FIELD_BYTES];
+        let input_buffer = &Buffer::from(input_data.as_ref());
+        for n_outputs in [public::MAX_HASH_OUTPUTS + 1, usize::MAX].iter() {
+            let mut output_buffer = MaybeUninit::<Buffer>::uninit();
+            let status = hash_n(
+                rln_pointer,
+                input_buffer,
+                2,
+                *n_outputs,
+                output_buffer.as_mut_ptr(),
+            );
+            assert_eq!(RlnStatus::DeserializeError, status);
+        }
+
+        let mut output_buffer = MaybeUninit::<Buffer>::uninit();
+        let status = hash_n(rln_pointer, input_buffer, 2, 3, output_buffer.as_mut_ptr());
+        assert_eq!(RlnStatus::Ok, status);
+        let mut output_buffer = unsafe { output_buffer.assume_init() };
+        assert_eq!(4 + 3 * FIELD_BYTES, <&[u8]>::from(&output_buffer).len());
+        free_buffer(&mut output_buffer);
     }
 }
@@ -4,6 +4,7 @@ use digest::{FixedOutput, VariableOutput, XofReader};
 use num_bigint::BigUint;
 use num_traits::{Num, One, Zero};
 use sha2::{Digest, Sha256};
+use sha3::Keccak256;
 use std::convert::TryInto;
 use std::str::EncodeUtf16;
 
@@ -34,6 +35,15 @@ pub fn hash_to_field<E: Engine>(data: &[u8]) -> <E as ScalarEngine>::Fr {
     big_to_fr::<E>(combined)
 }
 
+/// hashes a signal as evm contracts do, keccak256 of the signal reduced modulo the field
+/// * the digest is read as a big endian integer, as `uint256(keccak256(signal)) % SNARK_SCALAR_FIELD` in solidity
+/// * the result is meant as the external signal hash, the share x of a proof,
+/// it is unrelated to the poseidon hashes computed in the circuit
+pub fn signal_hash_keccak<E: Engine>(message: &[u8]) -> E::Fr {
+    let digest = Keccak256::digest(message);
+    big_to_fr::<E>(BigUint::from_bytes_be(&digest[..]))
+}
+
 fn big_modulus<E: Engine>() -> BigUint {
     let modulus = E::Fr::char();
     let mut buf: Vec<u8> = Vec::new();
@@ -2,14 +2,16 @@
 #![allow(unused_imports)]
 
 pub mod circuit;
+pub mod error;
 mod hash_to_field;
 pub mod merkle;
 pub mod poseidon;
 pub mod public;
+pub mod secret;
 mod utils;
 
 #[cfg(not(target_arch = "wasm32"))]
 pub mod ffi;
 
-#[cfg(target_arch = "wasm32")]
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
 mod wasm;
@@ -1,8 +1,9 @@
 use crate::poseidon::{Poseidon as Hasher, PoseidonParams};
 use sapling_crypto::bellman::pairing::ff::{Field, PrimeField, PrimeFieldRepr};
 use sapling_crypto::bellman::pairing::Engine;
+use std::collections::{BTreeSet, HashMap};
+use std::hash::Hash;
 use std::io::{self, Error, ErrorKind};
-use std::{collections::HashMap, hash::Hash};
 
 enum SyncMode {
     Bootstarp,
@@ -15,6 +16,9 @@ where
 {
     pub current_index: usize,
     merkle_tree: MerkleTree<E>,
+    // indices of each leaf other than the empty leaf, keyed by the leaf representation
+    // * a leaf may be written at several indices, removing one keeps the others
+    leaf_indices: HashMap<Vec<u64>, BTreeSet<usize>>,
 }
 
 impl<E> IncrementalMerkleTree<E>
@@ -22,33 +26,101 @@ where
     E: Engine,
 {
     pub fn empty(hasher: Hasher<E>, depth: usize) -> Self {
-        let mut zero: Vec<E::Fr> = Vec::with_capacity(depth + 1);
-        zero.push(E::Fr::from_str("0").unwrap());
-        for i in 0..depth {
-            zero.push(hasher.hash([zero[i]; 2].to_vec()));
-        }
-        zero.reverse();
-        let merkle_tree = MerkleTree {
-            hasher: hasher,
-            zero: zero.clone(),
-            depth: depth,
-            nodes: HashMap::new(),
-        };
-        let current_index: usize = 0;
         IncrementalMerkleTree {
-            current_index,
-            merkle_tree,
+            current_index: 0,
+            merkle_tree: MerkleTree::empty(hasher, depth),
+            leaf_indices: HashMap::new(),
+        }
+    }
+
+    fn leaf_key(leaf: &E::Fr) -> Vec<u64> {
+        leaf.into_repr().as_ref().to_vec()
+    }
+
+    // keeps `leaf_indices` in sync before `leaf` is written at `index`
+    fn index_leaf(&mut self, index: usize, leaf: E::Fr) {
+        let old_key = Self::leaf_key(&self.merkle_tree.get_leaf(index));
+        if let Some(indices) = self.leaf_indices.get_mut(&old_key) {
+            indices.remove(&index);
+            if indices.is_empty() {
+                self.leaf_indices.remove(&old_key);
+            }
+        }
+        if !leaf.is_zero() {
+            self.leaf_indices
+                .entry(Self::leaf_key(&leaf))
+                .or_insert_with(BTreeSet::new)
+                .insert(index);
         }
     }
 
+    /// lowest index of a leaf equal to `leaf`, the empty leaf is never found
+    pub fn index_of(&self, leaf: &E::Fr) -> Option<usize> {
+        self.leaf_indices
+            .get(&Self::leaf_key(leaf))
+            .and_then(|indices| indices.iter().next().copied())
+    }
+
     pub fn update_next(&mut self, leaf: E::Fr) -> io::Result<()> {
+        if self.current_index >= self.merkle_tree.set_size() {
+            return Err(full());
+        }
+        self.index_leaf(self.current_index, leaf);
         self.merkle_tree.update(self.current_index, leaf)?;
         self.current_index += 1;
         Ok(())
     }
 
+    pub fn update_next_batch(&mut self, leaves: Vec<E::Fr>) -> io::Result<()> {
+        let n = leaves.len();
+        if self.current_index + n > self.merkle_tree.set_size() {
+            return Err(full());
+        }
+        for (i, leaf) in leaves.iter().enumerate() {
+            self.index_leaf(self.current_index + i, *leaf);
+        }
+        self.merkle_tree.update_range(self.current_index, leaves)?;
+        self.current_index += n;
+        Ok(())
+    }
+
+    /// number of leaves the tree can hold
+    pub fn capacity(&self) -> usize {
+        self.merkle_tree.set_size()
+    }
+
+    /// sets the leaf at `index`, the cursor is moved after `index` if it is behind
+    pub fn set(&mut self, index: usize, leaf: E::Fr) -> io::Result<()> {
+        self.set_batch(vec![(index, leaf)])
+    }
+
+    /// sets each leaf at its index, ancestors shared by the leaves are calculated only once
+    /// * nothing is set if an index exceeds the set size
+    /// * the cursor is moved after the highest index if it is behind
+    pub fn set_batch(&mut self, leaves: Vec<(usize, E::Fr)>) -> io::Result<()> {
+        let set_size = self.merkle_tree.set_size();
+        if leaves.iter().any(|(index, _)| *index >= set_size) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "index exceeds set size",
+            ));
+        }
+        for (index, leaf) in leaves.into_iter() {
+            self.index_leaf(index, leaf);
+            self.merkle_tree.set_leaf(index, leaf)?;
+            if index >= self.current_index {
+                self.current_index = index + 1;
+            }
+        }
+        self.merkle_tree.flush_root();
+        Ok(())
+    }
+
     pub fn delete(&mut self, index: usize) -> io::Result<()> {
-        let zero = E::Fr::from_str("0").unwrap();
+        let zero = self.empty_leaf();
+        if index < self.merkle_tree.set_size() {
+            self.index_leaf(index, zero);
+        }
         self.merkle_tree.update(index, zero)?;
         Ok(())
     }
@@ -84,16 +156,111 @@ where
     pub fn get_root(&self) -> E::Fr {
         return self.merkle_tree.get_root();
     }
+
+    pub fn depth(&self) -> usize {
+        self.merkle_tree.depth
+    }
+
+    pub fn arity(&self) -> usize {
+        self.merkle_tree.arity
+    }
+
+    pub fn empty_leaf(&self) -> E::Fr {
+        self.merkle_tree.empty_leaf()
+    }
+
+    /// returns the leaf at `index`, fails if the slot is out of range or never written
+    pub fn get_leaf(&self, index: usize) -> io::Result<E::Fr> {
+        if index >= self.merkle_tree.set_size() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "index exceeds set size",
+            ));
+        }
+        if !self.merkle_tree.has_leaf(index) {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("leaf at index {} is not set", index),
+            ));
+        }
+        Ok(self.merkle_tree.get_leaf(index))
+    }
+
+    /// returns the leaves up to the insertion cursor, deleted leaves are the empty leaf
+    pub fn leaves(&self) -> Vec<E::Fr> {
+        (0..self.current_index)
+            .map(|i| self.merkle_tree.get_leaf(i))
+            .collect()
+    }
+}
+
+fn full() -> io::Error {
+    io::Error::new(io::ErrorKind::Other, "membership tree is full")
 }
 
+/// checks that `leaf` together with `witness` as returned by `get_witness` ascends to `root`
+/// * the arity of the tree is `hasher.width() - 1`, a witness of another shape does not verify
+pub fn verify_witness<E: Engine>(
+    hasher: &Hasher<E>,
+    root: E::Fr,
+    leaf: E::Fr,
+    witness: &[(E::Fr, bool)],
+) -> bool {
+    match ascend(hasher, leaf, witness) {
+        Some(acc) => acc.eq(&root),
+        None => false,
+    }
+}
+
+// hashes `leaf` up the levels of `witness`, each level holds the `arity - 1` siblings of the node
+// in order, flagged if they are right of the node, so that no flagged sibling precedes an unflagged one
+fn ascend<E: Engine>(hasher: &Hasher<E>, leaf: E::Fr, witness: &[(E::Fr, bool)]) -> Option<E::Fr> {
+    let siblings = hasher.width() - 2;
+    if siblings == 0 || witness.len() % siblings != 0 {
+        return None;
+    }
+    let mut acc = leaf;
+    for level in witness.chunks(siblings) {
+        if level.windows(2).any(|w| w[0].1 && !w[1].1) {
+            return None;
+        }
+        let mut children: Vec<E::Fr> = level.iter().filter(|w| !w.1).map(|w| w.0).collect();
+        children.push(acc);
+        children.extend(level.iter().filter(|w| w.1).map(|w| w.0));
+        acc = hasher.hash(children);
+    }
+    Some(acc)
+}
+
+/// sparse poseidon merkle tree, hashing as the membership tree of the circuit does
+/// * can compute roots and authentication paths of a member set without proving parameters
+/// * each node hashes `arity = width - 1` children in a single permutation of the poseidon width,
+/// so a width 3 poseidon gives a binary tree and a width 5 one a tree of arity 4
 pub struct MerkleTree<E>
 where
     E: Engine,
 {
     pub hasher: Hasher<E>,
     pub depth: usize,
+    pub arity: usize,
     zero: Vec<E::Fr>,
     nodes: HashMap<(usize, usize), E::Fr>,
+    // one past the highest written leaf
+    next_index: usize,
+    // leaves set with `set_leaf` whose paths are not stored yet
+    dirty: BTreeSet<usize>,
+}
+
+#[cfg(test)]
+thread_local! {
+    // parent nodes hashed on this thread, kept off the tree so the layout is the same in tests
+    static HASH_COUNT: std::cell::Cell<usize> = std::cell::Cell::new(0);
+}
+
+/// number of parent nodes hashed by merkle trees on the current thread
+#[cfg(test)]
+pub(crate) fn hash_count() -> usize {
+    HASH_COUNT.with(|count| count.get())
 }
 
 impl<E> MerkleTree<E>
@@ -101,25 +268,61 @@ where
     E: Engine,
 {
     pub fn empty(hasher: Hasher<E>, depth: usize) -> Self {
+        let arity = hasher.width() - 1;
+        assert!(arity >= 2, "poseidon width must be at least 3");
         let mut zero: Vec<E::Fr> = Vec::with_capacity(depth + 1);
         zero.push(E::Fr::from_str("0").unwrap());
         for i in 0..depth {
-            zero.push(hasher.hash([zero[i]; 2].to_vec()));
+            zero.push(hasher.hash(vec![zero[i]; arity]));
         }
         zero.reverse();
         MerkleTree {
             hasher: hasher,
             zero: zero.clone(),
             depth: depth,
+            arity,
             nodes: HashMap::new(),
+            next_index: 0,
+            dirty: BTreeSet::new(),
         }
     }
 
+    /// empty tree of `depth` hashing with `poseidon_params`
+    pub fn new(depth: usize, poseidon_params: PoseidonParams<E>) -> Self {
+        Self::empty(Hasher::new(poseidon_params), depth)
+    }
+
+    /// `arity ^ depth`, saturating at `usize::MAX` for trees too large to address in full
     pub fn set_size(&self) -> usize {
-        1 << self.depth
+        self.arity
+            .checked_pow(self.depth as u32)
+            .unwrap_or(usize::MAX)
     }
 
-    pub fn update(&mut self, index: usize, leaf: E::Fr) -> io::Result<()> {
+    /// sets the leaf after the highest written one and returns its index
+    pub fn insert(&mut self, leaf: E::Fr) -> io::Result<usize> {
+        let index = self.next_index;
+        if index >= self.set_size() {
+            return Err(full());
+        }
+        self.update(index, leaf)?;
+        Ok(index)
+    }
+
+    pub fn root(&self) -> E::Fr {
+        self.get_root()
+    }
+
+    /// authentication path of the leaf at `index`, see `get_witness`
+    pub fn proof(&self, index: usize) -> io::Result<Vec<(E::Fr, bool)>> {
+        self.get_witness(index)
+    }
+
+    /// sets the leaf at `index` without storing its path
+    /// * paths are stored by `flush_root`, so nearby leaves share the hashing of their common ancestors
+    /// * until then `get_root`, `get_witness` and `check_inclusion` see the leaf
+    ///   but recalculate the pending nodes on every call
+    pub fn set_leaf(&mut self, index: usize, leaf: E::Fr) -> io::Result<()> {
         if index >= self.set_size() {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidInput,
@@ -127,33 +330,96 @@ where
             ));
         }
         self.nodes.insert((self.depth, index), leaf);
-        self.recalculate_from(index);
+        self.next_index = self.next_index.max(index + 1);
+        self.dirty.insert(index);
         Ok(())
     }
 
-    pub fn check_inclusion(&self, witness: Vec<(E::Fr, bool)>, index: usize) -> io::Result<bool> {
+    /// stores the paths of the leaves set with `set_leaf` and returns the root,
+    /// each dirty node is calculated only once
+    pub fn flush_root(&mut self) -> E::Fr {
+        let mut dirty = std::mem::replace(&mut self.dirty, BTreeSet::new());
+        let mut depth = self.depth;
+        while depth > 0 && !dirty.is_empty() {
+            dirty = dirty.into_iter().map(|i| i / self.arity).collect();
+            for i in dirty.iter() {
+                let h = self.hash_children(depth, i * self.arity);
+                self.nodes.insert((depth - 1, *i), h);
+            }
+            depth -= 1;
+        }
+        self.get_root()
+    }
+
+    pub fn update(&mut self, index: usize, leaf: E::Fr) -> io::Result<()> {
         if index >= self.set_size() {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidInput,
                 "index exceeds set size",
             ));
         }
-        let mut acc = self.get_node(self.depth, index);
+        self.flush_root();
+        self.nodes.insert((self.depth, index), leaf);
+        self.next_index = self.next_index.max(index + 1);
+        self.recalculate_from(index);
+        Ok(())
+    }
 
-        for w in witness.into_iter() {
-            if w.1 {
-                acc = self.hasher.hash(vec![acc, w.0]);
-            } else {
-                acc = self.hasher.hash(vec![w.0, acc]);
+    /// sets consecutive leaves starting from `index`,
+    /// each affected parent node is calculated only once
+    pub fn update_range(&mut self, index: usize, leaves: Vec<E::Fr>) -> io::Result<()> {
+        if leaves.is_empty() {
+            return Ok(());
+        }
+        let mut last = index + leaves.len() - 1;
+        if last >= self.set_size() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "index exceeds set size",
+            ));
+        }
+        self.flush_root();
+        for (i, leaf) in leaves.into_iter().enumerate() {
+            self.nodes.insert((self.depth, index + i), leaf);
+        }
+        self.next_index = self.next_index.max(last + 1);
+        let mut first = index;
+        let mut depth = self.depth;
+        while depth > 0 {
+            first /= self.arity;
+            last /= self.arity;
+            for i in first..=last {
+                let h = self.hash_children(depth, i * self.arity);
+                self.nodes.insert((depth - 1, i), h);
             }
+            depth -= 1;
+        }
+        Ok(())
+    }
+
+    pub fn check_inclusion(&self, witness: Vec<(E::Fr, bool)>, index: usize) -> io::Result<bool> {
+        if index >= self.set_size() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "index exceeds set size",
+            ));
         }
-        Ok(acc.eq(&self.get_root()))
+        let leaf = self.get_node(self.depth, index);
+        Ok(verify_witness(
+            &self.hasher,
+            self.get_root(),
+            leaf,
+            &witness,
+        ))
     }
 
     pub fn get_root(&self) -> E::Fr {
         return self.get_node(0, 0);
     }
 
+    /// authentication path of the leaf at `index`, from the leaves up
+    /// * each level holds the `arity - 1` siblings of the node on the path in order,
+    /// flagged if they are right of it, a binary tree has one sibling per level
     pub fn get_witness(&self, index: usize) -> io::Result<Vec<(E::Fr, bool)>> {
         if index >= self.set_size() {
             return Err(io::Error::new(
@@ -161,23 +427,73 @@ where
                 "index exceeds set size",
             ));
         }
-        let mut witness = Vec::<(E::Fr, bool)>::with_capacity(self.depth);
+        #[cfg(feature = "parallel")]
+        let witness = self.parallel_witness(index);
+        #[cfg(not(feature = "parallel"))]
+        let witness = self.sequential_witness(index);
+        Ok(witness)
+    }
+
+    // the positions of the sibling nodes on the path of the leaf at `index`, from the leaves up,
+    // each flagged if it is right of the node on the path
+    fn siblings(&self, index: usize) -> Vec<(usize, usize, bool)> {
+        let mut siblings = Vec::with_capacity(self.depth * (self.arity - 1));
         let mut i = index;
         let mut depth = self.depth;
-        loop {
-            i ^= 1;
-            witness.push((self.get_node(depth, i), (i & 1 == 1)));
-            i >>= 1;
-            depth -= 1;
-            if depth == 0 {
-                break;
+        // a tree of depth 0 is its only leaf and has an empty path
+        while depth > 0 {
+            let position = i % self.arity;
+            let first = i - position;
+            for j in (0..self.arity).filter(|j| *j != position) {
+                siblings.push((depth, first + j, j > position));
             }
+            i /= self.arity;
+            depth -= 1;
         }
         assert_eq!(i, 0);
-        Ok(witness)
+        siblings
+    }
+
+    // authentication path of the leaf at `index`, which must be in range
+    #[cfg_attr(all(feature = "parallel", not(test)), allow(dead_code))]
+    pub(crate) fn sequential_witness(&self, index: usize) -> Vec<(E::Fr, bool)> {
+        self.siblings(index)
+            .into_iter()
+            .map(|(depth, i, right)| (self.get_node(depth, i), right))
+            .collect()
+    }
+
+    // same as `sequential_witness`, siblings above pending leaves do not depend on each other
+    // and are hashed on the rayon thread pool, as are the two halves of each of their subtrees
+    #[cfg(feature = "parallel")]
+    pub(crate) fn parallel_witness(&self, index: usize) -> Vec<(E::Fr, bool)> {
+        use rayon::prelude::*;
+        self.siblings(index)
+            .into_par_iter()
+            .map(|(depth, i, right)| (self.get_node_parallel(depth, i), right))
+            .collect()
+    }
+
+    #[cfg(feature = "parallel")]
+    fn get_node_parallel(&self, depth: usize, index: usize) -> E::Fr {
+        use rayon::prelude::*;
+        if depth < self.depth && self.has_dirty_leaf(depth, index) {
+            let first = index * self.arity;
+            let children: Vec<E::Fr> = (first..first + self.arity)
+                .into_par_iter()
+                .map(|i| self.get_node_parallel(depth + 1, i))
+                .collect();
+            #[cfg(test)]
+            HASH_COUNT.with(|count| count.set(count.get() + 1));
+            return self.hasher.hash(children);
+        }
+        self.get_node(depth, index)
     }
 
     fn get_node(&self, depth: usize, index: usize) -> E::Fr {
+        if depth < self.depth && self.has_dirty_leaf(depth, index) {
+            return self.hash_children(depth + 1, index * self.arity);
+        }
         let node = *self
             .nodes
             .get(&(depth, index))
@@ -185,29 +501,50 @@ where
         node
     }
 
-    fn get_leaf(&self, index: usize) -> E::Fr {
+    pub fn get_leaf(&self, index: usize) -> E::Fr {
         self.get_node(self.depth, index)
     }
 
-    fn hash_couple(&mut self, depth: usize, index: usize) -> E::Fr {
-        let b = index & !1;
-        self.hasher
-            .hash([self.get_node(depth, b), self.get_node(depth, b + 1)].to_vec())
+    /// the value of leaves never set, zero
+    pub fn empty_leaf(&self) -> E::Fr {
+        self.zero[self.depth]
+    }
+
+    /// whether the leaf at `index` has been written, including deleted leaves
+    pub fn has_leaf(&self, index: usize) -> bool {
+        self.nodes.contains_key(&(self.depth, index))
+    }
+
+    // whether a leaf under the node is set with `set_leaf` and not flushed yet
+    fn has_dirty_leaf(&self, depth: usize, index: usize) -> bool {
+        let leaves = self.arity.saturating_pow((self.depth - depth) as u32);
+        self.dirty
+            .range(index.saturating_mul(leaves)..(index + 1).saturating_mul(leaves))
+            .next()
+            .is_some()
+    }
+
+    // hashes the children of the parent of the node at `index`
+    fn hash_children(&self, depth: usize, index: usize) -> E::Fr {
+        #[cfg(test)]
+        HASH_COUNT.with(|count| count.set(count.get() + 1));
+        let first = index - index % self.arity;
+        self.hasher.hash(
+            (first..first + self.arity)
+                .map(|i| self.get_node(depth, i))
+                .collect(),
+        )
     }
 
     fn recalculate_from(&mut self, index: usize) {
         let mut i = index;
         let mut depth = self.depth;
-        loop {
-            let h = self.hash_couple(depth, i);
-            i >>= 1;
+        while depth > 0 {
+            let h = self.hash_children(depth, i);
+            i /= self.arity;
             depth -= 1;
             self.nodes.insert((depth, i), h);
-            if depth == 0 {
-                break;
-            }
         }
-        assert_eq!(depth, 0);
         assert_eq!(i, 0);
     }
 }
@@ -227,3 +564,146 @@ fn test_merkle_set() {
     let witness = set.get_witness(leaf_index).unwrap();
     assert!(set.check_inclusion(witness, leaf_index).unwrap());
 }
+
+#[test]
+fn test_merkle_batch_update() {
+    use sapling_crypto::bellman::pairing::bn256::{Bn256, Fr};
+    let params = PoseidonParams::<Bn256>::new(8, 55, 3, None, None, None);
+    let hasher = Hasher::new(params);
+    let leaves: Vec<Fr> = (0..100)
+        .map(|s| Fr::from_str(&format!("{}", s + 1)).unwrap())
+        .collect();
+
+    let mut tree_0 = IncrementalMerkleTree::empty(hasher.clone(), 7);
+    tree_0.update_next(leaves[0]).unwrap();
+    let before = hash_count();
+    for leaf in leaves[1..].iter() {
+        tree_0.update_next(*leaf).unwrap();
+    }
+    let sequential_hashes = hash_count() - before;
+
+    let mut tree_1 = IncrementalMerkleTree::empty(hasher.clone(), 7);
+    tree_1.update_next(leaves[0]).unwrap();
+    let before = hash_count();
+    tree_1.update_next_batch(leaves[1..].to_vec()).unwrap();
+    let batch_hashes = hash_count() - before;
+
+    assert_eq!(tree_0.get_root(), tree_1.get_root());
+    assert_eq!(tree_0.current_index, tree_1.current_index);
+    // each insertion hashes its whole path, a batch hashes each affected node once
+    assert_eq!(99 * 7, sequential_hashes);
+    assert!(batch_hashes * 5 < sequential_hashes);
+
+    // a batch may not overflow the tree
+    assert!(tree_1.update_next_batch(leaves[..29].to_vec()).is_err());
+}
+
+#[test]
+fn test_merkle_lazy_root() {
+    use sapling_crypto::bellman::pairing::bn256::{Bn256, Fr};
+    let params = PoseidonParams::<Bn256>::new(8, 55, 3, None, None, None);
+    let hasher = Hasher::new(params);
+    let leaves: Vec<Fr> = (0..50)
+        .map(|s| Fr::from_str(&format!("{}", s + 1)).unwrap())
+        .collect();
+
+    let mut eager = MerkleTree::empty(hasher.clone(), 10);
+    let mut lazy = MerkleTree::empty(hasher.clone(), 10);
+    let before = hash_count();
+    for (i, leaf) in leaves.iter().enumerate() {
+        eager.update(100 + i, *leaf).unwrap();
+    }
+    let eager_hashes = hash_count() - before;
+    assert_eq!(50 * 10, eager_hashes);
+    let before = hash_count();
+    for (i, leaf) in leaves.iter().enumerate() {
+        lazy.set_leaf(100 + i, *leaf).unwrap();
+    }
+    assert_eq!(before, hash_count());
+
+    // getters see pending leaves before a flush
+    assert_eq!(eager.get_root(), lazy.get_root());
+    let witness = lazy.get_witness(120).unwrap();
+    assert_eq!(eager.get_witness(120).unwrap(), witness);
+    assert!(lazy.check_inclusion(witness, 120).unwrap());
+
+    let before = hash_count();
+    assert_eq!(eager.get_root(), lazy.flush_root());
+    assert!((hash_count() - before) * 5 < eager_hashes);
+    // flushed paths are stored
+    let before = hash_count();
+    assert_eq!(eager.root(), lazy.root());
+    assert_eq!(eager.proof(120).unwrap(), lazy.proof(120).unwrap());
+    assert_eq!(before, hash_count());
+
+    // an eager update sees pending leaves
+    lazy.set_leaf(7, leaves[0]).unwrap();
+    eager.update(7, leaves[0]).unwrap();
+    lazy.update(8, leaves[1]).unwrap();
+    eager.update(8, leaves[1]).unwrap();
+    assert_eq!(eager.get_root(), lazy.get_root());
+    assert!(lazy.set_leaf(1 << 10, leaves[0]).is_err());
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn test_merkle_parallel_witness() {
+    use sapling_crypto::bellman::pairing::bn256::{Bn256, Fr};
+    let params = PoseidonParams::<Bn256>::new(8, 55, 3, None, None, None);
+    let mut tree = MerkleTree::empty(Hasher::new(params), 10);
+    for i in 0..50 {
+        let leaf = Fr::from_str(&format!("{}", i + 1)).unwrap();
+        tree.set_leaf(i * 20, leaf).unwrap();
+    }
+    // siblings are hashed from pending leaves, then read from stored nodes
+    for _ in 0..2 {
+        for index in [0usize, 120, 121, 999, 1023].iter() {
+            let witness = tree.parallel_witness(*index);
+            assert_eq!(tree.sequential_witness(*index), witness);
+            assert!(tree.check_inclusion(witness, *index).unwrap());
+        }
+        tree.flush_root();
+    }
+}
+
+#[test]
+fn test_merkle_arity() {
+    use sapling_crypto::bellman::pairing::bn256::{Bn256, Fr};
+    let params = PoseidonParams::<Bn256>::new(8, 55, 5, None, None, None);
+    let hasher = Hasher::new(params);
+    let leaves: Vec<Fr> = (0..16)
+        .map(|s| Fr::from_str(&format!("{}", s + 1)).unwrap())
+        .collect();
+
+    let mut tree = MerkleTree::empty(hasher.clone(), 2);
+    let mut lazy = MerkleTree::empty(hasher.clone(), 2);
+    assert_eq!(4, tree.arity);
+    assert_eq!(16, tree.set_size());
+    tree.update_range(0, leaves.clone()).unwrap();
+    for (i, leaf) in leaves.iter().enumerate() {
+        lazy.set_leaf(i, *leaf).unwrap();
+    }
+    let nodes: Vec<Fr> = leaves.chunks(4).map(|c| hasher.hash(c.to_vec())).collect();
+    assert_eq!(hasher.hash(nodes), tree.get_root());
+    assert_eq!(tree.get_root(), lazy.get_root());
+
+    for (index, leaf) in leaves.iter().enumerate() {
+        let witness = tree.get_witness(index).unwrap();
+        assert_eq!(6, witness.len());
+        assert_eq!(lazy.get_witness(index).unwrap(), witness);
+        assert!(verify_witness(&hasher, tree.get_root(), *leaf, &witness));
+        // siblings keep their order, and the ones right of the node follow the ones left of it
+        let mut misordered = witness.clone();
+        misordered.swap(0, 2);
+        assert!(!tree.check_inclusion(misordered, index).unwrap());
+        // a path of another shape does not verify
+        assert!(!verify_witness(
+            &hasher,
+            tree.get_root(),
+            *leaf,
+            &witness[..4]
+        ));
+    }
+    assert_eq!(tree.get_root(), lazy.flush_root());
+    assert!(tree.set_leaf(16, leaves[0]).is_err());
+}
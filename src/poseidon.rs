@@ -12,12 +12,92 @@ pub struct PoseidonParams<E: Engine> {
     mds_matrix: Vec<E::Fr>,
 }
 
+/// builds `PoseidonParams`, unset fields take the defaults of the rln circuit
+/// * 8 full rounds, 55 partial rounds, width 3
+/// * round constants and mds matrix are generated from the seed and the domain tag
+pub struct PoseidonParamsBuilder<E: Engine> {
+    rf: usize,
+    rp: usize,
+    t: usize,
+    round_constants: Option<Vec<E::Fr>>,
+    mds_matrix: Option<Vec<E::Fr>>,
+    seed: Vec<u8>,
+    domain_tag: Option<Vec<u8>>,
+}
+
 #[derive(Clone)]
 pub struct Poseidon<E: Engine> {
     params: PoseidonParams<E>,
 }
 
+impl<E: Engine> PoseidonParamsBuilder<E> {
+    pub fn full_rounds(mut self, rf: usize) -> Self {
+        self.rf = rf;
+        self
+    }
+
+    pub fn partial_rounds(mut self, rp: usize) -> Self {
+        self.rp = rp;
+        self
+    }
+
+    pub fn width(mut self, t: usize) -> Self {
+        self.t = t;
+        self
+    }
+
+    pub fn round_constants(mut self, round_constants: Vec<E::Fr>) -> Self {
+        self.round_constants = Some(round_constants);
+        self
+    }
+
+    pub fn mds_matrix(mut self, mds_matrix: Vec<E::Fr>) -> Self {
+        self.mds_matrix = Some(mds_matrix);
+        self
+    }
+
+    pub fn seed(mut self, seed: &[u8]) -> Self {
+        self.seed = seed.to_vec();
+        self
+    }
+
+    /// separates hashing contexts, hashes of the same inputs differ under different tags
+    pub fn domain_tag(mut self, tag: &[u8]) -> Self {
+        self.domain_tag = Some(tag.to_vec());
+        self
+    }
+
+    pub fn build(self) -> PoseidonParams<E> {
+        let mut seed = self.seed;
+        if let Some(tag) = self.domain_tag {
+            // tag length keeps (seed, tag) pairs apart from each other
+            seed.extend_from_slice(&tag);
+            seed.extend_from_slice(&(tag.len() as u64).to_le_bytes());
+        }
+        PoseidonParams::new(
+            self.rf,
+            self.rp,
+            self.t,
+            self.round_constants,
+            self.mds_matrix,
+            Some(seed),
+        )
+    }
+}
+
 impl<E: Engine> PoseidonParams<E> {
+    pub fn builder() -> PoseidonParamsBuilder<E> {
+        PoseidonParamsBuilder {
+            rf: 8,
+            rp: 55,
+            t: 3,
+            round_constants: None,
+            mds_matrix: None,
+            seed: Vec::new(),
+            domain_tag: None,
+        }
+    }
+
     pub fn new(
         rf: usize,
         rp: usize,
@@ -119,18 +199,55 @@ impl<E: Engine> Poseidon<E> {
         Poseidon { params }
     }
 
+    /// state width, a single permutation hashes up to `width - 1` inputs
+    pub fn width(&self) -> usize {
+        self.t()
+    }
+
     pub fn hash(&self, inputs: Vec<E::Fr>) -> E::Fr {
         let mut state = inputs.clone();
         state.resize(self.t(), E::Fr::zero());
+        self.permute(&mut state);
+        state[0]
+    }
+
+    /// sponge with rate `t - 1`, absorbs all inputs and squeezes `n_outputs` elements
+    /// * for up to `t - 1` inputs the first output equals `hash`
+    pub fn hash_n(&self, inputs: Vec<E::Fr>, n_outputs: usize) -> Vec<E::Fr> {
+        let rate = self.t() - 1;
+        let mut state = vec![E::Fr::zero(); self.t()];
+        let mut chunks = inputs.chunks(rate).peekable();
+        if chunks.peek().is_none() {
+            self.permute(&mut state);
+        }
+        for chunk in chunks {
+            for (s, input) in state.iter_mut().zip(chunk.iter()) {
+                s.add_assign(input);
+            }
+            self.permute(&mut state);
+        }
+        // grows as outputs are squeezed, `n_outputs` may come from untrusted input
+        let mut outputs: Vec<E::Fr> = Vec::new();
+        loop {
+            for s in state[..rate].iter() {
+                if outputs.len() == n_outputs {
+                    return outputs;
+                }
+                outputs.push(*s);
+            }
+            self.permute(&mut state);
+        }
+    }
+
+    fn permute(&self, state: &mut Vec<E::Fr>) {
         let mut round_counter: usize = 0;
         loop {
-            self.round(&mut state, round_counter);
+            self.round(state, round_counter);
             round_counter += 1;
             if round_counter == self.params.total_rounds() {
                 break;
             }
         }
-        state[0]
     }
 
     fn t(&self) -> usize {
@@ -225,3 +342,64 @@ fn test_poseidon_hash() {
     // println!("{:?}", r1);
     assert_eq!(r1, r2, "just to see if internal state resets");
 }
+
+#[test]
+fn test_poseidon_params_builder() {
+    use sapling_crypto::bellman::pairing::bn256::{Bn256, Fr};
+    let inputs: Vec<Fr> = ["1", "2"]
+        .iter()
+        .map(|e| Fr::from_str(e).unwrap())
+        .collect();
+    let hash = |params: PoseidonParams<Bn256>| Poseidon::<Bn256>::new(params).hash(inputs.clone());
+
+    let default_params = PoseidonParams::<Bn256>::new(8, 55, 3, None, None, None);
+    let params = PoseidonParams::<Bn256>::builder().build();
+    assert_eq!(default_params.mds_matrix(), params.mds_matrix());
+    assert_eq!(hash(default_params), hash(params));
+
+    let params = PoseidonParams::<Bn256>::builder()
+        .full_rounds(8)
+        .partial_rounds(55)
+        .width(3)
+        .domain_tag(b"identity")
+        .build();
+    let other_params = PoseidonParams::<Bn256>::builder()
+        .domain_tag(b"nullifier")
+        .build();
+    assert_ne!(hash(params.clone()), hash(other_params));
+    assert_ne!(
+        hash(params.clone()),
+        hash(PoseidonParams::<Bn256>::builder().build())
+    );
+    assert_eq!(
+        hash(params),
+        hash(
+            PoseidonParams::<Bn256>::builder()
+                .domain_tag(b"identity")
+                .build()
+        )
+    );
+}
+
+#[test]
+fn test_poseidon_hash_n() {
+    use sapling_crypto::bellman::pairing::bn256::{Bn256, Fr};
+    let params = PoseidonParams::<Bn256>::new(8, 55, 3, None, None, None);
+    let hasher = Poseidon::<Bn256>::new(params);
+    let inputs: Vec<Fr> = ["1", "2", "3", "4", "5"]
+        .iter()
+        .map(|e| Fr::from_str(e).unwrap())
+        .collect();
+
+    for n in 0..3 {
+        let expected = hasher.hash(inputs[..n].to_vec());
+        assert_eq!(vec![expected], hasher.hash_n(inputs[..n].to_vec(), 1));
+    }
+
+    let outputs = hasher.hash_n(inputs.clone(), 5);
+    assert_eq!(5, outputs.len());
+    assert_eq!(outputs, hasher.hash_n(inputs.clone(), 5));
+    assert_eq!(outputs[..2].to_vec(), hasher.hash_n(inputs.clone(), 2));
+    assert_ne!(outputs[0], outputs[1]);
+    assert_ne!(outputs, hasher.hash_n(inputs[..4].to_vec(), 5));
+}
@@ -1,20 +1,36 @@
+use crate::circuit::counter::ConstraintCounter;
 use crate::circuit::rln::{RLNCircuit, RLNInputs};
+use crate::error::{non_canonical_field, RlnError};
 use crate::hash_to_field::hash_to_field;
-use crate::merkle::MerkleTree;
+pub use crate::hash_to_field::signal_hash_keccak;
+use crate::merkle::{verify_witness, MerkleTree};
 use crate::poseidon::{Poseidon as PoseidonHasher, PoseidonParams};
-use crate::utils::{read_fr, read_signal_hash, read_uncompressed_proof, write_uncompressed_proof};
+use crate::secret::SecretKey;
+use crate::utils::{
+    read_fr, read_proof_header, read_signal, read_signal_hash, read_single_fr,
+    read_uncompressed_proof, write_proof_header, write_uncompressed_proof, PROOF_MAGIC,
+};
 use crate::{circuit::poseidon::PoseidonCircuit, merkle::IncrementalMerkleTree};
 use bellman::groth16::generate_random_parameters;
 use bellman::groth16::{create_proof, prepare_verifying_key, verify_proof};
-use bellman::groth16::{create_random_proof, Parameters, Proof};
+use bellman::groth16::{
+    create_random_proof, Parameters, PreparedVerifyingKey, Proof, VerifyingKey,
+};
+use bellman::pairing::bn256::Bn256;
 use bellman::pairing::ff::{Field, PrimeField, PrimeFieldRepr, ScalarEngine};
-use bellman::pairing::{CurveAffine, EncodedPoint, Engine};
+use bellman::pairing::{CurveAffine, CurveProjective, EncodedPoint, Engine};
 use bellman::{Circuit, ConstraintSystem, SynthesisError};
-use byteorder::{LittleEndian, ReadBytesExt};
-use rand::{thread_rng, Rand, Rng};
+use byteorder::{BigEndian, LittleEndian, ReadBytesExt, WriteBytesExt};
+use rand::{thread_rng, ChaChaRng, Rand, Rng, SeedableRng};
+use sapling_crypto::circuit::test::TestConstraintSystem;
+use sha2::{Digest, Sha256};
 use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fs::{File, OpenOptions},
     io::{self, Error, ErrorKind, Read, Write},
+    path::Path,
     ptr::null,
+    sync::Mutex,
 };
 
 // Rate Limit Nullifier
@@ -25,6 +41,8 @@ where
     E: Engine,
 {
     pub epoch: E::Fr,
+    pub message_id: E::Fr,
+    pub signal: Vec<u8>,
     pub hash: E::Fr,
 }
 
@@ -32,251 +50,5852 @@ impl<E> RLNSignal<E>
 where
     E: Engine,
 {
+    /// reads a signal serialized as [ epoch<32> | message_id<32> | signal_len<8> | signal<var> ]
+    /// * field elements that are not below the modulus are rejected with an `InvalidData` error
     pub fn read<R: Read>(mut reader: R) -> io::Result<RLNSignal<E>> {
         let mut buf = <E::Fr as PrimeField>::Repr::default();
 
         buf.read_le(&mut reader)?;
-        let epoch =
-            E::Fr::from_repr(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let epoch = E::Fr::from_repr(buf).map_err(|_| non_canonical_field())?;
+
+        buf.read_le(&mut reader)?;
+        let message_id = E::Fr::from_repr(buf).map_err(|_| non_canonical_field())?;
 
-        let hash = read_signal_hash::<R, E>(reader)?;
+        let signal = read_signal(&mut reader)?;
+        let hash = hash_to_field::<E>(&signal);
 
-        Ok(RLNSignal { epoch, hash })
+        Ok(RLNSignal {
+            epoch,
+            message_id,
+            signal,
+            hash,
+        })
+    }
+
+    /// writes the signal serialized as the input of `read`
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        self.epoch.into_repr().write_le(&mut writer)?;
+        self.message_id.into_repr().write_le(&mut writer)?;
+        writer.write_u64::<LittleEndian>(self.signal.len() as u64)?;
+        writer.write_all(&self.signal)?;
+        Ok(())
     }
 }
 
-pub struct RLN<E: Engine> {
-    circuit_parameters: Parameters<E>,
-    poseidon_params: PoseidonParams<E>,
-    tree: IncrementalMerkleTree<E>,
+/// public outputs of a proof apart from the epoch
+#[derive(Clone)]
+pub struct ProofMetadata<E>
+where
+    E: Engine,
+{
+    pub root: E::Fr,
+    pub nullifier: E::Fr,
+    pub share_x: E::Fr,
+    pub share_y: E::Fr,
 }
 
-impl<E: Engine> RLN<E> {
-    fn default_poseidon_params() -> PoseidonParams<E> {
-        PoseidonParams::<E>::new(8, 55, 3, None, None, None)
+impl<E> ProofMetadata<E>
+where
+    E: Engine,
+{
+    /// reads the metadata of proof data serialized as the output of `RLN::generate_proof`
+    /// * decoded as in `PublicInputs::read`, the proof points are skipped
+    pub fn read<R: Read>(reader: R) -> io::Result<ProofMetadata<E>> {
+        Ok(PublicInputs::read(reader)?.into())
     }
+}
 
-    fn new_circuit(merkle_depth: usize, poseidon_params: PoseidonParams<E>) -> Parameters<E> {
-        let mut rng = thread_rng();
-        let inputs = RLNInputs::<E>::empty(merkle_depth);
-        let circuit = RLNCircuit::<E> {
-            inputs,
-            hasher: PoseidonCircuit::new(poseidon_params.clone()),
-        };
-        generate_random_parameters(circuit, &mut rng).unwrap()
+impl<E> From<PublicInputs<E>> for ProofMetadata<E>
+where
+    E: Engine,
+{
+    fn from(inputs: PublicInputs<E>) -> Self {
+        ProofMetadata {
+            root: inputs.root,
+            nullifier: inputs.nullifier,
+            share_x: inputs.share_x,
+            share_y: inputs.share_y,
+        }
+    }
+}
+
+/// public inputs of a proof as carried in proof data
+#[derive(Clone)]
+pub struct PublicInputs<E>
+where
+    E: Engine,
+{
+    pub root: E::Fr,
+    /// the external nullifier, see `RLN::external_nullifier`
+    pub epoch: E::Fr,
+    pub nullifier: E::Fr,
+    pub share_x: E::Fr,
+    pub share_y: E::Fr,
+}
+
+impl<E> PublicInputs<E>
+where
+    E: Engine,
+{
+    /// reads the public inputs of proof data serialized as the output of `RLN::generate_proof`
+    /// * the proof points are skipped without decoding
+    pub fn read<R: Read>(mut reader: R) -> io::Result<PublicInputs<E>> {
+        read_proof_header(&mut reader)?;
+        let g1_size = <<E::G1Affine as CurveAffine>::Uncompressed as EncodedPoint>::size();
+        let g2_size = <<E::G2Affine as CurveAffine>::Uncompressed as EncodedPoint>::size();
+        let mut proof = vec![0u8; 2 * g1_size + g2_size];
+        reader.read_exact(&mut proof)?;
+        let public_inputs = RLNInputs::<E>::read_public_inputs(reader)?;
+        Ok(PublicInputs {
+            root: public_inputs[0],
+            epoch: public_inputs[1],
+            share_x: public_inputs[2],
+            share_y: public_inputs[3],
+            nullifier: public_inputs[4],
+        })
     }
+}
 
-    fn new_with_params(
-        merkle_depth: usize,
-        circuit_parameters: Parameters<E>,
-        poseidon_params: PoseidonParams<E>,
-    ) -> RLN<E> {
-        let hasher = PoseidonHasher::new(poseidon_params.clone());
-        let tree = IncrementalMerkleTree::empty(hasher, merkle_depth);
-        RLN {
-            circuit_parameters,
-            poseidon_params,
-            tree,
+// json representation, field elements are 0x prefixed big-endian hex strings
+// and the signal is a 0x prefixed hex string of its bytes
+#[cfg(feature = "serde")]
+mod json {
+    use super::{ProofMetadata, RLNSignal};
+    use crate::hash_to_field::hash_to_field;
+    use bellman::pairing::ff::{PrimeField, PrimeFieldRepr};
+    use bellman::pairing::Engine;
+    use serde_crate::de::Error as _;
+    use serde_crate::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(crate = "serde_crate")]
+    struct SignalJson {
+        epoch: String,
+        message_id: String,
+        signal: String,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(crate = "serde_crate")]
+    struct ProofMetadataJson {
+        root: String,
+        nullifier: String,
+        share_x: String,
+        share_y: String,
+    }
+
+    fn bytes_to_hex(bytes: &[u8]) -> String {
+        format!("0x{}", hex::encode(bytes))
+    }
+
+    fn bytes_from_hex(s: &str) -> Result<Vec<u8>, String> {
+        let s = s.strip_prefix("0x").ok_or("missing 0x prefix")?;
+        hex::decode(s).map_err(|e| e.to_string())
+    }
+
+    fn fr_to_hex<E: Engine>(e: &E::Fr) -> String {
+        let mut bytes: Vec<u8> = Vec::new();
+        e.into_repr().write_be(&mut bytes).unwrap();
+        bytes_to_hex(&bytes)
+    }
+
+    fn fr_from_hex<E: Engine>(s: &str) -> Result<E::Fr, String> {
+        let bytes = bytes_from_hex(s)?;
+        let mut repr = <E::Fr as PrimeField>::Repr::default();
+        if bytes.len() != repr.as_ref().len() * 8 {
+            return Err(format!(
+                "field element must be {} bytes",
+                repr.as_ref().len() * 8
+            ));
         }
+        repr.read_be(bytes.as_slice()).map_err(|e| e.to_string())?;
+        E::Fr::from_repr(repr).map_err(|e| e.to_string())
     }
 
-    pub fn new(merkle_depth: usize, poseidon_params: Option<PoseidonParams<E>>) -> RLN<E> {
-        let poseidon_params = match poseidon_params {
-            Some(params) => params,
-            None => Self::default_poseidon_params(),
-        };
-        let circuit_parameters = Self::new_circuit(merkle_depth, poseidon_params.clone());
-        Self::new_with_params(merkle_depth, circuit_parameters, poseidon_params)
+    impl<E: Engine> Serialize for RLNSignal<E> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            SignalJson {
+                epoch: fr_to_hex::<E>(&self.epoch),
+                message_id: fr_to_hex::<E>(&self.message_id),
+                signal: bytes_to_hex(&self.signal),
+            }
+            .serialize(serializer)
+        }
     }
 
-    pub fn new_with_raw_params<R: Read>(
-        merkle_depth: usize,
-        raw_circuit_parameters: R,
-        poseidon_params: Option<PoseidonParams<E>>,
-    ) -> io::Result<RLN<E>> {
-        let circuit_parameters = Parameters::<E>::read(raw_circuit_parameters, true)?;
-        let poseidon_params = match poseidon_params {
-            Some(params) => params,
-            None => Self::default_poseidon_params(),
+    impl<'de, E: Engine> Deserialize<'de> for RLNSignal<E> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let json = SignalJson::deserialize(deserializer)?;
+            let signal = bytes_from_hex(&json.signal).map_err(D::Error::custom)?;
+            let hash = hash_to_field::<E>(&signal);
+            Ok(RLNSignal {
+                epoch: fr_from_hex::<E>(&json.epoch).map_err(D::Error::custom)?,
+                message_id: fr_from_hex::<E>(&json.message_id).map_err(D::Error::custom)?,
+                signal,
+                hash,
+            })
+        }
+    }
+
+    impl<E: Engine> Serialize for ProofMetadata<E> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            ProofMetadataJson {
+                root: fr_to_hex::<E>(&self.root),
+                nullifier: fr_to_hex::<E>(&self.nullifier),
+                share_x: fr_to_hex::<E>(&self.share_x),
+                share_y: fr_to_hex::<E>(&self.share_y),
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de, E: Engine> Deserialize<'de> for ProofMetadata<E> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let json = ProofMetadataJson::deserialize(deserializer)?;
+            Ok(ProofMetadata {
+                root: fr_from_hex::<E>(&json.root).map_err(D::Error::custom)?,
+                nullifier: fr_from_hex::<E>(&json.nullifier).map_err(D::Error::custom)?,
+                share_x: fr_from_hex::<E>(&json.share_x).map_err(D::Error::custom)?,
+                share_y: fr_from_hex::<E>(&json.share_y).map_err(D::Error::custom)?,
+            })
+        }
+    }
+}
+
+/// reads proof data serialized as the output of `generate_proof`
+/// and returns the proof together with its public inputs
+fn read_proof_data<R: Read, E: Engine>(mut reader: R) -> io::Result<(Proof<E>, Vec<E::Fr>)> {
+    read_proof_header(&mut reader)?;
+    let proof = read_uncompressed_proof(&mut reader)?;
+    let public_inputs = RLNInputs::<E>::read_public_inputs(&mut reader)?;
+    Ok((proof, public_inputs))
+}
+
+/// reads proof data followed by the signal as expected by `verify`
+/// and checks that the signal hash matches the public inputs
+fn read_verify_data<R: Read, E: Engine>(mut reader: R) -> io::Result<(Proof<E>, Vec<E::Fr>)> {
+    let (proof, public_inputs) = read_proof_data::<_, E>(&mut reader)?;
+    let signal_hash = read_signal_hash::<R, E>(reader)?;
+    if signal_hash != public_inputs[2] {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "signal hash mismatch",
+        ));
+    }
+    Ok((proof, public_inputs))
+}
+
+fn check_proof<E: Engine>(
+    prepared_verifying_key: &PreparedVerifyingKey<E>,
+    proof: &Proof<E>,
+    public_inputs: &[E::Fr],
+) -> Result<bool, RlnError> {
+    let success =
+        verify_proof(prepared_verifying_key, proof, public_inputs).map_err(RlnError::Proving)?;
+    Ok(success)
+}
+
+// checks that the external nullifier of a proof is `hash(epoch, rln_identifier)`
+fn check_external_nullifier<E: Engine>(
+    hasher: &PoseidonHasher<E>,
+    epoch: E::Fr,
+    rln_identifier: E::Fr,
+    external_nullifier: E::Fr,
+) -> Result<(), RlnError> {
+    if hasher.hash(vec![epoch, rln_identifier]) != external_nullifier {
+        return Err(RlnError::IdentifierMismatch);
+    }
+    Ok(())
+}
+
+/// checks all proofs at once against a random linear combination of the verification equations
+/// * returns `false` if at least one of the proofs is invalid
+fn batch_check_proofs<E: Engine, G: Rng>(
+    verifying_key: &VerifyingKey<E>,
+    proofs: &[(Proof<E>, Vec<E::Fr>)],
+    rng: &mut G,
+) -> bool {
+    let mut acc_r = E::Fr::zero();
+    let mut acc_ic = E::G1::zero();
+    let mut acc_c = E::G1::zero();
+    let mut pairs = Vec::with_capacity(proofs.len() + 3);
+    for (proof, public_inputs) in proofs.iter() {
+        if public_inputs.len() + 1 != verifying_key.ic.len() {
+            return false;
+        }
+        let r = E::Fr::rand(rng);
+        let mut ic = verifying_key.ic[0].into_projective();
+        for (input, base) in public_inputs.iter().zip(verifying_key.ic[1..].iter()) {
+            ic.add_assign(&base.mul(input.into_repr()));
+        }
+        ic.mul_assign(r.into_repr());
+        acc_ic.add_assign(&ic);
+        acc_c.add_assign(&proof.c.mul(r.into_repr()));
+        acc_r.add_assign(&r);
+        pairs.push((
+            proof.a.mul(r.into_repr()).into_affine().prepare(),
+            proof.b.prepare(),
+        ));
+    }
+
+    // prod e(r_i * a_i, b_i) == e(alpha, beta)^sum(r_i) * e(sum(r_i * ic_i), gamma) * e(sum(r_i * c_i), delta)
+    let mut acc_alpha = verifying_key.alpha_g1.mul(acc_r.into_repr());
+    acc_alpha.negate();
+    acc_ic.negate();
+    acc_c.negate();
+    pairs.push((
+        acc_alpha.into_affine().prepare(),
+        verifying_key.beta_g2.prepare(),
+    ));
+    pairs.push((
+        acc_ic.into_affine().prepare(),
+        verifying_key.gamma_g2.prepare(),
+    ));
+    pairs.push((
+        acc_c.into_affine().prepare(),
+        verifying_key.delta_g2.prepare(),
+    ));
+    let pairs: Vec<(&_, &_)> = pairs.iter().map(|(a, b)| (a, b)).collect();
+    match E::final_exponentiation(&E::miller_loop(pairs.iter())) {
+        Some(result) => result == E::Fqk::one(),
+        None => false,
+    }
+}
+
+/// reads `count` proofs serialized as the input of `verify` one after another and verifies them
+/// * a batch is checked at once first, proofs are checked one by one only if the batch fails
+/// * a proof with a signal that does not match its public inputs is invalid
+/// * with an identifier each proof is preceded by [ epoch<32> ], see `RLN::verify`,
+/// and a proof whose external nullifier is not derived from it is invalid
+fn verify_batch_data<R: Read, E: Engine>(
+    verifying_key: &VerifyingKey<E>,
+    prepared_verifying_key: &PreparedVerifyingKey<E>,
+    identifier: Option<(&PoseidonHasher<E>, E::Fr)>,
+    mut input_data: R,
+    count: usize,
+) -> Result<Vec<bool>, RlnError> {
+    // `count` is not trusted, the vectors grow as proofs are read
+    let mut proofs = Vec::new();
+    let mut signal_matches = Vec::new();
+    for _ in 0..count {
+        let epoch = match identifier {
+            Some(_) => Some(read_fr::<_, E>(&mut input_data, 1)?[0]),
+            None => None,
         };
-        Ok(Self::new_with_params(
-            merkle_depth,
-            circuit_parameters,
-            poseidon_params,
-        ))
+        let (proof, public_inputs) = read_proof_data::<_, E>(&mut input_data)?;
+        let signal_hash = read_signal_hash::<_, E>(&mut input_data)?;
+        let identifier_match = match (identifier, epoch) {
+            (Some((hasher, rln_identifier)), Some(epoch)) => {
+                check_external_nullifier(hasher, epoch, rln_identifier, public_inputs[1]).is_ok()
+            }
+            _ => true,
+        };
+        signal_matches.push(signal_hash == public_inputs[2] && identifier_match);
+        proofs.push((proof, public_inputs));
     }
 
-    /// returns current membership root
-    /// * `root` is a scalar field element in 32 bytes
-    pub fn get_root<W: Write>(&self, mut result_data: W) -> io::Result<()> {
-        let root = self.tree.get_root();
-        root.into_repr().write_le(&mut result_data)?;
-        Ok(())
+    if signal_matches.iter().all(|m| *m)
+        && batch_check_proofs(verifying_key, &proofs, &mut thread_rng())
+    {
+        return Ok(vec![true; count]);
     }
 
-    /// inserts new member with given public key
-    /// * `input_data` is a 32 scalar field element in 32 bytes
-    pub fn update_next_member<R: Read>(&mut self, input_data: R) -> io::Result<()> {
-        let mut buf = <E::Fr as PrimeField>::Repr::default();
-        buf.read_le(input_data)?;
-        let leaf =
-            E::Fr::from_repr(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-        self.tree.update_next(leaf)?;
-        Ok(())
+    let mut results = Vec::with_capacity(proofs.len());
+    for ((proof, public_inputs), signal_match) in proofs.iter().zip(signal_matches.into_iter()) {
+        let success = signal_match
+            && verify_proof(prepared_verifying_key, proof, public_inputs)
+                .map_err(RlnError::Proving)?;
+        results.push(success);
     }
+    Ok(results)
+}
 
-    //// deletes member with given index
-    pub fn delete_member(&mut self, index: usize) -> io::Result<()> {
-        self.tree.delete(index)?;
-        Ok(())
+/// reads the next frame of a stream serialized as [ frame_len<8> | frame<frame_len> ]
+/// * returns `None` at the end of the stream, a stream ending inside a frame is an `UnexpectedEof` error
+fn read_frame<R: Read>(mut reader: R) -> io::Result<Option<Vec<u8>>> {
+    let mut len_data = [0u8; 8];
+    if !read_exact_or_eof(&mut reader, &mut len_data)? {
+        return Ok(None);
     }
+    let len = (&len_data[..]).read_u64::<LittleEndian>()?;
+    let mut frame: Vec<u8> = Vec::new();
+    reader.take(len).read_to_end(&mut frame)?;
+    if frame.len() as u64 != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(Some(frame))
+}
 
-    /// hashes plain text to a field element
-    pub fn signal_to_field<R: Read, W: Write>(
+// fills `buf`, returns false if the input ends before its first byte
+// and an `UnexpectedEof` error if it ends within it
+fn read_exact_or_eof<R: Read>(mut reader: R, buf: &mut [u8]) -> io::Result<bool> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(true)
+}
+
+// size of an uncompressed verifying key, from its number of points
+fn verifying_key_size<E: Engine>(verifying_key: &VerifyingKey<E>) -> usize {
+    let g1_size = <<E::G1Affine as CurveAffine>::Uncompressed as EncodedPoint>::size();
+    let g2_size = <<E::G2Affine as CurveAffine>::Uncompressed as EncodedPoint>::size();
+    // alpha_g1, beta_g1, delta_g1 and beta_g2, gamma_g2, delta_g2, then the u32 prefixed ic
+    3 * g1_size + 3 * g2_size + 4 + verifying_key.ic.len() * g1_size
+}
+
+/// verifies signals with only the verifying key of the circuit
+/// * holds no membership state and all methods take `&self`,
+/// so a single verifier can be shared across threads, for example in an `Arc`
+pub struct RLNVerifier<E: Engine> {
+    verifying_key: VerifyingKey<E>,
+    // prepared once, reused by every verification
+    prepared_verifying_key: PreparedVerifyingKey<E>,
+}
+
+impl<E: Engine> RLNVerifier<E> {
+    /// creates a verifier from a verifying key serialized as the output of `RLN::export_verifying_key`
+    pub fn from_raw_vk<R: Read>(raw_verifying_key: R) -> Result<RLNVerifier<E>, RlnError> {
+        let verifying_key = VerifyingKey::<E>::read(raw_verifying_key)?;
+        Ok(RLNVerifier::new(verifying_key))
+    }
+
+    fn new(verifying_key: VerifyingKey<E>) -> RLNVerifier<E> {
+        let prepared_verifying_key = prepare_verifying_key(&verifying_key);
+        RLNVerifier {
+            verifying_key,
+            prepared_verifying_key,
+        }
+    }
+
+    /// given proof and public data verifies the signal
+    /// * expect `input_data` serialized as in `RLN::verify`
+    pub fn verify<R: Read>(&self, input_data: R) -> Result<bool, RlnError> {
+        let (proof, public_inputs) = read_verify_data::<_, E>(input_data)?;
+        check_proof(&self.prepared_verifying_key, &proof, &public_inputs)
+    }
+
+    /// serialized size in bytes of the verifying key the verifier holds,
+    /// as written by `RLN::export_verifying_key`
+    pub fn size(&self) -> usize {
+        verifying_key_size(&self.verifying_key)
+    }
+
+    /// verifies `count` signals, see `RLN::verify_batch`
+    pub fn verify_batch<R: Read>(
         &self,
         input_data: R,
-        mut result_data: W,
-    ) -> io::Result<()> {
-        let result = read_signal_hash::<R, E>(input_data)?;
-        result.into_repr().write_le(&mut result_data)?;
-        Ok(())
+        count: usize,
+    ) -> Result<Vec<bool>, RlnError> {
+        verify_batch_data::<_, E>(
+            &self.verifying_key,
+            &self.prepared_verifying_key,
+            None,
+            input_data,
+            count,
+        )
     }
+}
 
-    /// given public inputs and autharization data generates public inputs and proof
-    /// * expect `input_data`  serialized as  [ id_key<32> | id_index<8> | epoch<32> | signal_len<8> | signal<var> ]
-    /// * `result_data` is proof data serialized as [ proof<256>| root<32>| epoch<32>| share_x<32>| share_y<32>| nullifier<32> ]
-    pub fn generate_proof<R: Read, W: Write>(
-        &self,
-        mut input_data: R,
-        mut result_data: W,
-    ) -> io::Result<()> {
-        let id_key: E::Fr = read_fr::<_, E>(&mut input_data, 1)?[0];
-        let id_index = input_data.read_u64::<LittleEndian>()? as usize;
+/// writes the header of exported circuit parameters serialized as [ merkle_depth<8> | message_limit<8> ]
+pub fn write_parameters_header<W: Write>(
+    mut writer: W,
+    merkle_depth: usize,
+    message_limit: u64,
+) -> io::Result<()> {
+    writer.write_u64::<LittleEndian>(merkle_depth as u64)?;
+    writer.write_u64::<LittleEndian>(message_limit)?;
+    Ok(())
+}
 
-        let signal = RLNSignal::<E>::read(input_data)?;
+/// writes circuit parameters serialized as [ merkle_depth<8> | message_limit<8> | parameters<var> | digest<32> ]
+/// * `digest` is the sha256 of the preceding bytes, it is checked when the parameters are loaded
+pub fn write_circuit_parameters<E: Engine, W: Write>(
+    writer: W,
+    merkle_depth: usize,
+    message_limit: u64,
+    parameters: &Parameters<E>,
+) -> io::Result<()> {
+    let mut writer = DigestWriter {
+        inner: writer,
+        digest: Sha256::new(),
+    };
+    write_parameters_header(&mut writer, merkle_depth, message_limit)?;
+    parameters.write(&mut writer)?;
+    let digest = writer.digest.finalize();
+    writer.inner.write_all(&digest)
+}
 
-        use hex;
+// hashes the bytes written through it
+struct DigestWriter<W: Write> {
+    inner: W,
+    digest: Sha256,
+}
 
-        let hasher = self.hasher();
-        let share_x = signal.hash.clone();
+impl<W: Write> Write for DigestWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.digest.update(&buf[..n]);
+        Ok(n)
+    }
 
-        // line equation
-        let a_0 = id_key.clone();
-        let a_1: E::Fr = hasher.hash(vec![a_0, signal.epoch]);
-        // evaluate line equation
-        let mut share_y = a_1.clone();
-        share_y.mul_assign(&share_x);
-        share_y.add_assign(&a_0);
-        let nullifier = hasher.hash(vec![a_1]);
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
 
-        let root = self.tree.get_root();
-        // TODO: check id key here
-        let auth_path = self.tree.get_witness(id_index)?;
+// hashes the bytes read through it unless `digest` is none
+struct DigestReader<R: Read> {
+    inner: R,
+    digest: Option<Sha256>,
+}
 
-        let inputs = RLNInputs::<E> {
-            share_x: Some(share_x),
-            share_y: Some(share_y),
-            epoch: Some(signal.epoch),
-            nullifier: Some(nullifier),
-            root: Some(root),
-            id_key: Some(id_key),
-            auth_path: auth_path.into_iter().map(|w| Some(w)).collect(),
-        };
+impl<R: Read> Read for DigestReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if let Some(digest) = self.digest.as_mut() {
+            digest.update(&buf[..n]);
+        }
+        Ok(n)
+    }
+}
 
-        let circuit = RLNCircuit {
-            inputs: inputs.clone(),
-            hasher: PoseidonCircuit::new(self.poseidon_params.clone()),
-        };
+// reports the bytes written so far and the expected total after every write
+struct ProgressWriter<W: Write, F: FnMut(u64, u64)> {
+    inner: W,
+    written: u64,
+    total: u64,
+    progress: F,
+}
 
-        let mut rng = thread_rng();
-        let proof = create_random_proof(circuit, &self.circuit_parameters, &mut rng).unwrap();
-        write_uncompressed_proof(proof.clone(), &mut result_data)?;
-        root.into_repr().write_le(&mut result_data)?;
-        signal.epoch.into_repr().write_le(&mut result_data)?;
-        share_x.into_repr().write_le(&mut result_data)?;
-        share_y.into_repr().write_le(&mut result_data)?;
-        nullifier.into_repr().write_le(&mut result_data)?;
+impl<W: Write, F: FnMut(u64, u64)> Write for ProgressWriter<W, F> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.written += n as u64;
+        (self.progress)(self.written, self.total);
+        Ok(n)
+    }
 
-        Ok(())
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
     }
+}
 
-    /// given proof and public data verifies the signal
-    /// * expect `input_data` is serialized as:
-    /// [ proof<256>| root<32>| epoch<32>| share_x<32>| share_y<32>| nullifier<32> | signal_len<8> | signal<var> ]
-    pub fn verify<R: Read>(&self, mut input_data: R) -> io::Result<bool> {
-        let proof = read_uncompressed_proof(&mut input_data)?;
-        let public_inputs = RLNInputs::<E>::read_public_inputs(&mut input_data)?;
-        let signal_hash = read_signal_hash::<R, E>(input_data)?;
-
-        if signal_hash != public_inputs[2] {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidInput,
-                "signal hash mismatch",
-            ));
-        }
+/// byte order of serialized field elements
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+    Little,
+    Big,
+}
 
-        let verifing_key = prepare_verifying_key(&self.circuit_parameters.vk);
-        let success = verify_proof(&verifing_key, &proof, &public_inputs).unwrap();
-        Ok(success)
+/// writes a field element in 32 bytes in the given byte order
+pub fn serialize_field<E: Engine, W: Write>(
+    fr: &E::Fr,
+    endian: Endian,
+    writer: W,
+) -> io::Result<()> {
+    match endian {
+        Endian::Little => fr.into_repr().write_le(writer),
+        Endian::Big => fr.into_repr().write_be(writer),
     }
+}
 
-    /// generates public private key pair
-    /// * `key_pair_data` is seralized as [ secret<32> | public<32> ]
-    pub fn key_gen<W: Write>(&self, mut input_data: W) -> io::Result<()> {
-        let mut rng = thread_rng();
-        let hasher = self.hasher();
-        let secret = E::Fr::rand(&mut rng);
-        let public: E::Fr = hasher.hash(vec![secret.clone()]);
-        secret.into_repr().write_le(&mut input_data)?;
-        public.into_repr().write_le(&mut input_data)?;
-        Ok(())
+/// reads a field element serialized in 32 bytes in the given byte order
+/// * input of another length is rejected with an `InvalidInput` error
+/// and elements not below the modulus with an `InvalidData` error
+pub fn deserialize_field<E: Engine>(bytes: &[u8], endian: Endian) -> io::Result<E::Fr> {
+    let mut repr = <E::Fr as PrimeField>::Repr::default();
+    if bytes.len() != repr.as_ref().len() * 8 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "field element must be 32 bytes",
+        ));
     }
+    match endian {
+        Endian::Little => repr.read_le(bytes)?,
+        Endian::Big => repr.read_be(bytes)?,
+    }
+    E::Fr::from_repr(repr).map_err(|_| non_canonical_field())
+}
 
-    pub fn export_verifier_key<W: Write>(&self, w: W) -> io::Result<()> {
-        self.circuit_parameters.vk.write(w)
+// flips the byte order of the public inputs of proof data in place,
+// the proof points are encoded the same for both byte orders
+fn flip_public_inputs<E: Engine>(proof_data: &mut [u8]) -> io::Result<()> {
+    let g1_size = <<E::G1Affine as CurveAffine>::Uncompressed as EncodedPoint>::size();
+    let g2_size = <<E::G2Affine as CurveAffine>::Uncompressed as EncodedPoint>::size();
+    let start = PROOF_MAGIC.len() + 1 + 2 * g1_size + g2_size;
+    let fr_size = <E::Fr as PrimeField>::Repr::default().as_ref().len() * 8;
+    let end = start + 5 * fr_size;
+    if proof_data.len() < end {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "proof data is too short",
+        ));
     }
+    for element in proof_data[start..end].chunks_mut(fr_size) {
+        element.reverse();
+    }
+    Ok(())
+}
+
+// interpolates the line through two shares with distinct x and returns a_0
+fn recover_secret<E: Engine>(share_1: (E::Fr, E::Fr), share_2: (E::Fr, E::Fr)) -> E::Fr {
+    let (share_x_1, share_y_1) = share_1;
+    let (share_x_2, share_y_2) = share_2;
+
+    // a_1 = (y_2 - y_1) / (x_2 - x_1)
+    let mut a_1 = share_y_2;
+    a_1.sub_assign(&share_y_1);
+    let mut denominator = share_x_2;
+    denominator.sub_assign(&share_x_1);
+    a_1.mul_assign(&denominator.inverse().unwrap());
 
-    pub fn export_circuit_parameters<W: Write>(&self, w: W) -> io::Result<()> {
-        self.circuit_parameters.write(w)
+    // a_0 = y_1 - a_1 * x_1
+    let mut a_0 = share_y_1;
+    let mut a_1_x_1 = a_1;
+    a_1_x_1.mul_assign(&share_x_1);
+    a_0.sub_assign(&a_1_x_1);
+    a_0
+}
+
+/// records the shares of observed proofs and recovers the secret of a member
+/// once two distinct shares with the same nullifier are observed
+/// * proofs are not verified here, only observe proofs that passed `RLN::verify`
+pub struct Slasher<E>
+where
+    E: Engine,
+{
+    // first share of each nullifier, keyed by the nullifier representation
+    shares: HashMap<Vec<u64>, (E::Fr, E::Fr)>,
+    // nullifiers whose secret is already recovered
+    slashed: HashSet<Vec<u64>>,
+}
+
+impl<E> Slasher<E>
+where
+    E: Engine,
+{
+    pub fn new() -> Slasher<E> {
+        Slasher {
+            shares: HashMap::new(),
+            slashed: HashSet::new(),
+        }
     }
 
-    pub fn hasher(&self) -> PoseidonHasher<E> {
-        PoseidonHasher::new(self.poseidon_params.clone())
+    /// records the share of proof data and returns the recovered id key
+    /// the first time a second distinct share of its nullifier is observed
+    /// * expect `proof_data` serialized as the output of `RLN::generate_proof`
+    /// * the recovered id key is serialized as [ id_key<32> ]
+    pub fn observe<R: Read>(&mut self, proof_data: R) -> Result<Option<Vec<u8>>, RlnError> {
+        let inputs = PublicInputs::<E>::read(proof_data)?;
+        let id_key = self.observe_share(inputs.nullifier, (inputs.share_x, inputs.share_y));
+        id_key_data::<E>(id_key)
     }
 
-    pub fn poseidon_params(&self) -> PoseidonParams<E> {
-        self.poseidon_params.clone()
+    // records a share of a nullifier and returns the id key if it is recovered now
+    fn observe_share(&mut self, nullifier: E::Fr, share: (E::Fr, E::Fr)) -> Option<E::Fr> {
+        let key = nullifier.into_repr().as_ref().to_vec();
+        if self.slashed.contains(&key) {
+            return None;
+        }
+        let first_share = match self.shares.get(&key) {
+            Some(first_share) => *first_share,
+            None => {
+                self.shares.insert(key, share);
+                return None;
+            }
+        };
+        if first_share.0 == share.0 {
+            return None;
+        }
+        let id_key = recover_secret::<E>(first_share, share);
+        self.shares.remove(&key);
+        self.slashed.insert(key);
+        Some(id_key)
     }
 }
 
-// #[cfg(test)]
-// mod tests {
-//     use crate::{circuit::bench, public::RLNSignal};
-//     use crate::{poseidon::PoseidonParams, public};
-//     use bellman::pairing::bn256::{Bn256, Fr};
-//     use rand::{Rand, SeedableRng, XorShiftRng};
+fn id_key_data<E: Engine>(id_key: Option<E::Fr>) -> Result<Option<Vec<u8>>, RlnError> {
+    match id_key {
+        Some(id_key) => {
+            let mut id_key_data: Vec<u8> = Vec::new();
+            id_key.into_repr().write_le(&mut id_key_data)?;
+            Ok(Some(id_key_data))
+        }
+        None => Ok(None),
+    }
+}
 
-//     fn merkle_depth() -> usize {
-//         4usize
-//     }
+impl<E> Default for Slasher<E>
+where
+    E: Engine,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-//     fn rln_test() -> bench::RLNTest<Bn256> {
-//         let merkle_depth = merkle_depth();
-//         let poseidon_params = PoseidonParams::<Bn256>::new(8, 55, 3, None, None, None);
-//         let rln_test = bench::RLNTest::<Bn256>::new(merkle_depth, Some(poseidon_params));
-//         rln_test
-//     }
+/// a `Slasher` persisting the observed shares in an append only file
+/// * the file is serialized as [ nullifier<32> | share_x<32> | share_y<32> ] * record_count
+/// * opening the file replays its records, so a second share of a nullifier is detected across restarts
+/// * the log has a single writer, the file is not locked and records of concurrent writers may interleave
+pub struct NullifierLog<E>
+where
+    E: Engine,
+{
+    slasher: Slasher<E>,
+    file: File,
+}
 
-//     #[test]
-//     fn test_xxx() {
-//         let rln = rln_test();
-//         let rln = rln.rln;
+impl<E> NullifierLog<E>
+where
+    E: Engine,
+{
+    /// opens the log at `path`, creating it if it does not exist, and replays its records
+    /// * returns the log and the id keys recovered while replaying, serialized as [ id_key<32> ] each,
+    /// so a key recovered before a restart is reported again even if the caller did not act on it
+    /// * an incomplete record at the end, left by a write interrupted by a crash, is truncated
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<(NullifierLog<E>, Vec<Vec<u8>>), RlnError> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)?;
+        let mut log_data: Vec<u8> = Vec::new();
+        file.read_to_end(&mut log_data)?;
+        let record_len = 3 * <E::Fr as PrimeField>::Repr::default().as_ref().len() * 8;
+        let complete_len = log_data.len() - log_data.len() % record_len;
+        if complete_len != log_data.len() {
+            file.set_len(complete_len as u64)?;
+            file.sync_data()?;
+        }
+        let mut slasher = Slasher::new();
+        let mut recovered: Vec<Vec<u8>> = Vec::new();
+        for record in log_data[..complete_len].chunks(record_len) {
+            let elements = read_fr::<_, E>(record, 3)?;
+            let id_key = slasher.observe_share(elements[0], (elements[1], elements[2]));
+            if let Some(id_key_data) = id_key_data::<E>(id_key)? {
+                recovered.push(id_key_data);
+            }
+        }
+        Ok((NullifierLog { slasher, file }, recovered))
+    }
 
-//         let input_data: Vec<u8> = vec![1, 2, 3, 4];
-//         let n = 4;
-//         let mut result_data: Vec<u8> = Vec::new();
+    /// appends a share to the log and returns the recovered id key
+    /// the first time a second distinct share of its nullifier is appended, as `Slasher::observe`
+    /// * expect `input_data` serialized as [ nullifier<32> | share_x<32> | share_y<32> ]
+    /// * the record is synced to disk before the share is observed
+    /// * the recovered id key is serialized as [ id_key<32> ]
+    pub fn append(&mut self, input_data: &[u8]) -> Result<Option<Vec<u8>>, RlnError> {
+        let elements = read_fr::<_, E>(input_data, 3)?;
+        let mut record: Vec<u8> = Vec::new();
+        for element in elements.iter() {
+            element.into_repr().write_le(&mut record)?;
+        }
+        // the file is opened in append mode, a record interrupted by a crash is truncated by `open`
+        self.file.write_all(&record)?;
+        self.file.sync_data()?;
+        let id_key = self
+            .slasher
+            .observe_share(elements[0], (elements[1], elements[2]));
+        id_key_data::<E>(id_key)
+    }
+}
 
-//         rln.signal_to_field(&input_data[..], n, &mut result_data)
-//             .unwrap();
-//     }
-// }
+/// durations of the two phases of a proof, see `RLN::generate_proof_with_timings`
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProofTimings {
+    /// reading the signal and assigning the circuit inputs, including the membership witness
+    pub witness_ns: u64,
+    /// creating the groth16 proof and writing the proof data
+    pub prove_ns: u64,
+}
+
+/// result of `RLN::verify_and_check_nullifier`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyOutcome {
+    /// the proof does not verify
+    Invalid,
+    /// the proof verifies and its nullifier is not seen before
+    Valid,
+    /// the proof verifies but its nullifier is seen before
+    DuplicateNullifier,
+}
+
+/// compares two nullifiers serialized as 32 bytes little-endian field elements
+/// * encodings that are not canonical, not below the modulus, are rejected with an `InvalidData` error
+/// * the comparison takes the same time wherever the nullifiers differ
+pub fn nullifier_eq<E: Engine>(a: &[u8], b: &[u8]) -> Result<bool, RlnError> {
+    let mut a_data = a;
+    let mut b_data = b;
+    let a = read_fr::<_, E>(&mut a_data, 1)?[0];
+    let b = read_fr::<_, E>(&mut b_data, 1)?[0];
+    if !a_data.is_empty() || !b_data.is_empty() {
+        return Err(RlnError::Io(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "nullifier must be a single field element",
+        )));
+    }
+    let a = a.into_repr();
+    let b = b.into_repr();
+    let diff = a
+        .as_ref()
+        .iter()
+        .zip(b.as_ref().iter())
+        .fold(0u64, |acc, (a, b)| acc | (a ^ b));
+    Ok(diff == 0)
+}
+
+fn decode_hex(s: &str) -> io::Result<Vec<u8>> {
+    hex::decode(s).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// encodes proof data as output of `generate_proof` to a lowercase hex string
+pub fn proof_to_hex(proof_data: &[u8]) -> String {
+    hex::encode(proof_data)
+}
+
+/// decodes proof data from a hex string
+/// * odd length or non hex input is rejected with an `InvalidData` error
+pub fn proof_from_hex(s: &str) -> io::Result<Vec<u8>> {
+    decode_hex(s)
+}
+
+/// encodes proof data as output of `generate_proof` for a groth16 verifier contract on the EVM
+/// * `result_data` is serialized as 32 bytes big-endian words
+/// [ a.x | a.y | b.x.c1 | b.x.c0 | b.y.c1 | b.y.c0 | c.x | c.y | root | epoch | share_x | share_y | nullifier ]
+/// * the coefficients of b are swapped as expected by the bn254 pairing precompile
+pub fn proof_to_evm_calldata<W: Write>(proof_data: &[u8], mut result_data: W) -> io::Result<()> {
+    let mut reader = proof_data;
+    let (proof, public_inputs) = read_proof_data::<_, Bn256>(&mut reader)?;
+    if !reader.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "trailing bytes after proof data",
+        ));
+    }
+    let (a_x, a_y) = proof.a.into_xy_unchecked();
+    let (b_x, b_y) = proof.b.into_xy_unchecked();
+    let (c_x, c_y) = proof.c.into_xy_unchecked();
+    for word in [a_x, a_y, b_x.c1, b_x.c0, b_y.c1, b_y.c0, c_x, c_y].iter() {
+        word.into_repr().write_be(&mut result_data)?;
+    }
+    for input in public_inputs.iter() {
+        input.into_repr().write_be(&mut result_data)?;
+    }
+    Ok(())
+}
+
+/// encodes key pair data as output of `key_gen` to a lowercase hex string
+pub fn keypair_to_hex(keypair_data: &[u8]) -> String {
+    hex::encode(keypair_data)
+}
+
+/// decodes key pair data serialized as [ id_key<32> | public_key<32> ] from a hex string
+/// * odd length, non hex input or a wrong key pair length is rejected with an `InvalidData` error
+pub fn keypair_from_hex(s: &str) -> io::Result<Vec<u8>> {
+    let keypair_data = decode_hex(s)?;
+    if keypair_data.len() != 64 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "key pair must be 64 bytes",
+        ));
+    }
+    Ok(keypair_data)
+}
+
+/// computes the root of the membership tree holding given public keys without creating an `RLN` instance
+/// * expect `commitments` serialized as [ public_key<32> * count ], members take the first `count` indices
+/// * the root is serialized as [ root<32> ] and equals `RLN::get_root` of an instance with the same members
+pub fn compute_root<E: Engine>(
+    merkle_depth: usize,
+    poseidon_params: Option<PoseidonParams<E>>,
+    commitments: &[u8],
+    count: usize,
+) -> io::Result<Vec<u8>> {
+    let mut reader = commitments;
+    let leaves = read_fr::<_, E>(&mut reader, count)?;
+    if !reader.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "trailing bytes after commitments",
+        ));
+    }
+    let poseidon_params = poseidon_params.unwrap_or_else(RLN::<E>::default_poseidon_params);
+    let mut tree = MerkleTree::new(merkle_depth, poseidon_params);
+    tree.update_range(0, leaves)?;
+    let mut root: Vec<u8> = Vec::new();
+    tree.get_root().into_repr().write_le(&mut root)?;
+    Ok(root)
+}
+
+/// decodes a root serialized as [ root<32> ] from a hex string
+/// * odd length, non hex input or a wrong root length is rejected with an `InvalidData` error
+pub fn root_from_hex(s: &str) -> io::Result<Vec<u8>> {
+    let root_data = decode_hex(s)?;
+    if root_data.len() != 32 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "root must be 32 bytes",
+        ));
+    }
+    Ok(root_data)
+}
+
+// proof data of recent proof inputs, the least recently used entry is evicted first
+// * keyed by the sha256 of the serialized proof input followed by the root it was proven against,
+//   so the secret `id_key` of the input is not kept
+struct ProofCache {
+    capacity: usize,
+    entries: HashMap<Vec<u8>, Vec<u8>>,
+    // least recently used first
+    order: VecDeque<Vec<u8>>,
+}
+
+impl ProofCache {
+    fn new(capacity: usize) -> ProofCache {
+        ProofCache {
+            capacity,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    fn get(&mut self, key: &[u8]) -> Option<Vec<u8>> {
+        let proof_data = self.entries.get(key)?.clone();
+        if let Some(position) = self.order.iter().position(|k| k.as_slice() == key) {
+            let key = self.order.remove(position).unwrap();
+            self.order.push_back(key);
+        }
+        Some(proof_data)
+    }
+
+    fn insert(&mut self, key: Vec<u8>, proof_data: Vec<u8>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.insert(key.clone(), proof_data).is_none() {
+            self.order.push_back(key);
+        }
+        self.evict();
+    }
+
+    fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        while self.order.len() > self.capacity {
+            let key = self.order.pop_front().unwrap();
+            self.entries.remove(&key);
+        }
+    }
+}
+
+pub struct RLN<E: Engine> {
+    circuit_parameters: Parameters<E>,
+    // prepared from the verifying key of `circuit_parameters` once at construction
+    prepared_verifying_key: PreparedVerifyingKey<E>,
+    poseidon_params: PoseidonParams<E>,
+    tree: IncrementalMerkleTree<E>,
+    message_limit: u64,
+    // recent roots, the current root is the last one
+    root_history: VecDeque<E::Fr>,
+    root_history_capacity: usize,
+    // distinguishes applications sharing epochs, see `with_rln_identifier`
+    rln_identifier: Option<E::Fr>,
+    // disabled unless a capacity is set, see `set_proof_cache_capacity`
+    proof_cache: Mutex<ProofCache>,
+}
+
+/// configures and creates an `RLN` instance, see `RLN::builder`
+/// * `merkle_depth` must be set, `message_limit` is one unless set
+/// * circuit parameters are generated unless serialized `params` are given
+pub struct RLNBuilder<'a, E: Engine> {
+    merkle_depth: Option<usize>,
+    message_limit: u64,
+    raw_circuit_parameters: Option<&'a [u8]>,
+    poseidon_params: Option<PoseidonParams<E>>,
+    root_history_capacity: usize,
+    unchecked: bool,
+    skip_digest: bool,
+    rln_identifier: Option<E::Fr>,
+}
+
+impl<'a, E: Engine> RLNBuilder<'a, E> {
+    pub fn merkle_depth(mut self, merkle_depth: usize) -> Self {
+        self.merkle_depth = Some(merkle_depth);
+        self
+    }
+
+    pub fn message_limit(mut self, message_limit: u64) -> Self {
+        self.message_limit = message_limit;
+        self
+    }
+
+    /// circuit parameters serialized as the output of `RLN::export_circuit_parameters`
+    pub fn params(mut self, raw_circuit_parameters: &'a [u8]) -> Self {
+        self.raw_circuit_parameters = Some(raw_circuit_parameters);
+        self
+    }
+
+    /// overrides the default poseidon parameters, must match the ones the circuit parameters are generated with
+    /// * the width sets the arity of the membership tree, `width - 1` children hashed in a single permutation,
+    /// so width 3 gives a binary tree and width 5 a tree of arity 4 holding `4 ^ merkle_depth` members
+    /// * any width of at least 3 is supported, building fails if the circuit parameters are generated for another width
+    pub fn poseidon(mut self, poseidon_params: PoseidonParams<E>) -> Self {
+        self.poseidon_params = Some(poseidon_params);
+        self
+    }
+
+    pub fn root_history_capacity(mut self, capacity: usize) -> Self {
+        self.root_history_capacity = capacity;
+        self
+    }
+
+    /// skips the point validity checks while reading `params`
+    /// * loading is considerably faster, parameters of a large circuit take seconds to check
+    /// * only safe for parameters from a trusted source, invalid curve points
+    /// may produce proofs that do not verify or break the soundness of the proving system
+    pub fn unchecked(mut self, unchecked: bool) -> Self {
+        self.unchecked = unchecked;
+        self
+    }
+
+    /// skips checking the trailing digest of `params`, see `write_circuit_parameters`
+    /// * saves hashing the parameters while loading, corrupted parameters are then only
+    /// detected by the point validity checks unless `unchecked` is set too
+    pub fn skip_digest(mut self, skip_digest: bool) -> Self {
+        self.skip_digest = skip_digest;
+        self
+    }
+
+    /// sets the application identifier, see `RLN::with_rln_identifier`
+    pub fn rln_identifier(mut self, rln_identifier: E::Fr) -> Self {
+        self.rln_identifier = Some(rln_identifier);
+        self
+    }
+
+    pub fn build(self) -> Result<RLN<E>, RlnError> {
+        let merkle_depth = match self.merkle_depth {
+            Some(merkle_depth) => merkle_depth,
+            None => {
+                return Err(RlnError::Io(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "merkle depth is not set",
+                )))
+            }
+        };
+        RLN::<E>::check_config(merkle_depth, self.message_limit)?;
+        let rln = match self.raw_circuit_parameters {
+            Some(raw_circuit_parameters) => RLN::read_with_reader(
+                merkle_depth,
+                self.message_limit,
+                raw_circuit_parameters,
+                self.poseidon_params,
+                !self.unchecked,
+                !self.skip_digest,
+            )?,
+            None => RLN::new(merkle_depth, self.message_limit, self.poseidon_params),
+        };
+        let mut rln = rln.with_root_history_capacity(self.root_history_capacity);
+        rln.rln_identifier = self.rln_identifier;
+        Ok(rln)
+    }
+}
+
+/// number of recent roots accepted by `verify_with_recent_root` unless configured otherwise
+pub const DEFAULT_ROOT_HISTORY_CAPACITY: usize = 64;
+
+/// largest number of outputs `RLN::hash_n` squeezes in one call
+pub const MAX_HASH_OUTPUTS: usize = 1 << 16;
+
+impl<E: Engine> RLN<E> {
+    fn default_poseidon_params() -> PoseidonParams<E> {
+        PoseidonParams::<E>::new(8, 55, 3, None, None, None)
+    }
+
+    fn new_circuit(
+        merkle_depth: usize,
+        message_limit: u64,
+        poseidon_params: PoseidonParams<E>,
+    ) -> Parameters<E> {
+        Self::new_circuit_with_rng(
+            merkle_depth,
+            message_limit,
+            poseidon_params,
+            &mut thread_rng(),
+        )
+        .unwrap()
+    }
+
+    fn new_circuit_with_rng<G: Rng>(
+        merkle_depth: usize,
+        message_limit: u64,
+        poseidon_params: PoseidonParams<E>,
+        rng: &mut G,
+    ) -> Result<Parameters<E>, RlnError> {
+        let inputs = RLNInputs::<E>::empty_with_arity(merkle_depth, poseidon_params.width() - 1);
+        let circuit = RLNCircuit::<E> {
+            inputs,
+            hasher: PoseidonCircuit::new(poseidon_params.clone()),
+            message_limit,
+        };
+        Ok(generate_random_parameters(circuit, rng)?)
+    }
+
+    fn new_with_params(
+        merkle_depth: usize,
+        message_limit: u64,
+        circuit_parameters: Parameters<E>,
+        poseidon_params: PoseidonParams<E>,
+    ) -> RLN<E> {
+        let hasher = PoseidonHasher::new(poseidon_params.clone());
+        let tree = IncrementalMerkleTree::empty(hasher, merkle_depth);
+        let mut root_history = VecDeque::with_capacity(DEFAULT_ROOT_HISTORY_CAPACITY);
+        root_history.push_back(tree.get_root());
+        let prepared_verifying_key = prepare_verifying_key(&circuit_parameters.vk);
+        RLN {
+            circuit_parameters,
+            prepared_verifying_key,
+            poseidon_params,
+            tree,
+            message_limit,
+            root_history,
+            root_history_capacity: DEFAULT_ROOT_HISTORY_CAPACITY,
+            rln_identifier: None,
+            proof_cache: Mutex::new(ProofCache::new(0)),
+        }
+    }
+
+    /// sets the application identifier the external nullifier is derived from
+    /// * with an identifier the external nullifier is `hash(epoch, rln_identifier)`,
+    /// so applications signaling in the same epoch get unrelated nullifiers
+    /// * without an identifier the epoch itself is the external nullifier
+    /// * with an identifier the inputs of the verify methods are preceded by the epoch of the signal, see `verify`
+    pub fn with_rln_identifier(mut self, rln_identifier: E::Fr) -> RLN<E> {
+        self.rln_identifier = Some(rln_identifier);
+        self
+    }
+
+    pub fn rln_identifier(&self) -> Option<E::Fr> {
+        self.rln_identifier
+    }
+
+    fn epoch_nullifier(&self, epoch: E::Fr) -> E::Fr {
+        match self.rln_identifier {
+            Some(rln_identifier) => self.hasher().hash(vec![epoch, rln_identifier]),
+            None => epoch,
+        }
+    }
+
+    /// sets the number of recent roots kept for `verify_with_recent_root`
+    /// * the current root is always kept, so `capacity` is at least one
+    pub fn with_root_history_capacity(mut self, capacity: usize) -> RLN<E> {
+        self.set_root_history_capacity(capacity);
+        self
+    }
+
+    pub fn set_root_history_capacity(&mut self, capacity: usize) {
+        self.root_history_capacity = std::cmp::max(capacity, 1);
+        while self.root_history.len() > self.root_history_capacity {
+            self.root_history.pop_front();
+        }
+    }
+
+    pub fn root_history_capacity(&self) -> usize {
+        self.root_history_capacity
+    }
+
+    /// keeps the proof data of up to `capacity` recent inputs of `generate_proof`, zero disables the cache
+    /// * an input proven again against the same root returns the cached proof data byte for byte,
+    /// which reuses the randomness of the cached proof and links the two signals to each other
+    /// * changes of the membership tree change the root, so cached proofs of older roots are not returned
+    /// * each entry retains the proof data and a sha256 digest of the input and root, not the input itself
+    pub fn set_proof_cache_capacity(&mut self, capacity: usize) {
+        self.proof_cache.get_mut().unwrap().set_capacity(capacity);
+    }
+
+    fn record_root(&mut self) {
+        self.root_history.push_back(self.tree.get_root());
+        while self.root_history.len() > self.root_history_capacity {
+            self.root_history.pop_front();
+        }
+    }
+
+    /// creates a new instance with freshly generated circuit parameters
+    /// * `message_limit` is the number of messages a member can send in an epoch and must be positive
+    /// * a `merkle_depth` of 0 admits a single signer at index 0 whose public key is the root
+    pub fn new(
+        merkle_depth: usize,
+        message_limit: u64,
+        poseidon_params: Option<PoseidonParams<E>>,
+    ) -> RLN<E> {
+        assert!(message_limit > 0, "message limit must be positive");
+        let poseidon_params = match poseidon_params {
+            Some(params) => params,
+            None => Self::default_poseidon_params(),
+        };
+        assert!(
+            poseidon_params.width() >= 3,
+            "poseidon width must be at least 3"
+        );
+        let circuit_parameters =
+            Self::new_circuit(merkle_depth, message_limit, poseidon_params.clone());
+        Self::new_with_params(
+            merkle_depth,
+            message_limit,
+            circuit_parameters,
+            poseidon_params,
+        )
+    }
+
+    /// creates a new instance running the trusted setup of the circuit in process with `rng`
+    /// * meant for tests and demos only, whoever knows the randomness of the setup can forge proofs
+    /// and nothing here discards it the way a multi-party ceremony does
+    pub fn new_empty<G: Rng>(
+        merkle_depth: usize,
+        message_limit: u64,
+        poseidon_params: Option<PoseidonParams<E>>,
+        rng: &mut G,
+    ) -> Result<RLN<E>, RlnError> {
+        Self::check_config(merkle_depth, message_limit)?;
+        let poseidon_params = match poseidon_params {
+            Some(params) => params,
+            None => Self::default_poseidon_params(),
+        };
+        Self::check_poseidon(&poseidon_params)?;
+        let circuit_parameters =
+            Self::new_circuit_with_rng(merkle_depth, message_limit, poseidon_params.clone(), rng)?;
+        Ok(Self::new_with_params(
+            merkle_depth,
+            message_limit,
+            circuit_parameters,
+            poseidon_params,
+        ))
+    }
+
+    /// creates a new instance from serialized circuit parameters
+    /// * expect `raw_circuit_parameters` serialized as the output of `export_circuit_parameters`
+    /// * parameters must be generated for the same `merkle_depth` and `message_limit`, otherwise an `InvalidInput` error is returned
+    pub fn new_with_raw_params(
+        merkle_depth: usize,
+        message_limit: u64,
+        raw_circuit_parameters: &[u8],
+        poseidon_params: Option<PoseidonParams<E>>,
+    ) -> Result<RLN<E>, RlnError> {
+        let mut builder = RLN::builder()
+            .merkle_depth(merkle_depth)
+            .message_limit(message_limit)
+            .params(raw_circuit_parameters);
+        builder.poseidon_params = poseidon_params;
+        builder.build()
+    }
+
+    /// same as `new_with_raw_params` with the membership tree built from known public keys
+    /// * expect `commitments` serialized as [ public_key<32> * count ], members get the first `count` indices
+    /// * the tree is built bottom up, hashing each node once
+    pub fn from_commitments(
+        merkle_depth: usize,
+        message_limit: u64,
+        raw_circuit_parameters: &[u8],
+        commitments: &[u8],
+        count: usize,
+    ) -> Result<RLN<E>, RlnError> {
+        let fr_len = <E::Fr as PrimeField>::Repr::default().as_ref().len() * 8;
+        if count.checked_mul(fr_len) != Some(commitments.len()) {
+            return Err(RlnError::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "expected {} commitments of {} bytes, got {} bytes",
+                    count,
+                    fr_len,
+                    commitments.len()
+                ),
+            )));
+        }
+        let mut rln =
+            Self::new_with_raw_params(merkle_depth, message_limit, raw_circuit_parameters, None)?;
+        rln.update_next_batch(commitments, count)?;
+        Ok(rln)
+    }
+
+    pub fn builder<'a>() -> RLNBuilder<'a, E> {
+        RLNBuilder {
+            merkle_depth: None,
+            message_limit: 1,
+            raw_circuit_parameters: None,
+            poseidon_params: None,
+            root_history_capacity: DEFAULT_ROOT_HISTORY_CAPACITY,
+            unchecked: false,
+            skip_digest: false,
+            rln_identifier: None,
+        }
+    }
+
+    // a node of the membership tree hashes `width - 1` children, at least two of them
+    fn check_poseidon(poseidon_params: &PoseidonParams<E>) -> Result<(), RlnError> {
+        if poseidon_params.width() < 3 {
+            return Err(RlnError::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "poseidon width must be at least 3, {} is given",
+                    poseidon_params.width()
+                ),
+            )));
+        }
+        Ok(())
+    }
+
+    fn check_config(merkle_depth: usize, message_limit: u64) -> Result<(), RlnError> {
+        if message_limit == 0 {
+            return Err(RlnError::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "message limit must be positive",
+            )));
+        }
+        if merkle_depth >= std::mem::size_of::<usize>() * 8 {
+            return Err(RlnError::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("merkle depth {} is too large", merkle_depth),
+            )));
+        }
+        Ok(())
+    }
+
+    /// same as `new_with_raw_params` but deserializes circuit parameters while reading them,
+    /// e.g. from a file, without buffering them in memory first
+    pub fn new_with_reader<R: Read>(
+        merkle_depth: usize,
+        message_limit: u64,
+        raw_circuit_parameters: R,
+        poseidon_params: Option<PoseidonParams<E>>,
+    ) -> Result<RLN<E>, RlnError> {
+        Self::read_with_reader(
+            merkle_depth,
+            message_limit,
+            raw_circuit_parameters,
+            poseidon_params,
+            true,
+            true,
+        )
+    }
+
+    /// same as `new_with_raw_params` reading circuit parameters from a memory mapped file
+    /// * pages of the file are loaded by the operating system as the parameters are deserialized,
+    /// the file is never buffered as a whole and is unmapped once the instance is created
+    /// * bellman deserializes the proving key into owned points, so the key itself still takes
+    /// as much memory as with `new_with_raw_params`, only the copy of the file is saved
+    /// * the file must not be modified or truncated while loading, which is undefined behaviour
+    /// on most platforms, and memory mapping is not available on wasm
+    #[cfg(feature = "mmap")]
+    pub fn new_mmap<P: AsRef<Path>>(
+        merkle_depth: usize,
+        message_limit: u64,
+        path: P,
+    ) -> Result<RLN<E>, RlnError> {
+        let file = File::open(path)?;
+        // only read while the parameters are deserialized below
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        Self::new_with_raw_params(merkle_depth, message_limit, &map[..], None)
+    }
+
+    /// same as `new_with_raw_params` but skips the point validity checks of the parameters
+    /// * only use with parameters from a trusted source, see `RLNBuilder::unchecked`
+    pub fn new_with_raw_params_unchecked(
+        merkle_depth: usize,
+        message_limit: u64,
+        raw_circuit_parameters: &[u8],
+        poseidon_params: Option<PoseidonParams<E>>,
+    ) -> Result<RLN<E>, RlnError> {
+        let mut builder = RLN::builder()
+            .merkle_depth(merkle_depth)
+            .message_limit(message_limit)
+            .params(raw_circuit_parameters)
+            .unchecked(true);
+        builder.poseidon_params = poseidon_params;
+        builder.build()
+    }
+
+    fn read_with_reader<R: Read>(
+        merkle_depth: usize,
+        message_limit: u64,
+        raw_circuit_parameters: R,
+        poseidon_params: Option<PoseidonParams<E>>,
+        checked: bool,
+        verify_digest: bool,
+    ) -> Result<RLN<E>, RlnError> {
+        Self::check_config(merkle_depth, message_limit)?;
+        let mut raw_circuit_parameters = DigestReader {
+            inner: raw_circuit_parameters,
+            digest: if verify_digest {
+                Some(Sha256::new())
+            } else {
+                None
+            },
+        };
+        let parameters_depth = raw_circuit_parameters.read_u64::<LittleEndian>()?;
+        if parameters_depth != merkle_depth as u64 {
+            return Err(RlnError::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "merkle depth mismatch, parameters are generated for depth {} but {} is given",
+                    parameters_depth, merkle_depth
+                ),
+            )));
+        }
+        let parameters_message_limit = raw_circuit_parameters.read_u64::<LittleEndian>()?;
+        if parameters_message_limit != message_limit {
+            return Err(RlnError::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "message limit mismatch, parameters are generated for limit {} but {} is given",
+                    parameters_message_limit, message_limit
+                ),
+            )));
+        }
+        let poseidon_params = match poseidon_params {
+            Some(params) => params,
+            None => Self::default_poseidon_params(),
+        };
+        Self::check_poseidon(&poseidon_params)?;
+        let circuit_parameters = Parameters::<E>::read(&mut raw_circuit_parameters, checked)?;
+        if let Some(digest) = raw_circuit_parameters.digest {
+            let mut expected_digest = [0u8; 32];
+            // parameters exported before the digest was appended end here
+            if read_exact_or_eof(&mut raw_circuit_parameters.inner, &mut expected_digest)?
+                && digest.finalize()[..] != expected_digest[..]
+            {
+                return Err(RlnError::Io(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "circuit parameters digest mismatch, the parameters are corrupted",
+                )));
+            }
+        }
+        let rln = Self::new_with_params(
+            merkle_depth,
+            message_limit,
+            circuit_parameters,
+            poseidon_params,
+        );
+        rln.validate_parameters()?;
+        Ok(rln)
+    }
+
+    pub fn merkle_depth(&self) -> usize {
+        self.tree.depth()
+    }
+
+    /// number of members the membership tree can hold, `arity ^ merkle_depth` where `arity` is the poseidon width less one
+    pub fn capacity(&self) -> usize {
+        self.tree.capacity()
+    }
+
+    /// number of inserted members, which is the index the next member is assigned
+    /// * deleted members and slots skipped by `set_leaf` are counted
+    pub fn len(&self) -> usize {
+        self.tree.current_index
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn check_index(&self, index: usize) -> Result<(), RlnError> {
+        if index >= self.capacity() {
+            return Err(RlnError::IndexOutOfRange {
+                index,
+                set_size: self.capacity(),
+            });
+        }
+        Ok(())
+    }
+
+    /// returns current membership root
+    /// * `root` is a scalar field element in 32 bytes
+    pub fn get_root<W: Write>(&self, mut result_data: W) -> Result<(), RlnError> {
+        let root = self.tree.get_root();
+        root.into_repr().write_le(&mut result_data)?;
+        Ok(())
+    }
+
+    /// inserts new member with given public key and returns the index it is assigned
+    /// * `input_data` is a 32 scalar field element in 32 bytes
+    /// * fails with an `InvalidInput` error if `input_data` is not exactly 32 bytes
+    ///   and with `RlnError::NonCanonicalField` if the element is not below the modulus
+    /// * fails with `RlnError::TreeFull` once `len` reaches `capacity`
+    pub fn update_next_member<R: Read>(&mut self, input_data: R) -> Result<usize, RlnError> {
+        let leaf = read_single_fr::<_, E>(input_data)?;
+        if self.len() >= self.capacity() {
+            return Err(RlnError::TreeFull);
+        }
+        let index = self.tree.current_index;
+        self.tree.update_next(leaf)?;
+        self.record_root();
+        Ok(index)
+    }
+
+    /// inserts new members with given public keys
+    /// * `input_data` is `count` scalar field elements, each in 32 bytes
+    pub fn update_next_batch<R: Read>(
+        &mut self,
+        input_data: R,
+        count: usize,
+    ) -> Result<(), RlnError> {
+        let leaves = read_fr::<_, E>(input_data, count)?;
+        if self.len() + count > self.capacity() {
+            return Err(RlnError::TreeFull);
+        }
+        self.tree.update_next_batch(leaves)?;
+        self.record_root();
+        Ok(())
+    }
+
+    /// sets member with given public key at given index
+    /// * `input_data` is a 32 scalar field element in 32 bytes
+    /// * following `update_next_member` calls continue after `index` if it is beyond the current cursor
+    pub fn set_leaf<R: Read>(&mut self, index: usize, input_data: R) -> Result<(), RlnError> {
+        self.check_index(index)?;
+        let leaf = read_fr::<_, E>(input_data, 1)?[0];
+        self.tree.set(index, leaf)?;
+        self.record_root();
+        Ok(())
+    }
+
+    /// sets members with given public keys at given indices and records a single root
+    /// * `input_data` is `indices.len()` scalar field elements, each in 32 bytes
+    /// * ancestors shared by nearby leaves are calculated only once, suiting churny regions of the tree
+    /// * nothing is set if an index is out of range
+    pub fn set_leaves<R: Read>(
+        &mut self,
+        indices: &[usize],
+        input_data: R,
+    ) -> Result<(), RlnError> {
+        for index in indices.iter() {
+            self.check_index(*index)?;
+        }
+        let leaves = read_fr::<_, E>(input_data, indices.len())?;
+        self.tree
+            .set_batch(indices.iter().copied().zip(leaves).collect())?;
+        self.record_root();
+        Ok(())
+    }
+
+    /// deletes member with given index
+    /// * the leaf is set back to the empty leaf and the path up to the root is recalculated
+    pub fn delete_member(&mut self, index: usize) -> Result<(), RlnError> {
+        self.check_index(index)?;
+        self.tree.delete(index)?;
+        self.record_root();
+        Ok(())
+    }
+
+    /// removes all members and moves the insertion cursor back to the first leaf
+    /// * the circuit parameters are kept, the empty root is recorded as a recent root
+    pub fn reset_tree(&mut self) {
+        self.tree = IncrementalMerkleTree::empty(self.hasher(), self.tree.depth());
+        self.record_root();
+    }
+
+    /// returns the index of the member with given public key
+    /// * `leaf_data` is a scalar field element in 32 bytes
+    /// * returns `None` for deleted members and public keys never inserted
+    /// * a public key set at several indices is found at the lowest one still holding it
+    pub fn contains(&self, leaf_data: &[u8]) -> Result<Option<usize>, RlnError> {
+        let leaf = read_single_fr::<_, E>(leaf_data)?;
+        Ok(self.tree.index_of(&leaf))
+    }
+
+    /// writes the public key of the member at given index
+    /// * `result_data` is a scalar field element in 32 bytes
+    /// * fails if `index` is out of range or the slot was never written, deleted members are the empty leaf
+    pub fn get_leaf<W: Write>(&self, index: usize, mut result_data: W) -> Result<(), RlnError> {
+        self.check_index(index)?;
+        let leaf = self.tree.get_leaf(index)?;
+        leaf.into_repr().write_le(&mut result_data)?;
+        Ok(())
+    }
+
+    /// writes the value of empty leaves, the leaves of members never inserted or deleted
+    /// * `result_data` is serialized as [ empty_leaf<32> ], the empty leaf is zero and not a hashed constant
+    /// * the root of an empty tree is the empty leaf hashed with itself once per level
+    pub fn empty_leaf<W: Write>(&self, mut result_data: W) -> Result<(), RlnError> {
+        self.tree
+            .empty_leaf()
+            .into_repr()
+            .write_le(&mut result_data)?;
+        Ok(())
+    }
+
+    /// exports the membership tree
+    /// * `result_data` is serialized as [ merkle_depth<8> | leaf_count<8> | leaf<32> * leaf_count ]
+    /// * `leaf_count` is the insertion cursor, leaves of deleted members are the empty leaf
+    pub fn export_tree<W: Write>(&self, mut result_data: W) -> Result<(), RlnError> {
+        result_data.write_u64::<LittleEndian>(self.tree.depth() as u64)?;
+        self.write_leaves(result_data)
+    }
+
+    // writes [ leaf_count<8> | leaf<32> * leaf_count ]
+    fn write_leaves<W: Write>(&self, mut result_data: W) -> Result<(), RlnError> {
+        let leaves = self.tree.leaves();
+        result_data.write_u64::<LittleEndian>(leaves.len() as u64)?;
+        for leaf in leaves.iter() {
+            leaf.into_repr().write_le(&mut result_data)?;
+        }
+        Ok(())
+    }
+
+    /// replaces the membership tree with an exported one
+    /// * expect `input_data` serialized as the output of `export_tree`
+    /// * the tree must have the same depth as this instance
+    pub fn import_tree<R: Read>(&mut self, mut input_data: R) -> Result<(), RlnError> {
+        let depth = input_data.read_u64::<LittleEndian>()?;
+        if depth != self.tree.depth() as u64 {
+            return Err(RlnError::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "merkle depth mismatch, tree has depth {} but {} is expected",
+                    depth,
+                    self.tree.depth()
+                ),
+            )));
+        }
+        self.read_leaves(input_data)
+    }
+
+    // replaces the membership tree with leaves serialized as the output of `write_leaves`
+    fn read_leaves<R: Read>(&mut self, mut input_data: R) -> Result<(), RlnError> {
+        let leaf_count = input_data.read_u64::<LittleEndian>()? as usize;
+        if leaf_count > self.capacity() {
+            return Err(RlnError::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "leaf count exceeds set size",
+            )));
+        }
+        let leaves = read_fr::<_, E>(input_data, leaf_count)?;
+        let mut tree = IncrementalMerkleTree::empty(self.hasher(), self.tree.depth());
+        tree.update_next_batch(leaves)?;
+        self.tree = tree;
+        self.record_root();
+        Ok(())
+    }
+
+    /// writes the state needed to resume this instance, except the circuit parameters
+    /// * `result_data` is serialized as
+    /// [ merkle_depth<8> | message_limit<8> | has_rln_identifier<1> | rln_identifier<32>? | leaf_count<8> | leaf<32> * leaf_count ]
+    /// * `rln_identifier` is present only if `has_rln_identifier` is `1`, leaves are serialized as in `export_tree`
+    pub fn snapshot<W: Write>(&self, mut result_data: W) -> Result<(), RlnError> {
+        write_parameters_header(&mut result_data, self.tree.depth(), self.message_limit)?;
+        match self.rln_identifier {
+            Some(rln_identifier) => {
+                result_data.write_all(&[1])?;
+                rln_identifier.into_repr().write_le(&mut result_data)?;
+            }
+            None => result_data.write_all(&[0])?,
+        }
+        self.write_leaves(result_data)
+    }
+
+    /// resumes an instance from the output of `snapshot`
+    /// * expect `raw_circuit_parameters` serialized as the output of `export_circuit_parameters`,
+    /// generated for the merkle depth and message limit of the snapshot
+    /// * default poseidon parameters are used and the root history holds only the restored root
+    pub fn restore<R: Read>(
+        raw_circuit_parameters: &[u8],
+        mut snapshot: R,
+    ) -> Result<RLN<E>, RlnError> {
+        let merkle_depth = snapshot.read_u64::<LittleEndian>()? as usize;
+        let message_limit = snapshot.read_u64::<LittleEndian>()?;
+        let mut builder = RLN::builder()
+            .merkle_depth(merkle_depth)
+            .message_limit(message_limit)
+            .params(raw_circuit_parameters);
+        match snapshot.read_u8()? {
+            0 => {}
+            1 => builder = builder.rln_identifier(read_fr::<_, E>(&mut snapshot, 1)?[0]),
+            _ => {
+                return Err(RlnError::Io(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "invalid rln identifier flag",
+                )))
+            }
+        }
+        let mut rln = builder.build()?;
+        rln.read_leaves(snapshot)?;
+        Ok(rln)
+    }
+
+    /// returns the authentication path of the member with given index
+    /// * `result_data` is serialized as [ sibling<32> * path_len | direction<1> * path_len ]
+    /// * siblings are ordered from the leaf to the root, direction is `1` if the sibling is right of the node on the path
+    /// * `path_len` is `merkle_depth` for the binary tree of a width 3 poseidon,
+    /// each level of a tree of higher arity holds its `arity - 1` siblings in order
+    pub fn get_merkle_proof<W: Write>(
+        &self,
+        index: usize,
+        mut result_data: W,
+    ) -> Result<(), RlnError> {
+        let witness = self.tree.get_witness(index)?;
+        for w in witness.iter() {
+            w.0.into_repr().write_le(&mut result_data)?;
+        }
+        for w in witness.iter() {
+            result_data.write_all(&[w.1 as u8])?;
+        }
+        Ok(())
+    }
+
+    /// checks an authentication path against a root
+    /// * expect `input_data` serialized as [ root<32> | leaf<32> | merkle_proof<var> ]
+    /// * `merkle_proof` is serialized as the output of `get_merkle_proof`
+    pub fn verify_merkle_proof<R: Read>(&self, mut input_data: R) -> Result<bool, RlnError> {
+        let elements = read_fr::<_, E>(&mut input_data, 2)?;
+        let witness = self.read_merkle_proof(input_data)?;
+        Ok(verify_witness(
+            &self.hasher(),
+            elements[0],
+            elements[1],
+            &witness,
+        ))
+    }
+
+    // reads an authentication path serialized as the output of `get_merkle_proof`
+    fn read_merkle_proof<R: Read>(
+        &self,
+        mut input_data: R,
+    ) -> Result<Vec<(E::Fr, bool)>, RlnError> {
+        let path_len = self.tree.depth() * (self.tree.arity() - 1);
+        let siblings = read_fr::<_, E>(&mut input_data, path_len)?;
+        let mut directions = vec![0u8; path_len];
+        input_data.read_exact(&mut directions)?;
+
+        let mut witness = Vec::<(E::Fr, bool)>::with_capacity(path_len);
+        for (sibling, direction) in siblings.iter().zip(directions.into_iter()) {
+            let direction = match direction {
+                0u8 => false,
+                1u8 => true,
+                _ => {
+                    return Err(RlnError::Io(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "invalid path direction",
+                    )))
+                }
+            };
+            witness.push((*sibling, direction));
+        }
+        Ok(witness)
+    }
+
+    /// proves that the current root is obtained from `old_root` by inserting the member at `index`
+    /// * expect `old_root` serialized as [ root<32> ]
+    /// * `result_data` is serialized as the output of `get_merkle_proof`, the path is shared by both roots
+    /// * fails with `RlnError::RootMismatch` if the leaf at `index` is not the only change since `old_root`
+    pub fn prove_insertion<W: Write>(
+        &self,
+        index: usize,
+        old_root: &[u8],
+        result_data: W,
+    ) -> Result<(), RlnError> {
+        self.check_index(index)?;
+        let old_root = read_single_fr::<_, E>(old_root)?;
+        let witness = self.tree.get_witness(index)?;
+        if !verify_witness(&self.hasher(), old_root, E::Fr::zero(), &witness) {
+            return Err(RlnError::RootMismatch);
+        }
+        self.get_merkle_proof(index, result_data)
+    }
+
+    /// checks that `new_root` is obtained from `old_root` by inserting `leaf` into an empty slot
+    /// * expect `old_root`, `new_root` and `leaf` serialized as a single field element in 32 bytes
+    /// * expect `witness` serialized as the output of `prove_insertion`
+    pub fn verify_insertion(
+        &self,
+        old_root: &[u8],
+        new_root: &[u8],
+        leaf: &[u8],
+        witness: &[u8],
+    ) -> Result<bool, RlnError> {
+        let old_root = read_single_fr::<_, E>(old_root)?;
+        let new_root = read_single_fr::<_, E>(new_root)?;
+        let leaf = read_single_fr::<_, E>(leaf)?;
+        let witness = self.read_merkle_proof(witness)?;
+        let hasher = self.hasher();
+        Ok(verify_witness(&hasher, old_root, E::Fr::zero(), &witness)
+            && verify_witness(&hasher, new_root, leaf, &witness))
+    }
+
+    /// hashes field elements with the poseidon sponge
+    /// * expect `input_data` serialized as [ input<32> * n_inputs ]
+    /// * `result_data` is serialized as [ output<32> * n_outputs ]
+    /// * `n_outputs` above `MAX_HASH_OUTPUTS` is rejected with an `InvalidInput` error
+    pub fn hash_n<R: Read, W: Write>(
+        &self,
+        input_data: R,
+        n_inputs: usize,
+        n_outputs: usize,
+        mut result_data: W,
+    ) -> Result<(), RlnError> {
+        if n_outputs > MAX_HASH_OUTPUTS {
+            return Err(RlnError::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "{} outputs requested, at most {} are supported",
+                    n_outputs, MAX_HASH_OUTPUTS
+                ),
+            )));
+        }
+        let inputs = read_fr::<_, E>(input_data, n_inputs)?;
+        for output in self.hasher().hash_n(inputs, n_outputs).iter() {
+            output.into_repr().write_le(&mut result_data)?;
+        }
+        Ok(())
+    }
+
+    /// hashes field elements with the poseidon sponge of `poseidon_params` instead of the ones of the circuit
+    /// * expect `input_data` serialized as [ input<32> * n_inputs ]
+    /// * `result_data` is serialized as [ output<32> ]
+    /// * the hash matches the one computed in the circuit only for the poseidon parameters of the circuit
+    /// * `n_inputs` may not exceed the rate `width - 1`, more inputs fail with an `InvalidInput` error
+    ///   since they would not be absorbed, see `hash_n` for longer inputs
+    pub fn hash_with_params<R: Read, W: Write>(
+        &self,
+        input_data: R,
+        n_inputs: usize,
+        poseidon_params: PoseidonParams<E>,
+        mut result_data: W,
+    ) -> Result<(), RlnError> {
+        Self::check_poseidon(&poseidon_params)?;
+        let rate = poseidon_params.width() - 1;
+        if n_inputs > rate {
+            return Err(RlnError::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} inputs exceed the poseidon rate of {}", n_inputs, rate),
+            )));
+        }
+        let inputs = read_fr::<_, E>(input_data, n_inputs)?;
+        PoseidonHasher::new(poseidon_params)
+            .hash(inputs)
+            .into_repr()
+            .write_le(&mut result_data)?;
+        Ok(())
+    }
+
+    /// hashes plain text to a field element
+    pub fn signal_to_field<R: Read, W: Write>(
+        &self,
+        input_data: R,
+        mut result_data: W,
+    ) -> Result<(), RlnError> {
+        let result = read_signal_hash::<R, E>(input_data)?;
+        result.into_repr().write_le(&mut result_data)?;
+        Ok(())
+    }
+
+    /// hashes a message of any length to a field element with the poseidon sponge
+    /// * the sponge absorbs the message length and then the message in little endian chunks of `(NUM_BITS - 1) / 8` bytes
+    /// * `result_data` is serialized as [ hash<32> ], a value suitable as the signal hash of `RLNSignal`
+    pub fn hash_message<W: Write>(
+        &self,
+        message: &[u8],
+        mut result_data: W,
+    ) -> Result<(), RlnError> {
+        let mut inputs: Vec<E::Fr> = Vec::new();
+        Self::absorb_bytes(message, &mut inputs)?;
+        self.hasher().hash_n(inputs, 1)[0]
+            .into_repr()
+            .write_le(&mut result_data)?;
+        Ok(())
+    }
+
+    /// hashes a message made of several parts, e.g. a payload and its topic, to a field element with the poseidon sponge
+    /// * the sponge absorbs the number of parts and then each part as in `hash_message`,
+    /// so the lengths keep apart parts that concatenate to the same bytes
+    /// * `result_data` is serialized as [ hash<32> ]
+    pub fn signal_hash<W: Write>(
+        &self,
+        parts: &[&[u8]],
+        mut result_data: W,
+    ) -> Result<(), RlnError> {
+        // a u64 is always below the modulus
+        let mut inputs: Vec<E::Fr> =
+            vec![E::Fr::from_repr(<E::Fr as PrimeField>::Repr::from(parts.len() as u64)).unwrap()];
+        for part in parts.iter() {
+            Self::absorb_bytes(part, &mut inputs)?;
+        }
+        self.hasher().hash_n(inputs, 1)[0]
+            .into_repr()
+            .write_le(&mut result_data)?;
+        Ok(())
+    }
+
+    // appends the length of `bytes` and then `bytes` in little endian chunks of `(NUM_BITS - 1) / 8` bytes
+    fn absorb_bytes(bytes: &[u8], inputs: &mut Vec<E::Fr>) -> io::Result<()> {
+        let chunk_len = (E::Fr::NUM_BITS as usize - 1) / 8;
+        inputs.reserve(bytes.len() / chunk_len + 2);
+        // a u64 is always below the modulus
+        inputs
+            .push(E::Fr::from_repr(<E::Fr as PrimeField>::Repr::from(bytes.len() as u64)).unwrap());
+        for chunk in bytes.chunks(chunk_len) {
+            let mut repr = <E::Fr as PrimeField>::Repr::default();
+            let mut chunk_data = vec![0u8; repr.as_ref().len() * 8];
+            chunk_data[..chunk.len()].copy_from_slice(chunk);
+            repr.read_le(chunk_data.as_slice())?;
+            // a chunk is shorter than the modulus
+            inputs.push(E::Fr::from_repr(repr).unwrap());
+        }
+        Ok(())
+    }
+
+    /// computes the external nullifier proofs of given epoch carry in place of the epoch
+    /// * expect `input_data` serialized as [ epoch<32> ]
+    /// * `result_data` is serialized as [ external_nullifier<32> ], see `with_rln_identifier`
+    pub fn external_nullifier<R: Read, W: Write>(
+        &self,
+        input_data: R,
+        mut result_data: W,
+    ) -> Result<(), RlnError> {
+        let epoch = read_fr::<_, E>(input_data, 1)?[0];
+        self.epoch_nullifier(epoch)
+            .into_repr()
+            .write_le(&mut result_data)?;
+        Ok(())
+    }
+
+    /// computes the epoch of the time window `unix_seconds` falls in
+    /// * `result_data` is serialized as [ epoch<32> ], `epoch = hash(unix_seconds / window_seconds)`
+    /// * the epoch is used as the epoch of `generate_proof` input
+    pub fn epoch_from_timestamp<W: Write>(
+        &self,
+        unix_seconds: u64,
+        window_seconds: u64,
+        mut result_data: W,
+    ) -> Result<(), RlnError> {
+        if window_seconds == 0 {
+            return Err(RlnError::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "epoch window must not be zero",
+            )));
+        }
+        let window = <E::Fr as PrimeField>::Repr::from(unix_seconds / window_seconds);
+        // a u64 is always below the modulus
+        let window = E::Fr::from_repr(window).unwrap();
+        self.hasher()
+            .hash(vec![window])
+            .into_repr()
+            .write_le(&mut result_data)?;
+        Ok(())
+    }
+
+    /// a_1 = hash(id_key, hash(epoch, message_id)) as constrained in the circuit
+    /// * `epoch` is the external nullifier
+    fn line_slope(&self, id_key: E::Fr, epoch: E::Fr, message_id: E::Fr) -> E::Fr {
+        let hasher = self.hasher();
+        let external_nullifier = hasher.hash(vec![epoch, message_id]);
+        hasher.hash(vec![id_key, external_nullifier])
+    }
+
+    /// computes the nullifier a proof for given inputs would carry
+    /// * expect `input_data` serialized as [ id_key<32> | epoch<32> | message_id<32> ]
+    /// * `result_data` is serialized as [ nullifier<32> ], `nullifier = hash(hash(id_key, hash(epoch, message_id)))`
+    pub fn compute_nullifier<R: Read, W: Write>(
+        &self,
+        input_data: R,
+        mut result_data: W,
+    ) -> Result<(), RlnError> {
+        let inputs = read_fr::<_, E>(input_data, 3)?;
+        let epoch = self.epoch_nullifier(inputs[1]);
+        let a_1 = self.line_slope(inputs[0], epoch, inputs[2]);
+        let nullifier = self.hasher().hash(vec![a_1]);
+        nullifier.into_repr().write_le(&mut result_data)?;
+        Ok(())
+    }
+
+    /// computes the shamir share of `id_key` a proof for given inputs would carry
+    /// * expect `input_data` serialized as [ id_key<32> | epoch<32> | message_id<32> | share_x<32> ]
+    /// * `share_x` is the signal hash, see `signal_to_field`
+    /// * `result_data` is serialized as [ share_y<32> ], `share_y = id_key + hash(id_key, hash(epoch, message_id)) * share_x`
+    /// as constrained in the circuit, with the external nullifier in place of `epoch`
+    pub fn shamir_share<R: Read, W: Write>(
+        &self,
+        input_data: R,
+        mut result_data: W,
+    ) -> Result<(), RlnError> {
+        let inputs = read_fr::<_, E>(input_data, 4)?;
+        let epoch = self.epoch_nullifier(inputs[1]);
+        let (share_y, _) = self.line_witness(inputs[0], epoch, inputs[2], inputs[3]);
+        share_y.into_repr().write_le(&mut result_data)?;
+        Ok(())
+    }
+
+    /// given public inputs and autharization data generates public inputs and proof
+    /// * expect `input_data`  serialized as  [ id_key<32> | id_index<8> | epoch<32> | message_id<32> | signal_len<8> | signal<var> ]
+    /// * `result_data` is proof data serialized as [ magic<4> | version<1> | proof<256>| root<32>| epoch<32>| share_x<32>| share_y<32>| nullifier<32> ]
+    /// * `magic` is `RLNP` and `version` is `PROOF_VERSION`, `proof` is the uncompressed groth16 proof [ a<64> | b<128> | c<64> ]
+    /// * field elements are 32 bytes little-endian, `nullifier = hash(hash(id_key, hash(epoch, message_id)))`
+    /// * `(share_x, share_y)` is the shamir share of `id_key`, `share_x` is the signal hash and `share_y = id_key + a_1 * share_x`
+    /// * `message_id` must be below the message limit and `id_index` below `capacity`
+    /// * fails with a `NotFound` error if no member was ever inserted at `id_index`
+    /// * the `epoch` of the output is the external nullifier, which differs from the input epoch if an identifier is set
+    /// * if an identifier is set, see `with_rln_identifier`, the input is preceded by [ rln_identifier<32> ]
+    /// and fails with `RlnError::IdentifierMismatch` unless it is the identifier of this instance
+    /// * proofs are returned from the cache if enabled, see `set_proof_cache_capacity`
+    pub fn generate_proof<R: Read, W: Write>(
+        &self,
+        mut input_data: R,
+        mut result_data: W,
+    ) -> Result<(), RlnError> {
+        self.check_rln_identifier(&mut input_data)?;
+        if self.proof_cache.lock().unwrap().capacity == 0 {
+            return self.generate_proof_with_rng(input_data, &mut thread_rng(), result_data);
+        }
+
+        let mut input: Vec<u8> = Vec::new();
+        input_data.read_to_end(&mut input)?;
+        let mut root: Vec<u8> = Vec::new();
+        self.tree.get_root().into_repr().write_le(&mut root)?;
+        let mut digest = Sha256::new();
+        digest.update(&input);
+        digest.update(&root);
+        let key = digest.finalize().to_vec();
+        let cached = self.proof_cache.lock().unwrap().get(&key);
+        let proof_data = match cached {
+            Some(proof_data) => Ok(proof_data),
+            None => {
+                let mut proof_data: Vec<u8> = Vec::new();
+                self.generate_proof_with_rng(input.as_slice(), &mut thread_rng(), &mut proof_data)
+                    .map(|_| {
+                        // the lock is not held while proving
+                        self.proof_cache
+                            .lock()
+                            .unwrap()
+                            .insert(key, proof_data.clone());
+                        proof_data
+                    })
+            }
+        };
+        // the input starts with the secret `id_key`
+        #[cfg(feature = "zeroize")]
+        {
+            use zeroize_crate::Zeroize;
+            input.zeroize();
+        }
+        result_data.write_all(&proof_data?)?;
+        Ok(())
+    }
+
+    /// same as `generate_proof` recording the durations of witness generation and proving in `timings`
+    /// * the proof cache is bypassed, so that both phases are always measured
+    #[cfg(feature = "metrics")]
+    pub fn generate_proof_with_timings<R: Read, W: Write>(
+        &self,
+        mut input_data: R,
+        result_data: W,
+        timings: &mut ProofTimings,
+    ) -> Result<(), RlnError> {
+        let start = std::time::Instant::now();
+        self.check_rln_identifier(&mut input_data)?;
+        let id_key: E::Fr = read_fr::<_, E>(&mut input_data, 1)?[0];
+        let id_index = input_data.read_u64::<LittleEndian>()? as usize;
+        let inputs = self.signal_witness(id_key, id_index, input_data)?;
+        let witnessed = std::time::Instant::now();
+        self.prove_inputs(inputs, &mut thread_rng(), result_data)?;
+        timings.witness_ns = (witnessed - start).as_nanos() as u64;
+        timings.prove_ns = witnessed.elapsed().as_nanos() as u64;
+        Ok(())
+    }
+
+    /// generates a proof and verifies it right away, meant for tests and demos
+    /// * expect `input_data` serialized as in `generate_proof`
+    /// * returns the result of `verify` for the proof data followed by the signal of the input
+    pub fn prove_and_verify(&self, input_data: &[u8]) -> Result<bool, RlnError> {
+        // [ id_key<32> | id_index<8> | epoch<32> | message_id<32> ] precede the signal,
+        // after the identifier if one is set
+        let fr_len = <E::Fr as PrimeField>::Repr::default().as_ref().len() * 8;
+        let mut proof_data: Vec<u8> = Vec::new();
+        self.generate_proof(input_data, &mut proof_data)?;
+        let mut verify_data: Vec<u8> = Vec::new();
+        let offset = match self.rln_identifier {
+            Some(_) => {
+                verify_data.extend_from_slice(&input_data[2 * fr_len + 8..3 * fr_len + 8]);
+                fr_len
+            }
+            None => 0,
+        };
+        verify_data.extend(proof_data);
+        verify_data.extend_from_slice(&input_data[offset + 3 * fr_len + 8..]);
+        self.verify(verify_data.as_slice())
+    }
+
+    /// checks whether the witness of a proof input satisfies the circuit, without proving
+    /// * expect `input_data` serialized as in `generate_proof`, malformed input fails as there
+    /// * returns false for an unsatisfied witness, for example if `id_key` is not the key of the member at `id_index`
+    pub fn check_witness<R: Read>(&self, mut input_data: R) -> Result<bool, RlnError> {
+        self.check_rln_identifier(&mut input_data)?;
+        let id_key: E::Fr = read_fr::<_, E>(&mut input_data, 1)?[0];
+        let id_index = input_data.read_u64::<LittleEndian>()? as usize;
+        let inputs = self.signal_witness(id_key, id_index, input_data)?;
+        let circuit = RLNCircuit {
+            inputs,
+            hasher: PoseidonCircuit::new(self.poseidon_params.clone()),
+            message_limit: self.message_limit,
+        };
+        let mut cs = TestConstraintSystem::<E>::new();
+        circuit.synthesize(&mut cs)?;
+        Ok(cs.is_satisfied())
+    }
+
+    // reads the identifier preceding the input of `generate_proof` if one is set,
+    // so that a signal meant for another application is not proven
+    fn check_rln_identifier<R: Read>(&self, mut input_data: R) -> Result<(), RlnError> {
+        if let Some(rln_identifier) = self.rln_identifier {
+            if read_fr::<_, E>(&mut input_data, 1)?[0] != rln_identifier {
+                return Err(RlnError::IdentifierMismatch);
+            }
+        }
+        Ok(())
+    }
+
+    /// same as `generate_proof` writing the public inputs of the proof data in the given byte order
+    pub fn generate_proof_endian<R: Read, W: Write>(
+        &self,
+        input_data: R,
+        endian: Endian,
+        mut result_data: W,
+    ) -> Result<(), RlnError> {
+        let mut proof_data: Vec<u8> = Vec::new();
+        self.generate_proof(input_data, &mut proof_data)?;
+        if endian == Endian::Big {
+            flip_public_inputs::<E>(&mut proof_data)?;
+        }
+        result_data.write_all(&proof_data)?;
+        Ok(())
+    }
+
+    /// same as `generate_proof` taking the randomness of the proof from `rng`
+    /// * the same `rng` state and inputs give the same proof, meant for test vectors
+    pub fn generate_proof_with_rng<R: Read, W: Write, G: Rng>(
+        &self,
+        mut input_data: R,
+        rng: &mut G,
+        result_data: W,
+    ) -> Result<(), RlnError> {
+        let id_key: E::Fr = read_fr::<_, E>(&mut input_data, 1)?[0];
+        let id_index = input_data.read_u64::<LittleEndian>()? as usize;
+        self.prove(id_key, id_index, input_data, rng, result_data)
+    }
+
+    /// generates a proof for the member at `id_index`, see `generate_proof`
+    /// * expect `input_data` serialized as [ id_key<32> | epoch<32> | message_id<32> | signal_len<8> | signal<var> ]
+    /// * `result_data` is serialized as the output of `generate_proof`
+    pub fn generate_proof_at<R: Read, W: Write>(
+        &self,
+        id_index: usize,
+        mut input_data: R,
+        result_data: W,
+    ) -> Result<(), RlnError> {
+        let id_key: E::Fr = read_fr::<_, E>(&mut input_data, 1)?[0];
+        self.prove(id_key, id_index, input_data, &mut thread_rng(), result_data)
+    }
+
+    /// generates a proof of the same signal for each of `count` epochs, e.g. for upcoming epochs
+    /// * expect `input_data` serialized as [ id_key<32> | id_index<8> | message_id<32> | signal_hash<32> | epoch<32> * count ]
+    /// * `signal_hash` is the `hash` of the `RLNSignal`, the share x of every proof
+    /// * `result_data` is serialized as [ proof_len<8> | proof_data<proof_len> ] * count,
+    /// where `proof_data` is the output of `generate_proof` for the epoch at the same position
+    /// * the membership witness is computed once for all epochs
+    pub fn generate_proofs_for_epochs<R: Read, W: Write>(
+        &self,
+        mut input_data: R,
+        count: usize,
+        mut result_data: W,
+    ) -> Result<(), RlnError> {
+        let id_key: E::Fr = read_fr::<_, E>(&mut input_data, 1)?[0];
+        let id_index = input_data.read_u64::<LittleEndian>()? as usize;
+        self.check_index(id_index)?;
+        let elements = read_fr::<_, E>(&mut input_data, 2)?;
+        let (message_id, share_x) = (elements[0], elements[1]);
+        self.check_message_id(message_id)?;
+        let epochs = read_fr::<_, E>(input_data, count)?;
+
+        let membership = self.membership_witness(id_index)?;
+        let mut rng = thread_rng();
+        for epoch in epochs.into_iter() {
+            let epoch = self.epoch_nullifier(epoch);
+            let line = self.line_witness(id_key, epoch, message_id, share_x);
+            let inputs =
+                Self::witness_inputs(id_key, epoch, message_id, share_x, line, membership.clone());
+            let mut proof_data: Vec<u8> = Vec::new();
+            self.prove_inputs(inputs, &mut rng, &mut proof_data)?;
+            result_data.write_u64::<LittleEndian>(proof_data.len() as u64)?;
+            result_data.write_all(&proof_data)?;
+        }
+        Ok(())
+    }
+
+    // evaluates the line equation at `share_x`, returns the share and the nullifier
+    fn line_witness(
+        &self,
+        id_key: E::Fr,
+        epoch: E::Fr,
+        message_id: E::Fr,
+        share_x: E::Fr,
+    ) -> (E::Fr, E::Fr) {
+        let a_0 = id_key;
+        let a_1 = self.line_slope(id_key, epoch, message_id);
+        let mut share_y = a_1;
+        share_y.mul_assign(&share_x);
+        share_y.add_assign(&a_0);
+        let nullifier = self.hasher().hash(vec![a_1]);
+        (share_y, nullifier)
+    }
+
+    // the root and the authentication path of the member at `id_index`
+    fn membership_witness(&self, id_index: usize) -> io::Result<(E::Fr, Vec<(E::Fr, bool)>)> {
+        // TODO: check id key here
+        // a proof for a slot never inserted is a proof for the empty leaf no member holds
+        self.tree.get_leaf(id_index)?;
+        let auth_path = self.tree.get_witness(id_index)?;
+        Ok((self.tree.get_root(), auth_path))
+    }
+
+    fn witness_inputs(
+        id_key: E::Fr,
+        epoch: E::Fr,
+        message_id: E::Fr,
+        share_x: E::Fr,
+        line: (E::Fr, E::Fr),
+        membership: (E::Fr, Vec<(E::Fr, bool)>),
+    ) -> RLNInputs<E> {
+        let (share_y, nullifier) = line;
+        let (root, auth_path) = membership;
+        RLNInputs::<E> {
+            share_x: Some(share_x),
+            share_y: Some(share_y),
+            epoch: Some(epoch),
+            nullifier: Some(nullifier),
+            root: Some(root),
+            id_key: Some(id_key),
+            message_id: Some(message_id),
+            auth_path: auth_path.into_iter().map(|w| Some(w)).collect(),
+        }
+    }
+
+    // assigns the circuit inputs of a proof
+    #[cfg_attr(all(feature = "parallel", not(test)), allow(dead_code))]
+    pub(crate) fn sequential_witness(
+        &self,
+        id_key: E::Fr,
+        id_index: usize,
+        epoch: E::Fr,
+        message_id: E::Fr,
+        share_x: E::Fr,
+    ) -> io::Result<RLNInputs<E>> {
+        let line = self.line_witness(id_key, epoch, message_id, share_x);
+        let membership = self.membership_witness(id_index)?;
+        Ok(Self::witness_inputs(
+            id_key, epoch, message_id, share_x, line, membership,
+        ))
+    }
+
+    // same as `sequential_witness`, the hashes of the line equation and the authentication path
+    // do not depend on each other and are computed on the rayon thread pool
+    #[cfg(feature = "parallel")]
+    pub(crate) fn parallel_witness(
+        &self,
+        id_key: E::Fr,
+        id_index: usize,
+        epoch: E::Fr,
+        message_id: E::Fr,
+        share_x: E::Fr,
+    ) -> io::Result<RLNInputs<E>> {
+        let (line, membership) = rayon::join(
+            || self.line_witness(id_key, epoch, message_id, share_x),
+            || self.membership_witness(id_index),
+        );
+        Ok(Self::witness_inputs(
+            id_key,
+            epoch,
+            message_id,
+            share_x,
+            line,
+            membership?,
+        ))
+    }
+
+    // reads the signal of a proof input and proves for the member at `id_index`
+    fn prove<R: Read, W: Write, G: Rng>(
+        &self,
+        id_key: E::Fr,
+        id_index: usize,
+        input_data: R,
+        rng: &mut G,
+        result_data: W,
+    ) -> Result<(), RlnError> {
+        let inputs = self.signal_witness(id_key, id_index, input_data)?;
+        self.prove_inputs(inputs, rng, result_data)
+    }
+
+    // reads the signal of a proof input and assigns the circuit inputs for the member at `id_index`
+    fn signal_witness<R: Read>(
+        &self,
+        id_key: E::Fr,
+        id_index: usize,
+        input_data: R,
+    ) -> Result<RLNInputs<E>, RlnError> {
+        self.check_index(id_index)?;
+
+        let signal = RLNSignal::<E>::read(input_data)?;
+        self.check_message_id(signal.message_id)?;
+
+        let epoch = self.epoch_nullifier(signal.epoch);
+        #[cfg(feature = "parallel")]
+        let inputs =
+            self.parallel_witness(id_key, id_index, epoch, signal.message_id, signal.hash)?;
+        #[cfg(not(feature = "parallel"))]
+        let inputs =
+            self.sequential_witness(id_key, id_index, epoch, signal.message_id, signal.hash)?;
+        Ok(inputs)
+    }
+
+    fn check_message_id(&self, message_id: E::Fr) -> Result<(), RlnError> {
+        if message_id.into_repr() >= <E::Fr as PrimeField>::Repr::from(self.message_limit) {
+            return Err(RlnError::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "message id exceeds message limit",
+            )));
+        }
+        Ok(())
+    }
+
+    // proves for the circuit inputs assigned by `signal_witness` and writes the proof data
+    fn prove_inputs<W: Write, G: Rng>(
+        &self,
+        inputs: RLNInputs<E>,
+        rng: &mut G,
+        mut result_data: W,
+    ) -> Result<(), RlnError> {
+        // all inputs are assigned by `signal_witness`
+        let root = inputs.root.unwrap();
+        let epoch = inputs.epoch.unwrap();
+        let share_x = inputs.share_x.unwrap();
+        let share_y = inputs.share_y.unwrap();
+        let nullifier = inputs.nullifier.unwrap();
+
+        let circuit = RLNCircuit {
+            inputs: inputs.clone(),
+            hasher: PoseidonCircuit::new(self.poseidon_params.clone()),
+            message_limit: self.message_limit,
+        };
+
+        let proof = create_random_proof(circuit, &self.circuit_parameters, rng)
+            .map_err(RlnError::Proving)?;
+        write_proof_header(&mut result_data)?;
+        write_uncompressed_proof(proof.clone(), &mut result_data)?;
+        root.into_repr().write_le(&mut result_data)?;
+        epoch.into_repr().write_le(&mut result_data)?;
+        share_x.into_repr().write_le(&mut result_data)?;
+        share_y.into_repr().write_le(&mut result_data)?;
+        nullifier.into_repr().write_le(&mut result_data)?;
+
+        Ok(())
+    }
+
+    /// given proof and public data verifies the signal
+    /// * expect `input_data` is serialized as:
+    /// [ magic<4> | version<1> | proof<256>| root<32>| epoch<32>| share_x<32>| share_y<32>| nullifier<32> | signal_len<8> | signal<var> ]
+    /// * data without a recognized header or version is rejected with an `InvalidData` error
+    /// * returns `Ok(false)` only for well formed data whose proof does not verify,
+    /// truncated data, points not on the curve and non canonical field elements are errors
+    /// * if an identifier is set, see `with_rln_identifier`, the input is preceded by [ epoch<32> ]
+    /// and fails with `RlnError::IdentifierMismatch` unless the epoch field of the proof is `hash(epoch, rln_identifier)`,
+    /// so proofs of other applications in the same epoch do not verify
+    pub fn verify<R: Read>(&self, input_data: R) -> Result<bool, RlnError> {
+        let (proof, public_inputs) = self.read_verify_data(input_data)?;
+        check_proof(&self.prepared_verifying_key, &proof, &public_inputs)
+    }
+
+    // reads the input of `verify`, checking the external nullifier if an identifier is set
+    fn read_verify_data<R: Read>(
+        &self,
+        mut input_data: R,
+    ) -> Result<(Proof<E>, Vec<E::Fr>), RlnError> {
+        let epoch = match self.rln_identifier {
+            Some(_) => Some(read_fr::<_, E>(&mut input_data, 1)?[0]),
+            None => None,
+        };
+        let (proof, public_inputs) = read_verify_data::<_, E>(input_data)?;
+        if let (Some(rln_identifier), Some(epoch)) = (self.rln_identifier, epoch) {
+            check_external_nullifier(&self.hasher(), epoch, rln_identifier, public_inputs[1])?;
+        }
+        Ok((proof, public_inputs))
+    }
+
+    /// decodes the public inputs of proof data without verifying the proof
+    /// * expect `proof_data` serialized as the output of `generate_proof`
+    pub fn decode_public_inputs<R: Read>(
+        &self,
+        proof_data: R,
+    ) -> Result<PublicInputs<E>, RlnError> {
+        Ok(PublicInputs::read(proof_data)?)
+    }
+
+    /// verifies the signal and checks its nullifier against nullifiers seen before
+    /// * expect `input_data` serialized as in `verify`
+    /// * expect each of `seen_nullifiers` serialized as [ nullifier<32> ]
+    pub fn verify_and_check_nullifier<R: Read>(
+        &self,
+        input_data: R,
+        seen_nullifiers: &[&[u8]],
+    ) -> Result<VerifyOutcome, RlnError> {
+        let (proof, public_inputs) = self.read_verify_data(input_data)?;
+        if !check_proof(&self.prepared_verifying_key, &proof, &public_inputs)? {
+            return Ok(VerifyOutcome::Invalid);
+        }
+        let mut nullifier: Vec<u8> = Vec::new();
+        public_inputs[4].into_repr().write_le(&mut nullifier)?;
+        for seen in seen_nullifiers.iter() {
+            if nullifier_eq::<E>(seen, &nullifier)? {
+                return Ok(VerifyOutcome::DuplicateNullifier);
+            }
+        }
+        Ok(VerifyOutcome::Valid)
+    }
+
+    /// verifies a proof against public inputs held by the caller
+    /// * expect `proof_data` serialized as [ proof<256> ], the proof of `generate_proof` output without the header
+    /// * `epoch` is the epoch of the signal, the epoch field of the proof data is derived from it as in `external_nullifier`
+    pub fn verify_with_inputs(
+        &self,
+        proof_data: &[u8],
+        root: E::Fr,
+        epoch: E::Fr,
+        nullifier: E::Fr,
+        share_x: E::Fr,
+        share_y: E::Fr,
+    ) -> Result<bool, RlnError> {
+        let mut reader = proof_data;
+        let proof = read_uncompressed_proof::<_, E>(&mut reader)?;
+        if !reader.is_empty() {
+            return Err(RlnError::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "trailing bytes after proof",
+            )));
+        }
+        let public_inputs = vec![
+            root,
+            self.epoch_nullifier(epoch),
+            share_x,
+            share_y,
+            nullifier,
+        ];
+        check_proof(&self.prepared_verifying_key, &proof, &public_inputs)
+    }
+
+    /// same as `verify` reading the public inputs of the proof data in the given byte order
+    /// * the signal length and the epoch preceding the proof data with an identifier stay little-endian
+    pub fn verify_endian<R: Read>(
+        &self,
+        mut input_data: R,
+        endian: Endian,
+    ) -> Result<bool, RlnError> {
+        let mut data: Vec<u8> = Vec::new();
+        input_data.read_to_end(&mut data)?;
+        if endian == Endian::Big {
+            let fr_len = <E::Fr as PrimeField>::Repr::default().as_ref().len() * 8;
+            let offset = match self.rln_identifier {
+                Some(_) => fr_len.min(data.len()),
+                None => 0,
+            };
+            flip_public_inputs::<E>(&mut data[offset..])?;
+        }
+        self.verify(data.as_slice())
+    }
+
+    /// verifies many signals reusing the verifying key
+    /// * expect `input_data` is `count` inputs of `verify` concatenated
+    /// * returns the result for each proof in order
+    pub fn verify_batch<R: Read>(
+        &self,
+        input_data: R,
+        count: usize,
+    ) -> Result<Vec<bool>, RlnError> {
+        let hasher = self.hasher();
+        verify_batch_data::<_, E>(
+            &self.circuit_parameters.vk,
+            &self.prepared_verifying_key,
+            self.rln_identifier
+                .map(|rln_identifier| (&hasher, rln_identifier)),
+            input_data,
+            count,
+        )
+    }
+
+    /// verifies length prefixed signals one at a time as they are read
+    /// * expect `reader` to yield frames serialized as [ frame_len<8> | frame<frame_len> ],
+    /// each frame being an input of `verify`
+    /// * a malformed frame only yields its error, the iterator ends after an error of the stream itself
+    pub fn verify_stream<'a, R: Read + 'a>(
+        &'a self,
+        mut reader: R,
+    ) -> impl Iterator<Item = Result<bool, RlnError>> + 'a {
+        let mut done = false;
+        std::iter::from_fn(move || {
+            if done {
+                return None;
+            }
+            match read_frame(&mut reader) {
+                Ok(Some(frame)) => Some(self.verify(frame.as_slice())),
+                Ok(None) => {
+                    done = true;
+                    None
+                }
+                Err(e) => {
+                    done = true;
+                    Some(Err(e.into()))
+                }
+            }
+        })
+    }
+
+    /// verifies the signal and checks that the proof is generated against one of the recent roots
+    /// * expect `input_data` serialized as in `verify`
+    /// * the number of recent roots is set with `set_root_history_capacity`
+    /// * fails with `RlnError::RootMismatch` if the root of the proof is not a recent one
+    pub fn verify_with_recent_root<R: Read>(&self, input_data: R) -> Result<bool, RlnError> {
+        let (proof, public_inputs) = self.read_verify_data(input_data)?;
+        if !self.root_history.contains(&public_inputs[0]) {
+            return Err(RlnError::RootMismatch);
+        }
+        check_proof(&self.prepared_verifying_key, &proof, &public_inputs)
+    }
+
+    /// verifies the signal and checks that the proof is generated against given root
+    /// * expect `input_data` serialized as in `verify`
+    /// * expect `root_data` serialized as [ root<32> ]
+    /// * fails with `RlnError::RootMismatch` if the proof is generated against another root
+    pub fn verify_with_root<R: Read, S: Read>(
+        &self,
+        input_data: R,
+        root_data: S,
+    ) -> Result<bool, RlnError> {
+        let root = read_fr::<_, E>(root_data, 1)?[0];
+        let (proof, public_inputs) = self.read_verify_data(input_data)?;
+        if public_inputs[0] != root {
+            return Err(RlnError::RootMismatch);
+        }
+        check_proof(&self.prepared_verifying_key, &proof, &public_inputs)
+    }
+
+    /// extracts the shamir share from proof data
+    /// * expect `proof_data` serialized as the output of `generate_proof`
+    /// * `result_data` is serialized as [ share_x<32> | share_y<32> ]
+    pub fn extract_share<R: Read, W: Write>(
+        &self,
+        proof_data: R,
+        mut result_data: W,
+    ) -> Result<(), RlnError> {
+        let (_, public_inputs) = read_proof_data::<_, E>(proof_data)?;
+        public_inputs[2].into_repr().write_le(&mut result_data)?;
+        public_inputs[3].into_repr().write_le(&mut result_data)?;
+        Ok(())
+    }
+
+    /// recovers the secret of a member who signaled twice in the same epoch
+    /// * expect `proof_data_1` and `proof_data_2` serialized as the output of `generate_proof`
+    /// * `result_data` is the recovered id key serialized as [ id_key<32> ]
+    pub fn recover_id_secret<R: Read, W: Write>(
+        &self,
+        proof_data_1: R,
+        proof_data_2: R,
+        mut result_data: W,
+    ) -> Result<(), RlnError> {
+        let (_, public_inputs_1) = read_proof_data::<_, E>(proof_data_1)?;
+        let (_, public_inputs_2) = read_proof_data::<_, E>(proof_data_2)?;
+        let (share_x_1, share_y_1) = (public_inputs_1[2], public_inputs_1[3]);
+        let (share_x_2, share_y_2) = (public_inputs_2[2], public_inputs_2[3]);
+
+        if public_inputs_1[4] != public_inputs_2[4] {
+            return Err(RlnError::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "nullifier mismatch",
+            )));
+        }
+        if share_x_1 == share_x_2 {
+            return Err(RlnError::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "shares have the same x",
+            )));
+        }
+
+        let a_0 = recover_secret::<E>((share_x_1, share_y_1), (share_x_2, share_y_2));
+        a_0.into_repr().write_le(&mut result_data)?;
+        Ok(())
+    }
+
+    /// generates public private key pair
+    /// * `key_pair_data` is seralized as [ secret<32> | public<32> ]
+    pub fn key_gen<W: Write>(&self, input_data: W) -> Result<(), RlnError> {
+        let mut rng = thread_rng();
+        self.key_gen_with_rng(&mut rng, input_data)
+    }
+
+    /// generates public private key pair deterministically from a seed
+    /// * expect `seed_data` serialized as [ seed<32> ]
+    /// * `key_pair_data` is seralized as [ secret<32> | public<32> ]
+    pub fn key_gen_from_seed<R: Read, W: Write>(
+        &self,
+        mut seed_data: R,
+        input_data: W,
+    ) -> Result<(), RlnError> {
+        let mut seed = [0u32; 8];
+        seed_data.read_u32_into::<LittleEndian>(&mut seed)?;
+        let mut rng = ChaChaRng::from_seed(&seed[..]);
+        self.key_gen_with_rng(&mut rng, input_data)
+    }
+
+    fn key_gen_with_rng<G: Rng, W: Write>(
+        &self,
+        rng: &mut G,
+        mut input_data: W,
+    ) -> Result<(), RlnError> {
+        let (secret, public) = self.secret_key_with_rng(rng);
+        secret.write(&mut input_data)?;
+        public.into_repr().write_le(&mut input_data)?;
+        Ok(())
+    }
+
+    /// generates a key pair keeping the secret in a `SecretKey`
+    /// * returns the secret and the public key
+    pub fn key_gen_secret(&self) -> (SecretKey<E>, E::Fr) {
+        let mut rng = thread_rng();
+        self.secret_key_with_rng(&mut rng)
+    }
+
+    fn secret_key_with_rng<G: Rng>(&self, rng: &mut G) -> (SecretKey<E>, E::Fr) {
+        let secret = SecretKey::new(E::Fr::rand(rng));
+        let public = secret.public_key(&self.hasher());
+        (secret, public)
+    }
+
+    /// computes the public key of a secret held elsewhere, e.g. by an external wallet
+    /// * expect `id_key` serialized as [ secret<32> ]
+    /// * `output` is serialized as [ public<32> ], the same public key `key_gen` returns for the secret
+    /// * meant for registration, the output is the input of `update_next_member`
+    pub fn id_commitment<W: Write>(&self, id_key: &[u8], mut output: W) -> Result<(), RlnError> {
+        let secret = SecretKey::<E>::new(read_single_fr::<_, E>(id_key)?);
+        secret
+            .public_key(&self.hasher())
+            .into_repr()
+            .write_le(&mut output)?;
+        Ok(())
+    }
+
+    /// returns a read only verifier for the circuit parameters of this instance, see `clone_verifier`
+    pub fn verifier(&self) -> RLNVerifier<E> {
+        RLNVerifier::new(self.circuit_parameters.vk.clone())
+    }
+
+    /// derives a verify only handle holding the verifying key of this instance, same as `verifier`
+    /// * neither the proving key nor the membership tree is copied
+    pub fn clone_verifier(&self) -> RLNVerifier<E> {
+        self.verifier()
+    }
+
+    /// replaces the circuit parameters with the output of a phase2 trusted setup ceremony
+    /// * expect `input_data` in the format of `MPCParameters::write` of the bellman `phase2` crate:
+    /// [ parameters<var> | cs_hash<64> | contribution_count<4> | contribution<var> * contribution_count ]
+    /// * `parameters` are groth16 parameters as written by `Parameters::write`, `contribution_count` is big-endian
+    /// * `contribution` is [ delta_after<g1> | s<g1> | s_delta<g1> | r_delta<g2> | transcript<64> ] with uncompressed points
+    /// * the parameters must be generated for the RLN circuit of this instance, which is checked against its constraint count,
+    /// contributions are skipped and must be verified with the ceremony tooling
+    pub fn import_phase2_params<R: Read>(&mut self, mut input_data: R) -> Result<(), RlnError> {
+        let circuit_parameters = Parameters::<E>::read(&mut input_data, true)?;
+        let mut cs_hash = [0u8; 64];
+        input_data.read_exact(&mut cs_hash)?;
+        let contribution_count = input_data.read_u32::<BigEndian>()?;
+        let g1_size = <<E::G1Affine as CurveAffine>::Uncompressed as EncodedPoint>::size();
+        let g2_size = <<E::G2Affine as CurveAffine>::Uncompressed as EncodedPoint>::size();
+        let mut contribution = vec![0u8; 3 * g1_size + g2_size + 64];
+        for _ in 0..contribution_count {
+            input_data.read_exact(&mut contribution)?;
+        }
+
+        if !self.matches_circuit(&circuit_parameters)? {
+            return Err(RlnError::Io(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "phase2 parameters do not match the circuit",
+            )));
+        }
+
+        self.prepared_verifying_key = prepare_verifying_key(&circuit_parameters.vk);
+        self.circuit_parameters = circuit_parameters;
+        Ok(())
+    }
+
+    /// exports only the verifying key of the circuit parameters, see `RLNVerifier`
+    pub fn export_verifying_key<W: Write>(&self, w: W) -> Result<(), RlnError> {
+        self.circuit_parameters.vk.write(w)?;
+        Ok(())
+    }
+
+    #[deprecated(note = "renamed to `export_verifying_key`")]
+    pub fn export_verifier_key<W: Write>(&self, w: W) -> Result<(), RlnError> {
+        self.export_verifying_key(w)
+    }
+
+    /// exports circuit parameters serialized as [ merkle_depth<8> | message_limit<8> | parameters<var> | digest<32> ]
+    pub fn export_circuit_parameters<W: Write>(&self, w: W) -> Result<(), RlnError> {
+        self.export_circuit_parameters_with_progress(w, |_, _| {})
+    }
+
+    /// same as `export_circuit_parameters` calling `progress` with the bytes written so far
+    /// and the estimated total after every write
+    pub fn export_circuit_parameters_with_progress<W: Write>(
+        &self,
+        w: W,
+        progress: impl FnMut(u64, u64),
+    ) -> Result<(), RlnError> {
+        let mut w = ProgressWriter {
+            inner: w,
+            written: 0,
+            total: self.circuit_parameters_size() as u64,
+            progress,
+        };
+        write_circuit_parameters(
+            &mut w,
+            self.tree.depth(),
+            self.message_limit,
+            &self.circuit_parameters,
+        )?;
+        Ok(())
+    }
+
+    // size of uncompressed circuit parameters with their header and digest, from the lengths of the parameters
+    fn circuit_parameters_size(&self) -> usize {
+        let g1_size = <<E::G1Affine as CurveAffine>::Uncompressed as EncodedPoint>::size();
+        let g2_size = <<E::G2Affine as CurveAffine>::Uncompressed as EncodedPoint>::size();
+        let params = &self.circuit_parameters;
+        let vk_size = verifying_key_size(&params.vk);
+        let g1_points = params.h.len() + params.l.len() + params.a.len() + params.b_g1.len();
+        // every point vector is prefixed with its u32 length
+        16 + vk_size + 5 * 4 + g1_points * g1_size + params.b_g2.len() * g2_size + 32
+    }
+
+    /// checks that the circuit parameters are generated for the RLN circuit of this instance
+    /// * the circuit is synthesized for the merkle depth, message limit and poseidon parameters of the instance,
+    /// its number of public inputs, auxiliary variables and constraints must match the lengths of the parameters
+    /// * parameters of another circuit are rejected with an `InvalidData` error
+    pub fn validate_parameters(&self) -> Result<(), RlnError> {
+        if !self.matches_circuit(&self.circuit_parameters)? {
+            return Err(RlnError::Io(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "circuit parameters do not match the circuit of this instance",
+            )));
+        }
+        Ok(())
+    }
+
+    // whether the sizes of `circuit_parameters` are the ones of the circuit of this instance
+    fn matches_circuit(&self, circuit_parameters: &Parameters<E>) -> Result<bool, SynthesisError> {
+        let counter = self.count_circuit()?;
+        let domain_size = (counter.num_constraints + counter.num_inputs).next_power_of_two();
+        Ok(circuit_parameters.vk.ic.len() == counter.num_inputs
+            && circuit_parameters.l.len() == counter.num_aux
+            && circuit_parameters.h.len() == domain_size - 1)
+    }
+
+    fn count_circuit(&self) -> Result<ConstraintCounter, SynthesisError> {
+        let circuit = RLNCircuit::<E> {
+            inputs: RLNInputs::<E>::empty_with_arity(self.tree.depth(), self.tree.arity()),
+            hasher: PoseidonCircuit::new(self.poseidon_params.clone()),
+            message_limit: self.message_limit,
+        };
+        ConstraintCounter::count(circuit)
+    }
+
+    /// number of constraints of the circuit
+    pub fn num_constraints(&self) -> usize {
+        self.count_circuit()
+            .expect("counting does not assign the witness")
+            .num_constraints
+    }
+
+    /// serialized sizes in bytes of the circuit parameters and the verifying key,
+    /// as written by `export_circuit_parameters` and `export_verifying_key`
+    /// * computed from the lengths of the parameters, nothing is serialized
+    pub fn param_sizes(&self) -> (usize, usize) {
+        (
+            self.circuit_parameters_size(),
+            verifying_key_size(&self.circuit_parameters.vk),
+        )
+    }
+
+    pub fn hasher(&self) -> PoseidonHasher<E> {
+        PoseidonHasher::new(self.poseidon_params.clone())
+    }
+
+    pub fn poseidon_params(&self) -> PoseidonParams<E> {
+        self.poseidon_params.clone()
+    }
+
+    pub fn message_limit(&self) -> u64 {
+        self.message_limit
+    }
+}
+
+/// serves several applications with the circuit parameters of a single `RLN` instance
+/// * each application has its own membership tree and is identified by its `app_id`, serialized as [ app_id<32> ]
+/// * proofs of an application carry `hash(epoch, app_id)` as the external nullifier, see `RLN::with_rln_identifier`
+pub struct RLNMulti<E: Engine> {
+    // the shared parameters, its tree is the one of the application being proven for
+    rln: RLN<E>,
+    // keyed by the representation of the app id
+    apps: HashMap<Vec<u64>, IncrementalMerkleTree<E>>,
+}
+
+impl<E: Engine> RLNMulti<E> {
+    /// shares the circuit parameters of `rln` between applications
+    /// * the members, identifier and proof cache of `rln` are not used
+    pub fn new(mut rln: RLN<E>) -> RLNMulti<E> {
+        rln.reset_tree();
+        rln.rln_identifier = None;
+        // cached proofs would not tell applications apart
+        rln.set_proof_cache_capacity(0);
+        RLNMulti {
+            rln,
+            apps: HashMap::new(),
+        }
+    }
+
+    fn app_key(app_id: &E::Fr) -> Vec<u64> {
+        app_id.into_repr().as_ref().to_vec()
+    }
+
+    fn unknown_app() -> RlnError {
+        RlnError::Io(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "unknown app id",
+        ))
+    }
+
+    /// adds an application with an empty membership tree
+    pub fn add_app(&mut self, app_id: &[u8]) -> Result<(), RlnError> {
+        let app_id = read_single_fr::<_, E>(app_id)?;
+        let key = Self::app_key(&app_id);
+        if self.apps.contains_key(&key) {
+            return Err(RlnError::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "app id already added",
+            )));
+        }
+        let tree = IncrementalMerkleTree::empty(self.rln.hasher(), self.rln.tree.depth());
+        self.apps.insert(key, tree);
+        Ok(())
+    }
+
+    fn app_tree_mut(&mut self, app_id: &E::Fr) -> Result<&mut IncrementalMerkleTree<E>, RlnError> {
+        self.apps
+            .get_mut(&Self::app_key(app_id))
+            .ok_or_else(Self::unknown_app)
+    }
+
+    fn app_tree(&self, app_id: &E::Fr) -> Result<&IncrementalMerkleTree<E>, RlnError> {
+        self.apps
+            .get(&Self::app_key(app_id))
+            .ok_or_else(Self::unknown_app)
+    }
+
+    /// inserts a member into the tree of the application, see `RLN::update_next_member`
+    pub fn update_next<R: Read>(
+        &mut self,
+        app_id: &[u8],
+        input_data: R,
+    ) -> Result<usize, RlnError> {
+        let app_id = read_single_fr::<_, E>(app_id)?;
+        let leaf = read_single_fr::<_, E>(input_data)?;
+        let tree = self.app_tree_mut(&app_id)?;
+        if tree.current_index >= tree.capacity() {
+            return Err(RlnError::TreeFull);
+        }
+        let index = tree.current_index;
+        tree.update_next(leaf)?;
+        Ok(index)
+    }
+
+    /// writes the root of the tree of the application, see `RLN::get_root`
+    pub fn get_root<W: Write>(&self, app_id: &[u8], mut result_data: W) -> Result<(), RlnError> {
+        let app_id = read_single_fr::<_, E>(app_id)?;
+        let root = self.app_tree(&app_id)?.get_root();
+        root.into_repr().write_le(&mut result_data)?;
+        Ok(())
+    }
+
+    /// generates a proof for a member of the application, see `RLN::generate_proof`
+    pub fn generate_proof<R: Read, W: Write>(
+        &mut self,
+        app_id: &[u8],
+        input_data: R,
+        result_data: W,
+    ) -> Result<(), RlnError> {
+        let app_id_data = app_id;
+        let app_id = read_single_fr::<_, E>(app_id_data)?;
+        let key = Self::app_key(&app_id);
+        let tree = self.apps.get_mut(&key).ok_or_else(Self::unknown_app)?;
+        // the shared instance proves against the tree of the application,
+        // which is moved back whatever the outcome
+        std::mem::swap(&mut self.rln.tree, tree);
+        self.rln.rln_identifier = Some(app_id);
+        let result = self
+            .rln
+            .generate_proof(app_id_data.chain(input_data), result_data);
+        self.rln.rln_identifier = None;
+        std::mem::swap(&mut self.rln.tree, tree);
+        result
+    }
+
+    /// verifies a signal of the application
+    /// * expect `input_data` serialized as [ epoch<32> | input of `RLN::verify` ]
+    /// * fails with `RlnError::RootMismatch` if the proof is not generated against the current root of the application
+    /// and with `RlnError::IdentifierMismatch` if its external nullifier is not the one of the application and epoch
+    pub fn verify<R: Read>(&self, app_id: &[u8], mut input_data: R) -> Result<bool, RlnError> {
+        let app_id = read_single_fr::<_, E>(app_id)?;
+        let root = self.app_tree(&app_id)?.get_root();
+        let epoch = read_fr::<_, E>(&mut input_data, 1)?[0];
+        let mut verify_data: Vec<u8> = Vec::new();
+        input_data.read_to_end(&mut verify_data)?;
+        let public_inputs = PublicInputs::<E>::read(verify_data.as_slice())?;
+        if public_inputs.root != root {
+            return Err(RlnError::RootMismatch);
+        }
+        check_external_nullifier(&self.rln.hasher(), epoch, app_id, public_inputs.epoch)?;
+        self.rln.verify(verify_data.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{PROOF_MAGIC, PROOF_VERSION};
+    use bellman::pairing::bn256::Fr;
+    use byteorder::{LittleEndian, WriteBytesExt};
+    use rand::{Rand, SeedableRng, XorShiftRng};
+
+    fn merkle_depth() -> usize {
+        4usize
+    }
+
+    fn rng() -> XorShiftRng {
+        XorShiftRng::from_seed([0x3dbe6258, 0x8d313d76, 0x3237db17, 0xe5bc0654])
+    }
+
+    fn message_limit() -> u64 {
+        2u64
+    }
+
+    fn rln() -> RLN<Bn256> {
+        RLN::<Bn256>::new(merkle_depth(), message_limit(), None)
+    }
+
+    fn fr_data(e: Fr) -> Vec<u8> {
+        let mut data: Vec<u8> = Vec::new();
+        e.into_repr().write_le(&mut data).unwrap();
+        data
+    }
+
+    /// inserts the public key of `id_key` and returns its index
+    fn insert_member(rln: &mut RLN<Bn256>, id_key: Fr) -> usize {
+        let public_key = rln.hasher().hash(vec![id_key]);
+        rln.update_next_member(fr_data(public_key).as_slice())
+            .unwrap()
+    }
+
+    fn proof_input(
+        id_key: Fr,
+        id_index: usize,
+        epoch: Fr,
+        message_id: Fr,
+        signal: &[u8],
+    ) -> Vec<u8> {
+        let mut input_data: Vec<u8> = Vec::new();
+        id_key.into_repr().write_le(&mut input_data).unwrap();
+        input_data
+            .write_u64::<LittleEndian>(id_index as u64)
+            .unwrap();
+        epoch.into_repr().write_le(&mut input_data).unwrap();
+        message_id.into_repr().write_le(&mut input_data).unwrap();
+        input_data
+            .write_u64::<LittleEndian>(signal.len() as u64)
+            .unwrap();
+        input_data.write_all(signal).unwrap();
+        input_data
+    }
+
+    /// appends the signal to the proof data as expected by `verify`
+    fn verify_input(proof_data: &[u8], signal: &[u8]) -> Vec<u8> {
+        let mut input_data = proof_data.to_vec();
+        input_data
+            .write_u64::<LittleEndian>(signal.len() as u64)
+            .unwrap();
+        input_data.write_all(signal).unwrap();
+        input_data
+    }
+
+    #[test]
+    fn test_proof_output_nullifier() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let id_key = Fr::rand(&mut rng);
+        let id_index = insert_member(&mut rln, id_key);
+        let epoch = Fr::rand(&mut rng);
+
+        let input_data = proof_input(id_key, id_index, epoch, Fr::zero(), b"rln signal");
+        let mut proof_data: Vec<u8> = Vec::new();
+        rln.generate_proof(input_data.as_slice(), &mut proof_data)
+            .unwrap();
+
+        let mut reader = proof_data.as_slice();
+        let (_, public_inputs) = read_proof_data::<_, Bn256>(&mut reader).unwrap();
+        assert!(reader.is_empty());
+
+        let hasher = rln.hasher();
+        let external_nullifier = hasher.hash(vec![epoch, Fr::zero()]);
+        let a_1 = hasher.hash(vec![id_key, external_nullifier]);
+        let nullifier = hasher.hash(vec![a_1]);
+        assert_eq!(nullifier, public_inputs[4]);
+
+        let mut input_data = fr_data(id_key);
+        input_data.extend(fr_data(epoch));
+        input_data.extend(fr_data(Fr::zero()));
+        let mut nullifier_data: Vec<u8> = Vec::new();
+        rln.compute_nullifier(input_data.as_slice(), &mut nullifier_data)
+            .unwrap();
+        assert_eq!(
+            &proof_data[proof_data.len() - 32..],
+            nullifier_data.as_slice()
+        );
+    }
+
+    #[test]
+    fn test_delete_member() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let mut expected_tree = MerkleTree::empty(rln.hasher(), merkle_depth());
+        for i in 0..4 {
+            let leaf = Fr::rand(&mut rng);
+            rln.update_next_member(fr_data(leaf).as_slice()).unwrap();
+            if i != 2 {
+                expected_tree.update(i, leaf).unwrap();
+            }
+        }
+
+        rln.delete_member(2).unwrap();
+        let mut root_data: Vec<u8> = Vec::new();
+        rln.get_root(&mut root_data).unwrap();
+        assert_eq!(fr_data(expected_tree.get_root()), root_data);
+
+        assert!(rln.delete_member(1 << merkle_depth()).is_err());
+    }
+
+    #[test]
+    fn test_set_leaf() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let leaves: Vec<Fr> = (0..6).map(|_| Fr::rand(&mut rng)).collect();
+        let mut expected_tree = MerkleTree::empty(rln.hasher(), merkle_depth());
+        for (i, leaf) in leaves.iter().enumerate() {
+            expected_tree.update(i, *leaf).unwrap();
+        }
+
+        for i in [3usize, 0, 4, 1, 2].iter() {
+            rln.set_leaf(*i, fr_data(leaves[*i]).as_slice()).unwrap();
+        }
+        // next member is appended after the highest index set so far
+        rln.update_next_member(fr_data(leaves[5]).as_slice())
+            .unwrap();
+
+        let mut root_data: Vec<u8> = Vec::new();
+        rln.get_root(&mut root_data).unwrap();
+        assert_eq!(fr_data(expected_tree.get_root()), root_data);
+
+        let leaf_data = fr_data(leaves[0]);
+        assert!(rln
+            .set_leaf(1 << merkle_depth(), leaf_data.as_slice())
+            .is_err());
+    }
+
+    #[test]
+    fn test_set_leaves() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let mut expected = rln();
+        let indices = [9usize, 3, 8, 3, 10];
+        let leaves: Vec<Fr> = (0..indices.len()).map(|_| Fr::rand(&mut rng)).collect();
+        let mut input_data: Vec<u8> = Vec::new();
+        for (index, leaf) in indices.iter().zip(leaves.iter()) {
+            input_data.extend(fr_data(*leaf));
+            expected
+                .set_leaf(*index, fr_data(*leaf).as_slice())
+                .unwrap();
+        }
+        let before = crate::merkle::hash_count();
+        rln.set_leaves(&indices, input_data.as_slice()).unwrap();
+        let lazy_hashes = crate::merkle::hash_count() - before;
+        assert!(lazy_hashes < indices.len() * merkle_depth());
+
+        assert_eq!(rln.tree.get_root(), expected.tree.get_root());
+        assert_eq!(rln.len(), expected.len());
+        assert_eq!(
+            rln.tree.get_witness(8).unwrap(),
+            expected.tree.get_witness(8).unwrap()
+        );
+        let mut leaf_data: Vec<u8> = Vec::new();
+        rln.get_leaf(3, &mut leaf_data).unwrap();
+        assert_eq!(fr_data(leaves[3]), leaf_data);
+
+        // an out of range index sets nothing
+        let root = rln.tree.get_root();
+        let err = rln
+            .set_leaves(&[0, 1 << merkle_depth()], &input_data[..64])
+            .unwrap_err();
+        assert!(matches!(err, RlnError::IndexOutOfRange { .. }));
+        assert_eq!(root, rln.tree.get_root());
+    }
+
+    #[test]
+    fn test_merkle_proof() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let leaves: Vec<Fr> = (0..5).map(|_| Fr::rand(&mut rng)).collect();
+        for leaf in leaves.iter() {
+            rln.update_next_member(fr_data(*leaf).as_slice()).unwrap();
+        }
+        let mut root_data: Vec<u8> = Vec::new();
+        rln.get_root(&mut root_data).unwrap();
+
+        let index = 3;
+        let mut proof_data: Vec<u8> = Vec::new();
+        rln.get_merkle_proof(index, &mut proof_data).unwrap();
+        assert_eq!(merkle_depth() * 33, proof_data.len());
+
+        let mut input_data = root_data.clone();
+        input_data.extend(fr_data(leaves[index]));
+        input_data.extend(proof_data.iter());
+        assert!(rln.verify_merkle_proof(input_data.as_slice()).unwrap());
+
+        let mut input_data = root_data.clone();
+        input_data.extend(fr_data(leaves[index - 1]));
+        input_data.extend(proof_data.iter());
+        assert!(!rln.verify_merkle_proof(input_data.as_slice()).unwrap());
+
+        // index is not populated yet
+        let mut proof_data: Vec<u8> = Vec::new();
+        assert!(rln.get_merkle_proof(leaves.len(), &mut proof_data).is_err());
+    }
+
+    #[test]
+    fn test_key_gen_from_seed() {
+        let rln = rln();
+        let seed = [7u8; 32];
+
+        let mut key_pair_data_1: Vec<u8> = Vec::new();
+        rln.key_gen_from_seed(&seed[..], &mut key_pair_data_1)
+            .unwrap();
+        let mut key_pair_data_2: Vec<u8> = Vec::new();
+        rln.key_gen_from_seed(&seed[..], &mut key_pair_data_2)
+            .unwrap();
+        assert_eq!(key_pair_data_1, key_pair_data_2);
+
+        let key_pair = read_fr::<_, Bn256>(key_pair_data_1.as_slice(), 2).unwrap();
+        assert_eq!(rln.hasher().hash(vec![key_pair[0]]), key_pair[1]);
+
+        let mut key_pair_data_3: Vec<u8> = Vec::new();
+        rln.key_gen_from_seed(&[8u8; 32][..], &mut key_pair_data_3)
+            .unwrap();
+        assert_ne!(key_pair_data_1, key_pair_data_3);
+
+        // seed is too short
+        let mut key_pair_data: Vec<u8> = Vec::new();
+        assert!(rln
+            .key_gen_from_seed(&seed[..16], &mut key_pair_data)
+            .is_err());
+    }
+
+    #[test]
+    fn test_verify_with_root() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let id_key = Fr::rand(&mut rng);
+        let id_index = insert_member(&mut rln, id_key);
+        let epoch = Fr::rand(&mut rng);
+        let signal = b"rln signal";
+
+        let input_data = proof_input(id_key, id_index, epoch, Fr::zero(), signal);
+        let mut proof_data: Vec<u8> = Vec::new();
+        rln.generate_proof(input_data.as_slice(), &mut proof_data)
+            .unwrap();
+        let input_data = verify_input(&proof_data, signal);
+
+        let mut root_data: Vec<u8> = Vec::new();
+        rln.get_root(&mut root_data).unwrap();
+        assert!(rln
+            .verify_with_root(input_data.as_slice(), root_data.as_slice())
+            .unwrap());
+
+        let root_data = fr_data(Fr::rand(&mut rng));
+        let err = rln
+            .verify_with_root(input_data.as_slice(), root_data.as_slice())
+            .unwrap_err();
+        assert!(matches!(err, RlnError::RootMismatch));
+    }
+
+    #[test]
+    fn test_recover_id_secret() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let id_key = Fr::rand(&mut rng);
+        let id_index = insert_member(&mut rln, id_key);
+        let epoch = Fr::rand(&mut rng);
+
+        let mut proof_data_1: Vec<u8> = Vec::new();
+        let input_data = proof_input(id_key, id_index, epoch, Fr::zero(), b"rln signal 1");
+        rln.generate_proof(input_data.as_slice(), &mut proof_data_1)
+            .unwrap();
+        let mut proof_data_2: Vec<u8> = Vec::new();
+        let input_data = proof_input(id_key, id_index, epoch, Fr::zero(), b"rln signal 2");
+        rln.generate_proof(input_data.as_slice(), &mut proof_data_2)
+            .unwrap();
+
+        let mut result_data: Vec<u8> = Vec::new();
+        rln.recover_id_secret(
+            proof_data_1.as_slice(),
+            proof_data_2.as_slice(),
+            &mut result_data,
+        )
+        .unwrap();
+        assert_eq!(fr_data(id_key), result_data);
+
+        // same signal twice cannot be interpolated
+        let mut result_data: Vec<u8> = Vec::new();
+        assert!(rln
+            .recover_id_secret(
+                proof_data_1.as_slice(),
+                proof_data_1.as_slice(),
+                &mut result_data
+            )
+            .is_err());
+
+        // signals from another epoch have a different nullifier
+        let mut proof_data_3: Vec<u8> = Vec::new();
+        let input_data = proof_input(
+            id_key,
+            id_index,
+            Fr::rand(&mut rng),
+            Fr::zero(),
+            b"rln signal 3",
+        );
+        rln.generate_proof(input_data.as_slice(), &mut proof_data_3)
+            .unwrap();
+        assert!(rln
+            .recover_id_secret(
+                proof_data_1.as_slice(),
+                proof_data_3.as_slice(),
+                &mut result_data
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_message_limit() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let id_key = Fr::rand(&mut rng);
+        let id_index = insert_member(&mut rln, id_key);
+        let epoch = Fr::rand(&mut rng);
+        let signal = b"rln signal";
+
+        let mut nullifiers: Vec<Vec<u8>> = Vec::new();
+        for message_id in 0..message_limit() {
+            let message_id = Fr::from_str(&message_id.to_string()).unwrap();
+            let input_data = proof_input(id_key, id_index, epoch, message_id, signal);
+            let mut proof_data: Vec<u8> = Vec::new();
+            rln.generate_proof(input_data.as_slice(), &mut proof_data)
+                .unwrap();
+            let input_data = verify_input(&proof_data, signal);
+            assert!(rln.verify(input_data.as_slice()).unwrap());
+            nullifiers.push(proof_data[proof_data.len() - 32..].to_vec());
+        }
+        // each message of the epoch has its own nullifier
+        assert_ne!(nullifiers[0], nullifiers[1]);
+
+        let message_id = Fr::from_str(&message_limit().to_string()).unwrap();
+        let input_data = proof_input(id_key, id_index, epoch, message_id, signal);
+        let mut proof_data: Vec<u8> = Vec::new();
+        assert!(rln
+            .generate_proof(input_data.as_slice(), &mut proof_data)
+            .is_err());
+    }
+
+    #[test]
+    fn test_proof_format() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let id_key = Fr::rand(&mut rng);
+        let id_index = insert_member(&mut rln, id_key);
+        let epoch = Fr::rand(&mut rng);
+        let signal = b"rln signal";
+
+        let input_data = proof_input(id_key, id_index, epoch, Fr::zero(), signal);
+        let mut proof_data: Vec<u8> = Vec::new();
+        rln.generate_proof(input_data.as_slice(), &mut proof_data)
+            .unwrap();
+        assert_eq!(5 + 256 + 5 * 32, proof_data.len());
+        assert_eq!(&PROOF_MAGIC[..], &proof_data[..4]);
+        assert_eq!(PROOF_VERSION, proof_data[4]);
+
+        // round trip
+        let (proof, public_inputs) = read_proof_data::<_, Bn256>(proof_data.as_slice()).unwrap();
+        let mut output_data: Vec<u8> = Vec::new();
+        write_proof_header(&mut output_data).unwrap();
+        write_uncompressed_proof(proof, &mut output_data).unwrap();
+        for input in public_inputs.iter() {
+            input.into_repr().write_le(&mut output_data).unwrap();
+        }
+        assert_eq!(proof_data, output_data);
+        let input_data = verify_input(&proof_data, signal);
+        assert!(rln.verify(input_data.as_slice()).unwrap());
+
+        // wrong version
+        let mut wrong_version = proof_data.clone();
+        wrong_version[4] = PROOF_VERSION + 1;
+        let input_data = verify_input(&wrong_version, signal);
+        let err = io::Error::from(rln.verify(input_data.as_slice()).unwrap_err());
+        assert_eq!(io::ErrorKind::InvalidData, err.kind());
+
+        // wrong magic
+        let mut wrong_magic = proof_data.clone();
+        wrong_magic[0] ^= 1;
+        let input_data = verify_input(&wrong_magic, signal);
+        let err = io::Error::from(rln.verify(input_data.as_slice()).unwrap_err());
+        assert_eq!(io::ErrorKind::InvalidData, err.kind());
+
+        // truncated
+        for len in [0usize, 3, 5, 100, proof_data.len() - 1].iter() {
+            let err = io::Error::from(rln.verify(&proof_data[..*len]).unwrap_err());
+            assert_eq!(io::ErrorKind::UnexpectedEof, err.kind());
+        }
+    }
+
+    #[test]
+    fn test_hex() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let id_key = Fr::rand(&mut rng);
+        let id_index = insert_member(&mut rln, id_key);
+        let epoch = Fr::rand(&mut rng);
+
+        let input_data = proof_input(id_key, id_index, epoch, Fr::zero(), b"rln signal");
+        let mut proof_data: Vec<u8> = Vec::new();
+        rln.generate_proof(input_data.as_slice(), &mut proof_data)
+            .unwrap();
+        let proof_hex = proof_to_hex(&proof_data);
+        assert_eq!(proof_data.len() * 2, proof_hex.len());
+        assert_eq!(proof_data, proof_from_hex(&proof_hex).unwrap());
+        assert_eq!(
+            proof_data,
+            proof_from_hex(&proof_hex.to_uppercase()).unwrap()
+        );
+
+        let mut keypair_data: Vec<u8> = Vec::new();
+        rln.key_gen(&mut keypair_data).unwrap();
+        let keypair_hex = keypair_to_hex(&keypair_data);
+        assert_eq!(keypair_data, keypair_from_hex(&keypair_hex).unwrap());
+
+        // odd length
+        let err = proof_from_hex(&proof_hex[1..]).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, err.kind());
+        // non hex
+        let err = keypair_from_hex(&format!("g{}", &keypair_hex[1..])).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, err.kind());
+        // wrong length
+        let err = keypair_from_hex(&keypair_hex[2..]).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, err.kind());
+    }
+
+    #[test]
+    fn test_verifier() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let id_key = Fr::rand(&mut rng);
+        let id_index = insert_member(&mut rln, id_key);
+        let epoch = Fr::rand(&mut rng);
+        let signal = b"rln signal";
+
+        let mut vk_data: Vec<u8> = Vec::new();
+        rln.export_verifying_key(&mut vk_data).unwrap();
+        let verifier = RLNVerifier::<Bn256>::from_raw_vk(vk_data.as_slice()).unwrap();
+        let mut old_vk_data: Vec<u8> = Vec::new();
+        #[allow(deprecated)]
+        let exported = rln.export_verifier_key(&mut old_vk_data);
+        exported.unwrap();
+        assert_eq!(vk_data, old_vk_data);
+
+        let input_data = proof_input(id_key, id_index, epoch, Fr::zero(), signal);
+        let mut proof_data: Vec<u8> = Vec::new();
+        rln.generate_proof(input_data.as_slice(), &mut proof_data)
+            .unwrap();
+        let input_data = verify_input(&proof_data, signal);
+        assert!(verifier.verify(input_data.as_slice()).unwrap());
+
+        // tampered nullifier
+        let mut proof_data = proof_data.clone();
+        let last = proof_data.len() - 32;
+        proof_data[last] ^= 1;
+        let input_data = verify_input(&proof_data, signal);
+        assert!(!verifier.verify(input_data.as_slice()).unwrap());
+
+        assert!(RLNVerifier::<Bn256>::from_raw_vk(&vk_data[..vk_data.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_verify_batch() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let id_key = Fr::rand(&mut rng);
+        let id_index = insert_member(&mut rln, id_key);
+
+        let mut proofs: Vec<Vec<u8>> = Vec::new();
+        for i in 0..4 {
+            let signal = format!("rln signal {}", i);
+            let input_data = proof_input(
+                id_key,
+                id_index,
+                Fr::rand(&mut rng),
+                Fr::zero(),
+                signal.as_bytes(),
+            );
+            let mut proof_data: Vec<u8> = Vec::new();
+            rln.generate_proof(input_data.as_slice(), &mut proof_data)
+                .unwrap();
+            proofs.push(verify_input(&proof_data, signal.as_bytes()));
+        }
+
+        let input_data: Vec<u8> = proofs.concat();
+        assert_eq!(
+            vec![true; 4],
+            rln.verify_batch(input_data.as_slice(), 4).unwrap()
+        );
+
+        // tampered nullifier of the third proof
+        let last = proofs[2].len() - 8 - "rln signal 2".len() - 32;
+        proofs[2][last] ^= 1;
+        let input_data: Vec<u8> = proofs.concat();
+        assert_eq!(
+            vec![true, true, false, true],
+            rln.verify_batch(input_data.as_slice(), 4).unwrap()
+        );
+
+        let mut vk_data: Vec<u8> = Vec::new();
+        rln.export_verifying_key(&mut vk_data).unwrap();
+        let verifier = RLNVerifier::<Bn256>::from_raw_vk(vk_data.as_slice()).unwrap();
+        assert_eq!(
+            vec![true, true, false, true],
+            verifier.verify_batch(input_data.as_slice(), 4).unwrap()
+        );
+
+        // fewer proofs than expected
+        assert!(rln.verify_batch(input_data.as_slice(), 5).is_err());
+        // a count far beyond the input fails without allocating for it
+        let err = io::Error::from(
+            rln.verify_batch(input_data.as_slice(), usize::MAX)
+                .unwrap_err(),
+        );
+        assert_eq!(io::ErrorKind::UnexpectedEof, err.kind());
+    }
+
+    #[test]
+    fn test_parameters_header() {
+        let rln = rln();
+        let mut circuit_parameters: Vec<u8> = Vec::new();
+        rln.export_circuit_parameters(&mut circuit_parameters)
+            .unwrap();
+
+        let restored = RLN::<Bn256>::new_with_raw_params(
+            merkle_depth(),
+            message_limit(),
+            circuit_parameters.as_slice(),
+            None,
+        )
+        .unwrap();
+        let mut vk_data: Vec<u8> = Vec::new();
+        restored.export_verifying_key(&mut vk_data).unwrap();
+        let mut expected_vk_data: Vec<u8> = Vec::new();
+        rln.export_verifying_key(&mut expected_vk_data).unwrap();
+        assert_eq!(expected_vk_data, vk_data);
+
+        let err = RLN::<Bn256>::new_with_raw_params(
+            merkle_depth() + 1,
+            message_limit(),
+            circuit_parameters.as_slice(),
+            None,
+        )
+        .err()
+        .unwrap();
+        assert!(matches!(&err, RlnError::Io(e) if e.kind() == io::ErrorKind::InvalidInput));
+        assert!(err.to_string().contains("merkle depth mismatch"));
+
+        let err = RLN::<Bn256>::new_with_raw_params(
+            merkle_depth(),
+            message_limit() + 1,
+            circuit_parameters.as_slice(),
+            None,
+        )
+        .err()
+        .unwrap();
+        assert!(matches!(&err, RlnError::Io(e) if e.kind() == io::ErrorKind::InvalidInput));
+    }
+
+    #[test]
+    fn test_export_import_tree() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let id_key = Fr::rand(&mut rng);
+        for _ in 0..3 {
+            insert_member(&mut rln, Fr::rand(&mut rng));
+        }
+        let id_index = insert_member(&mut rln, id_key);
+        insert_member(&mut rln, Fr::rand(&mut rng));
+        rln.delete_member(1).unwrap();
+
+        let mut tree_data: Vec<u8> = Vec::new();
+        rln.export_tree(&mut tree_data).unwrap();
+        assert_eq!(16 + 5 * 32, tree_data.len());
+
+        let mut circuit_parameters: Vec<u8> = Vec::new();
+        rln.export_circuit_parameters(&mut circuit_parameters)
+            .unwrap();
+        let mut restored = RLN::<Bn256>::new_with_raw_params(
+            merkle_depth(),
+            message_limit(),
+            circuit_parameters.as_slice(),
+            None,
+        )
+        .unwrap();
+        restored.import_tree(tree_data.as_slice()).unwrap();
+
+        let mut root_data: Vec<u8> = Vec::new();
+        rln.get_root(&mut root_data).unwrap();
+        let mut restored_root_data: Vec<u8> = Vec::new();
+        restored.get_root(&mut restored_root_data).unwrap();
+        assert_eq!(root_data, restored_root_data);
+        assert_eq!(rln.tree.current_index, restored.tree.current_index);
+
+        let signal = b"rln signal";
+        let input_data = proof_input(id_key, id_index, Fr::rand(&mut rng), Fr::zero(), signal);
+        let mut proof_data: Vec<u8> = Vec::new();
+        restored
+            .generate_proof(input_data.as_slice(), &mut proof_data)
+            .unwrap();
+        let input_data = verify_input(&proof_data, signal);
+        assert!(rln
+            .verify_with_root(input_data.as_slice(), root_data.as_slice())
+            .unwrap());
+
+        // tree of another depth
+        let mut other = RLN::<Bn256>::new(merkle_depth() + 1, message_limit(), None);
+        assert!(other.import_tree(tree_data.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_verify_with_recent_root() {
+        let mut rng = rng();
+        let mut rln = rln().with_root_history_capacity(3);
+        assert_eq!(3, rln.root_history_capacity());
+        let id_key = Fr::rand(&mut rng);
+        let id_index = insert_member(&mut rln, id_key);
+        let signal = b"rln signal";
+
+        let input_data = proof_input(id_key, id_index, Fr::rand(&mut rng), Fr::zero(), signal);
+        let mut proof_data: Vec<u8> = Vec::new();
+        rln.generate_proof(input_data.as_slice(), &mut proof_data)
+            .unwrap();
+        let input_data = verify_input(&proof_data, signal);
+        assert!(rln.verify_with_recent_root(input_data.as_slice()).unwrap());
+
+        // the root of the proof is still among the last three roots
+        for _ in 0..2 {
+            insert_member(&mut rln, Fr::rand(&mut rng));
+            assert!(rln.verify_with_recent_root(input_data.as_slice()).unwrap());
+        }
+
+        // too old
+        insert_member(&mut rln, Fr::rand(&mut rng));
+        let err = rln
+            .verify_with_recent_root(input_data.as_slice())
+            .unwrap_err();
+        assert!(matches!(err, RlnError::RootMismatch));
+        // yet the proof itself is valid
+        assert!(rln.verify(input_data.as_slice()).unwrap());
+    }
+
+    #[test]
+    fn test_signal_serialization() {
+        let mut rng = rng();
+        let signal = RLNSignal::<Bn256> {
+            epoch: Fr::rand(&mut rng),
+            message_id: Fr::one(),
+            signal: b"rln signal".to_vec(),
+            hash: hash_to_field::<Bn256>(b"rln signal"),
+        };
+        let mut signal_data: Vec<u8> = Vec::new();
+        signal.write(&mut signal_data).unwrap();
+        let mut reader = signal_data.as_slice();
+        let read_signal = RLNSignal::<Bn256>::read(&mut reader).unwrap();
+        assert!(reader.is_empty());
+        assert_eq!(signal.epoch, read_signal.epoch);
+        assert_eq!(signal.message_id, read_signal.message_id);
+        assert_eq!(signal.signal, read_signal.signal);
+        assert_eq!(signal.hash, read_signal.hash);
+
+        // the modulus and the value just above it are not canonical
+        let mut modulus = Fr::char();
+        for _ in 0..2 {
+            let mut epoch_data: Vec<u8> = Vec::new();
+            modulus.write_le(&mut epoch_data).unwrap();
+            let mut input_data = epoch_data.clone();
+            input_data.extend_from_slice(&signal_data[32..]);
+            let err = RLNSignal::<Bn256>::read(input_data.as_slice())
+                .err()
+                .unwrap();
+            assert_eq!(io::ErrorKind::InvalidData, err.kind());
+
+            let mut input_data = signal_data[..32].to_vec();
+            input_data.extend(epoch_data);
+            input_data.extend_from_slice(&signal_data[64..]);
+            let err = RLNSignal::<Bn256>::read(input_data.as_slice())
+                .err()
+                .unwrap();
+            assert_eq!(io::ErrorKind::InvalidData, err.kind());
+
+            modulus.add_nocarry(&1u64.into());
+        }
+
+        // truncated signal
+        let err = RLNSignal::<Bn256>::read(&signal_data[..signal_data.len() - 1])
+            .err()
+            .unwrap();
+        assert_eq!(io::ErrorKind::UnexpectedEof, err.kind());
+
+        // a length beyond the input is not allocated up front
+        let mut input_data = signal_data[..64].to_vec();
+        input_data.write_u64::<LittleEndian>(u64::MAX).unwrap();
+        input_data.extend_from_slice(b"rln signal");
+        let err = RLNSignal::<Bn256>::read(input_data.as_slice())
+            .err()
+            .unwrap();
+        assert_eq!(io::ErrorKind::UnexpectedEof, err.kind());
+        let err = read_signal_hash::<_, Bn256>(&input_data[64..])
+            .err()
+            .unwrap();
+        assert_eq!(io::ErrorKind::UnexpectedEof, err.kind());
+    }
+
+    #[test]
+    fn test_new_with_reader() {
+        use std::io::{BufReader, Cursor};
+
+        let mut rng = rng();
+        let rln = rln();
+        let mut circuit_parameters: Vec<u8> = Vec::new();
+        rln.export_circuit_parameters(&mut circuit_parameters)
+            .unwrap();
+        let mut vk_data: Vec<u8> = Vec::new();
+        rln.export_verifying_key(&mut vk_data).unwrap();
+
+        let from_cursor = RLN::<Bn256>::new_with_reader(
+            merkle_depth(),
+            message_limit(),
+            Cursor::new(circuit_parameters.clone()),
+            None,
+        )
+        .unwrap();
+        let from_buf_reader = RLN::<Bn256>::new_with_reader(
+            merkle_depth(),
+            message_limit(),
+            BufReader::with_capacity(1024, Cursor::new(circuit_parameters.clone())),
+            None,
+        )
+        .unwrap();
+
+        for mut restored in vec![from_cursor, from_buf_reader].into_iter() {
+            let mut restored_vk_data: Vec<u8> = Vec::new();
+            restored
+                .export_verifying_key(&mut restored_vk_data)
+                .unwrap();
+            assert_eq!(vk_data, restored_vk_data);
+
+            let id_key = Fr::rand(&mut rng);
+            let id_index = insert_member(&mut restored, id_key);
+            let signal = b"rln signal";
+            let input_data = proof_input(id_key, id_index, Fr::rand(&mut rng), Fr::zero(), signal);
+            let mut proof_data: Vec<u8> = Vec::new();
+            restored
+                .generate_proof(input_data.as_slice(), &mut proof_data)
+                .unwrap();
+            let input_data = verify_input(&proof_data, signal);
+            assert!(restored.verify(input_data.as_slice()).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_extract_share() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let id_key = Fr::rand(&mut rng);
+        let id_index = insert_member(&mut rln, id_key);
+        let epoch = Fr::rand(&mut rng);
+
+        let mut shares: Vec<(Fr, Fr)> = Vec::new();
+        for signal in [&b"rln signal 1"[..], &b"rln signal 2"[..]].iter() {
+            let input_data = proof_input(id_key, id_index, epoch, Fr::zero(), signal);
+            let mut proof_data: Vec<u8> = Vec::new();
+            rln.generate_proof(input_data.as_slice(), &mut proof_data)
+                .unwrap();
+            let mut share_data: Vec<u8> = Vec::new();
+            rln.extract_share(proof_data.as_slice(), &mut share_data)
+                .unwrap();
+            let share = read_fr::<_, Bn256>(share_data.as_slice(), 2).unwrap();
+            assert_eq!(hash_to_field::<Bn256>(signal), share[0]);
+            shares.push((share[0], share[1]));
+        }
+
+        // id_key = (y_1 * x_2 - y_2 * x_1) / (x_2 - x_1)
+        let (x_1, y_1) = shares[0];
+        let (x_2, y_2) = shares[1];
+        let mut numerator = y_1;
+        numerator.mul_assign(&x_2);
+        let mut tmp = y_2;
+        tmp.mul_assign(&x_1);
+        numerator.sub_assign(&tmp);
+        let mut denominator = x_2;
+        denominator.sub_assign(&x_1);
+        numerator.mul_assign(&denominator.inverse().unwrap());
+        assert_eq!(id_key, numerator);
+    }
+
+    #[test]
+    fn test_hash_n() {
+        let mut rng = rng();
+        let rln = rln();
+        let inputs: Vec<Fr> = (0..2).map(|_| Fr::rand(&mut rng)).collect();
+        let mut input_data: Vec<u8> = Vec::new();
+        for input in inputs.iter() {
+            input_data.extend(fr_data(*input));
+        }
+
+        let mut output_data: Vec<u8> = Vec::new();
+        rln.hash_n(input_data.as_slice(), 2, 1, &mut output_data)
+            .unwrap();
+        assert_eq!(fr_data(rln.hasher().hash(inputs)), output_data);
+
+        let mut output_data_1: Vec<u8> = Vec::new();
+        rln.hash_n(input_data.as_slice(), 2, 3, &mut output_data_1)
+            .unwrap();
+        let mut output_data_2: Vec<u8> = Vec::new();
+        rln.hash_n(input_data.as_slice(), 2, 3, &mut output_data_2)
+            .unwrap();
+        assert_eq!(3 * 32, output_data_1.len());
+        assert_eq!(output_data_1, output_data_2);
+        assert_eq!(output_data, output_data_1[..32].to_vec());
+
+        // fewer inputs than given
+        let mut output_data: Vec<u8> = Vec::new();
+        assert!(rln
+            .hash_n(input_data.as_slice(), 3, 1, &mut output_data)
+            .is_err());
+
+        let err = rln
+            .hash_n(
+                input_data.as_slice(),
+                2,
+                MAX_HASH_OUTPUTS + 1,
+                &mut output_data,
+            )
+            .unwrap_err();
+        assert!(matches!(&err, RlnError::Io(e) if e.kind() == io::ErrorKind::InvalidInput));
+        assert!(output_data.is_empty());
+    }
+
+    #[test]
+    fn test_member_index_range() {
+        let mut rng = rng();
+        let merkle_depth = 3;
+        let mut rln = RLN::<Bn256>::new(merkle_depth, message_limit(), None);
+        let id_key = Fr::rand(&mut rng);
+        let public_key = rln.hasher().hash(vec![id_key]);
+        rln.set_leaf(7, fr_data(public_key).as_slice()).unwrap();
+        let signal = b"rln signal";
+        let epoch = Fr::rand(&mut rng);
+
+        let input_data = proof_input(id_key, 8, epoch, Fr::zero(), signal);
+        let mut proof_data: Vec<u8> = Vec::new();
+        let err = rln
+            .generate_proof(input_data.as_slice(), &mut proof_data)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            RlnError::IndexOutOfRange {
+                index: 8,
+                set_size: 8
+            }
+        ));
+
+        let input_data = proof_input(id_key, 7, epoch, Fr::zero(), signal);
+        rln.generate_proof(input_data.as_slice(), &mut proof_data)
+            .unwrap();
+        let input_data = verify_input(&proof_data, signal);
+        assert!(rln.verify(input_data.as_slice()).unwrap());
+
+        assert!(
+            RLN::<Bn256>::new_with_raw_params(64, message_limit(), &[0u8; 16][..], None).is_err()
+        );
+    }
+
+    #[test]
+    fn test_builder() {
+        let mut rng = rng();
+        let rln = RLN::<Bn256>::builder()
+            .merkle_depth(merkle_depth())
+            .message_limit(message_limit())
+            .root_history_capacity(2)
+            .build()
+            .unwrap();
+        assert_eq!(message_limit(), rln.message_limit());
+        assert_eq!(2, rln.root_history_capacity());
+        let mut circuit_parameters: Vec<u8> = Vec::new();
+        rln.export_circuit_parameters(&mut circuit_parameters)
+            .unwrap();
+
+        let poseidon_params = PoseidonParams::<Bn256>::new(8, 55, 3, None, None, None);
+        let restored = vec![
+            RLN::<Bn256>::builder()
+                .merkle_depth(merkle_depth())
+                .message_limit(message_limit())
+                .params(&circuit_parameters)
+                .build()
+                .unwrap(),
+            RLN::<Bn256>::builder()
+                .merkle_depth(merkle_depth())
+                .message_limit(message_limit())
+                .params(&circuit_parameters)
+                .poseidon(poseidon_params)
+                .build()
+                .unwrap(),
+        ];
+        for mut restored in restored.into_iter() {
+            let id_key = Fr::rand(&mut rng);
+            let id_index = insert_member(&mut restored, id_key);
+            let signal = b"rln signal";
+            let input_data = proof_input(id_key, id_index, Fr::rand(&mut rng), Fr::zero(), signal);
+            let mut proof_data: Vec<u8> = Vec::new();
+            restored
+                .generate_proof(input_data.as_slice(), &mut proof_data)
+                .unwrap();
+            let input_data = verify_input(&proof_data, signal);
+            assert!(rln.verify(input_data.as_slice()).unwrap());
+        }
+
+        assert!(RLN::<Bn256>::builder().build().is_err());
+        assert!(RLN::<Bn256>::builder()
+            .merkle_depth(merkle_depth())
+            .message_limit(0)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_unchecked_parameters() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let mut circuit_parameters: Vec<u8> = Vec::new();
+        rln.export_circuit_parameters(&mut circuit_parameters)
+            .unwrap();
+
+        let id_key = Fr::rand(&mut rng);
+        let id_index = insert_member(&mut rln, id_key);
+        let signal = b"rln signal";
+        let input_data = proof_input(id_key, id_index, Fr::rand(&mut rng), Fr::zero(), signal);
+        let mut proof_data: Vec<u8> = Vec::new();
+        rln.generate_proof(input_data.as_slice(), &mut proof_data)
+            .unwrap();
+        let input_data = verify_input(&proof_data, signal);
+
+        let checked = RLN::<Bn256>::new_with_raw_params(
+            merkle_depth(),
+            message_limit(),
+            &circuit_parameters,
+            None,
+        )
+        .unwrap();
+        let unchecked = RLN::<Bn256>::new_with_raw_params_unchecked(
+            merkle_depth(),
+            message_limit(),
+            &circuit_parameters,
+            None,
+        )
+        .unwrap();
+
+        assert!(checked.verify(input_data.as_slice()).unwrap());
+        assert!(unchecked.verify(input_data.as_slice()).unwrap());
+    }
+
+    #[test]
+    fn test_get_leaf() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let public_key = Fr::rand(&mut rng);
+        let public_key_data = fr_data(public_key);
+        rln.update_next_member(public_key_data.as_slice()).unwrap();
+        rln.set_leaf(3, public_key_data.as_slice()).unwrap();
+
+        for &index in [0usize, 3].iter() {
+            let mut leaf_data: Vec<u8> = Vec::new();
+            rln.get_leaf(index, &mut leaf_data).unwrap();
+            assert_eq!(public_key_data, leaf_data);
+        }
+
+        let mut leaf_data: Vec<u8> = Vec::new();
+        assert!(rln.get_leaf(1, &mut leaf_data).is_err());
+        assert!(rln.get_leaf(1 << merkle_depth(), &mut leaf_data).is_err());
+        assert!(leaf_data.is_empty());
+
+        rln.delete_member(0).unwrap();
+        rln.get_leaf(0, &mut leaf_data).unwrap();
+        assert_eq!(fr_data(Fr::zero()), leaf_data);
+    }
+
+    #[test]
+    fn test_shared_verifier() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<RLNVerifier<Bn256>>();
+
+        let mut rng = rng();
+        let mut rln = rln();
+        let id_key = Fr::rand(&mut rng);
+        let id_index = insert_member(&mut rln, id_key);
+        let signal = b"rln signal";
+        let input_data = proof_input(id_key, id_index, Fr::rand(&mut rng), Fr::zero(), signal);
+        let mut proof_data: Vec<u8> = Vec::new();
+        rln.generate_proof(input_data.as_slice(), &mut proof_data)
+            .unwrap();
+        let input_data = std::sync::Arc::new(verify_input(&proof_data, signal));
+
+        let verifier = std::sync::Arc::new(rln.verifier());
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let verifier = verifier.clone();
+                let input_data = input_data.clone();
+                std::thread::spawn(move || verifier.verify(input_data.as_slice()).unwrap())
+            })
+            .collect();
+        for handle in handles {
+            assert!(handle.join().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_rln_identifier() {
+        let mut rng = rng();
+        let rln = rln();
+        let mut circuit_parameters: Vec<u8> = Vec::new();
+        rln.export_circuit_parameters(&mut circuit_parameters)
+            .unwrap();
+        let id_key = Fr::rand(&mut rng);
+        let epoch = Fr::rand(&mut rng);
+        let signal = b"rln signal";
+
+        let mut nullifiers: Vec<Vec<u8>> = Vec::new();
+        let mut apps: Vec<RLN<Bn256>> = Vec::new();
+        let mut proofs: Vec<Vec<u8>> = Vec::new();
+        for rln_identifier in [Fr::from_str("1").unwrap(), Fr::from_str("2").unwrap()].iter() {
+            let mut app = RLN::<Bn256>::builder()
+                .merkle_depth(merkle_depth())
+                .message_limit(message_limit())
+                .params(&circuit_parameters)
+                .rln_identifier(*rln_identifier)
+                .build()
+                .unwrap();
+            assert_eq!(Some(*rln_identifier), app.rln_identifier());
+            let id_index = insert_member(&mut app, id_key);
+
+            // the signal is given for the application
+            let mut input_data = fr_data(*rln_identifier);
+            input_data.extend(proof_input(id_key, id_index, epoch, Fr::zero(), signal));
+            let mut proof_data: Vec<u8> = Vec::new();
+            assert!(app.check_witness(input_data.as_slice()).unwrap());
+            assert!(app.prove_and_verify(&input_data).unwrap());
+            app.generate_proof(input_data.as_slice(), &mut proof_data)
+                .unwrap();
+            // the verifier of an application is given the epoch of the signal
+            let mut input_data = fr_data(epoch);
+            input_data.extend(verify_input(&proof_data, signal));
+            assert!(app.verify(input_data.as_slice()).unwrap());
+
+            let (_, public_inputs) = read_proof_data::<_, Bn256>(proof_data.as_slice()).unwrap();
+            let mut external_nullifier: Vec<u8> = Vec::new();
+            app.external_nullifier(fr_data(epoch).as_slice(), &mut external_nullifier)
+                .unwrap();
+            assert_eq!(fr_data(public_inputs[1]), external_nullifier);
+            assert_ne!(fr_data(epoch), external_nullifier);
+
+            let mut nullifier_input = fr_data(id_key);
+            nullifier_input.extend(fr_data(epoch));
+            nullifier_input.extend(fr_data(Fr::zero()));
+            let mut nullifier: Vec<u8> = Vec::new();
+            app.compute_nullifier(nullifier_input.as_slice(), &mut nullifier)
+                .unwrap();
+            assert_eq!(fr_data(public_inputs[4]), nullifier);
+            nullifiers.push(nullifier);
+            apps.push(app);
+            proofs.push(input_data);
+        }
+        assert_ne!(nullifiers[0], nullifiers[1]);
+
+        // a proof of another application in the same epoch does not verify
+        let err = apps[0].verify(proofs[1].as_slice()).unwrap_err();
+        assert!(matches!(err, RlnError::IdentifierMismatch));
+        let err = apps[0]
+            .verify_with_recent_root(proofs[1].as_slice())
+            .unwrap_err();
+        assert!(matches!(err, RlnError::IdentifierMismatch));
+        let err = apps[0]
+            .verify_and_check_nullifier(proofs[1].as_slice(), &[])
+            .unwrap_err();
+        assert!(matches!(err, RlnError::IdentifierMismatch));
+        let mut batch_data = proofs[0].clone();
+        batch_data.extend(&proofs[1]);
+        assert_eq!(
+            apps[0].verify_batch(batch_data.as_slice(), 2).unwrap(),
+            vec![true, false]
+        );
+        // nor does a proof of this application given another epoch
+        let mut other_epoch = proofs[0].clone();
+        other_epoch[..32].copy_from_slice(&fr_data(Fr::rand(&mut rng)));
+        let err = apps[0].verify(other_epoch.as_slice()).unwrap_err();
+        assert!(matches!(err, RlnError::IdentifierMismatch));
+        // an instance without identifier takes no epoch
+        assert!(rln.verify(&proofs[0][32..]).is_ok());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_json() {
+        let mut rng = rng();
+        let signal = RLNSignal::<Bn256> {
+            epoch: Fr::rand(&mut rng),
+            message_id: Fr::one(),
+            signal: b"rln signal".to_vec(),
+            hash: hash_to_field::<Bn256>(b"rln signal"),
+        };
+        let json = serde_json::to_string(&signal).unwrap();
+        let restored: RLNSignal<Bn256> = serde_json::from_str(&json).unwrap();
+        let mut signal_data: Vec<u8> = Vec::new();
+        signal.write(&mut signal_data).unwrap();
+        let mut restored_data: Vec<u8> = Vec::new();
+        restored.write(&mut restored_data).unwrap();
+        assert_eq!(signal_data, restored_data);
+        assert_eq!(signal.hash, restored.hash);
+
+        // the modulus is not a canonical field element
+        let mut modulus: Vec<u8> = Vec::new();
+        Fr::char().write_be(&mut modulus).unwrap();
+        let out_of_range = json.replace(
+            &serde_json::to_value(&signal).unwrap()["epoch"]
+                .as_str()
+                .unwrap()
+                .to_string(),
+            &format!("0x{}", hex::encode(&modulus)),
+        );
+        assert!(serde_json::from_str::<RLNSignal<Bn256>>(&out_of_range).is_err());
+
+        let mut rln = rln();
+        let id_key = Fr::rand(&mut rng);
+        let id_index = insert_member(&mut rln, id_key);
+        let input_data = proof_input(id_key, id_index, signal.epoch, Fr::zero(), &signal.signal);
+        let mut proof_data: Vec<u8> = Vec::new();
+        rln.generate_proof(input_data.as_slice(), &mut proof_data)
+            .unwrap();
+        let metadata = ProofMetadata::<Bn256>::read(proof_data.as_slice()).unwrap();
+        assert_eq!(signal.hash, metadata.share_x);
+        let json = serde_json::to_string(&metadata).unwrap();
+        let restored: ProofMetadata<Bn256> = serde_json::from_str(&json).unwrap();
+        assert_eq!(metadata.root, restored.root);
+        assert_eq!(metadata.nullifier, restored.nullifier);
+        assert_eq!(metadata.share_x, restored.share_x);
+        assert_eq!(metadata.share_y, restored.share_y);
+    }
+
+    #[test]
+    fn test_update_next_member_index() {
+        let mut rng = rng();
+        let mut rln = rln();
+        for expected in 0..3 {
+            let public_key = fr_data(Fr::rand(&mut rng));
+            let index = rln.update_next_member(public_key.as_slice()).unwrap();
+            assert_eq!(expected, index);
+        }
+    }
+
+    #[test]
+    fn test_full_tree() {
+        let mut rng = rng();
+        let mut rln = RLN::<Bn256>::new(3, message_limit(), None);
+        assert_eq!(3, rln.merkle_depth());
+        assert_eq!(8, rln.capacity());
+        for i in 0..8 {
+            let public_key = fr_data(Fr::rand(&mut rng));
+            rln.update_next_member(public_key.as_slice()).unwrap();
+            assert_eq!(i + 1, rln.len());
+        }
+        let mut root: Vec<u8> = Vec::new();
+        rln.get_root(&mut root).unwrap();
+
+        let public_key = fr_data(Fr::rand(&mut rng));
+        let err = rln.update_next_member(public_key.as_slice()).unwrap_err();
+        assert!(matches!(err, RlnError::TreeFull));
+        assert_eq!(8, rln.len());
+        let mut root_after: Vec<u8> = Vec::new();
+        rln.get_root(&mut root_after).unwrap();
+        assert_eq!(root, root_after);
+    }
+
+    #[test]
+    fn test_nullifier_eq() {
+        let mut rng = rng();
+        let a = Fr::rand(&mut rng);
+        let b = Fr::rand(&mut rng);
+        assert!(nullifier_eq::<Bn256>(&fr_data(a), &fr_data(a)).unwrap());
+        assert!(!nullifier_eq::<Bn256>(&fr_data(a), &fr_data(b)).unwrap());
+
+        // a + modulus fits in 32 bytes and encodes the same value as a but is not canonical
+        let mut repr = a.into_repr();
+        repr.add_nocarry(&Fr::char());
+        let mut non_canonical: Vec<u8> = Vec::new();
+        repr.write_le(&mut non_canonical).unwrap();
+        let err = nullifier_eq::<Bn256>(&fr_data(a), &non_canonical).unwrap_err();
+        assert!(matches!(err, RlnError::NonCanonicalField));
+        let mut modulus: Vec<u8> = Vec::new();
+        Fr::char().write_le(&mut modulus).unwrap();
+        assert!(nullifier_eq::<Bn256>(&modulus, &fr_data(Fr::zero())).is_err());
+        assert!(nullifier_eq::<Bn256>(&fr_data(a)[..31], &fr_data(a)).is_err());
+    }
+
+    #[test]
+    fn test_generate_proof_at() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let id_keys: Vec<Fr> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+        for id_key in id_keys.iter() {
+            insert_member(&mut rln, *id_key);
+        }
+        let epoch = Fr::rand(&mut rng);
+        let signal = b"rln signal";
+
+        for &id_index in [0usize, 2].iter() {
+            // proof input without the index
+            let mut input_data = proof_input(id_keys[id_index], 0, epoch, Fr::zero(), signal);
+            input_data.drain(32..40);
+            let mut proof_data: Vec<u8> = Vec::new();
+            rln.generate_proof_at(id_index, input_data.as_slice(), &mut proof_data)
+                .unwrap();
+            let input_data = verify_input(&proof_data, signal);
+            assert!(rln.verify(input_data.as_slice()).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_error_variants() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let public_key = fr_data(Fr::rand(&mut rng));
+        let set_size = 1 << merkle_depth();
+
+        let err = rln.set_leaf(set_size, public_key.as_slice()).unwrap_err();
+        assert!(matches!(err, RlnError::IndexOutOfRange { index, .. } if index == set_size));
+        let err = rln.delete_member(set_size).unwrap_err();
+        assert!(matches!(err, RlnError::IndexOutOfRange { .. }));
+        let err = rln.get_leaf(set_size, &mut Vec::new()).unwrap_err();
+        assert!(matches!(err, RlnError::IndexOutOfRange { .. }));
+
+        let mut modulus: Vec<u8> = Vec::new();
+        Fr::char().write_le(&mut modulus).unwrap();
+        let err = rln.update_next_member(modulus.as_slice()).unwrap_err();
+        assert!(matches!(err, RlnError::NonCanonicalField));
+        let err = rln
+            .verify_with_root(&[0u8; 0][..], modulus.as_slice())
+            .unwrap_err();
+        assert!(matches!(err, RlnError::NonCanonicalField));
+
+        let err = rln.update_next_member(&public_key[..31]).unwrap_err();
+        assert!(matches!(&err, RlnError::Io(e) if e.kind() == io::ErrorKind::InvalidInput));
+
+        let count = set_size + 1;
+        let mut batch_data: Vec<u8> = Vec::new();
+        for _ in 0..count {
+            batch_data.extend(fr_data(Fr::rand(&mut rng)));
+        }
+        let err = rln
+            .update_next_batch(batch_data.as_slice(), count)
+            .unwrap_err();
+        assert!(matches!(err, RlnError::TreeFull));
+        assert!(rln.is_empty());
+
+        // domain errors keep their variant through io::Error
+        let err = RlnError::from(io::Error::from(RlnError::TreeFull));
+        assert!(matches!(err, RlnError::TreeFull));
+    }
+
+    #[test]
+    fn test_prepared_verifying_key() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let id_key = Fr::rand(&mut rng);
+        let id_index = insert_member(&mut rln, id_key);
+        let signal = b"rln signal";
+        let input_data = proof_input(id_key, id_index, Fr::rand(&mut rng), Fr::zero(), signal);
+        let mut proof_data: Vec<u8> = Vec::new();
+        rln.generate_proof(input_data.as_slice(), &mut proof_data)
+            .unwrap();
+        let mut tampered = proof_data.clone();
+        let last = tampered.len() - 32;
+        tampered[last] ^= 1;
+
+        let fresh = prepare_verifying_key(&rln.circuit_parameters.vk);
+        for proof_data in [&proof_data, &tampered].iter() {
+            let (proof, public_inputs) =
+                read_proof_data::<_, Bn256>(proof_data.as_slice()).unwrap();
+            let expected = verify_proof(&fresh, &proof, &public_inputs).unwrap();
+            let input_data = verify_input(proof_data, signal);
+            assert_eq!(expected, rln.verify(input_data.as_slice()).unwrap());
+            assert_eq!(
+                expected,
+                rln.verifier().verify(input_data.as_slice()).unwrap()
+            );
+        }
+
+        // the cached key is reused by repeated verifications
+        let input_data = verify_input(&proof_data, signal);
+        for _ in 0..3 {
+            assert!(rln.verify(input_data.as_slice()).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_new_empty() {
+        let mut rng = rng();
+        let mut rln =
+            RLN::<Bn256>::new_empty(merkle_depth(), message_limit(), None, &mut rng).unwrap();
+        let id_key = Fr::rand(&mut rng);
+        let id_index = insert_member(&mut rln, id_key);
+        let signal = b"rln signal";
+        let input_data = proof_input(id_key, id_index, Fr::rand(&mut rng), Fr::zero(), signal);
+        let mut proof_data: Vec<u8> = Vec::new();
+        rln.generate_proof(input_data.as_slice(), &mut proof_data)
+            .unwrap();
+        let input_data = verify_input(&proof_data, signal);
+        assert!(rln.verify(input_data.as_slice()).unwrap());
+
+        assert!(RLN::<Bn256>::new_empty(merkle_depth(), 0, None, &mut rng).is_err());
+    }
+
+    #[test]
+    fn test_import_phase2_params() {
+        let mut rng = rng();
+        let ceremony = rln();
+        let mut phase2_data: Vec<u8> = Vec::new();
+        ceremony.circuit_parameters.write(&mut phase2_data).unwrap();
+        phase2_data.extend_from_slice(&[0u8; 64]);
+        phase2_data.write_u32::<BigEndian>(1).unwrap();
+        phase2_data.extend(std::iter::repeat(1u8).take(3 * 64 + 128 + 64));
+
+        let mut rln = rln();
+        rln.import_phase2_params(phase2_data.as_slice()).unwrap();
+        let id_key = Fr::rand(&mut rng);
+        let id_index = insert_member(&mut rln, id_key);
+        let signal = b"rln signal";
+        let input_data = proof_input(id_key, id_index, Fr::rand(&mut rng), Fr::zero(), signal);
+        let mut proof_data: Vec<u8> = Vec::new();
+        rln.generate_proof(input_data.as_slice(), &mut proof_data)
+            .unwrap();
+        let input_data = verify_input(&proof_data, signal);
+        assert!(rln.verify(input_data.as_slice()).unwrap());
+        assert!(ceremony.verify(input_data.as_slice()).unwrap());
+
+        // parameters of another circuit
+        let mut other = RLN::<Bn256>::new(merkle_depth() + 1, message_limit(), None);
+        assert!(other.import_phase2_params(phase2_data.as_slice()).is_err());
+        // truncated contributions
+        assert!(rln
+            .import_phase2_params(&phase2_data[..phase2_data.len() - 1])
+            .is_err());
+    }
+
+    #[test]
+    fn test_merkle_tree_utility() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let mut tree = MerkleTree::<Bn256>::new(merkle_depth(), rln.poseidon_params.clone());
+        for i in 0..5 {
+            let leaf = Fr::rand(&mut rng);
+            rln.update_next_member(fr_data(leaf).as_slice()).unwrap();
+            assert_eq!(tree.insert(leaf).unwrap(), i);
+        }
+
+        let mut root_data: Vec<u8> = Vec::new();
+        rln.get_root(&mut root_data).unwrap();
+        assert_eq!(fr_data(tree.root()), root_data);
+        assert_eq!(tree.proof(3).unwrap(), rln.tree.get_witness(3).unwrap());
+    }
+
+    #[test]
+    fn test_update_next_member_validation() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let public_key = fr_data(Fr::rand(&mut rng));
+
+        let mut long_data = public_key.clone();
+        long_data.push(0);
+        for data in vec![&public_key[..31], long_data.as_slice()] {
+            let err = rln.update_next_member(data).unwrap_err();
+            assert!(matches!(&err, RlnError::Io(e) if e.kind() == io::ErrorKind::InvalidInput));
+        }
+        let mut modulus: Vec<u8> = Vec::new();
+        Fr::char().write_le(&mut modulus).unwrap();
+        let err = rln.update_next_member(modulus.as_slice()).unwrap_err();
+        assert!(matches!(err, RlnError::NonCanonicalField));
+        assert!(rln.is_empty());
+
+        assert_eq!(rln.update_next_member(public_key.as_slice()).unwrap(), 0);
+        assert_eq!(rln.len(), 1);
+    }
+
+    #[test]
+    fn test_epoch_from_timestamp() {
+        let rln = rln();
+        let epoch = |unix_seconds: u64| {
+            let mut epoch_data: Vec<u8> = Vec::new();
+            rln.epoch_from_timestamp(unix_seconds, 10, &mut epoch_data)
+                .unwrap();
+            epoch_data
+        };
+        assert_eq!(epoch(1_600_000_000), epoch(1_600_000_009));
+        assert_ne!(epoch(1_600_000_009), epoch(1_600_000_010));
+
+        let window = Fr::from_str("160000000").unwrap();
+        assert_eq!(
+            epoch(1_600_000_000),
+            fr_data(rln.hasher().hash(vec![window]))
+        );
+        assert!(rln
+            .epoch_from_timestamp(1_600_000_000, 0, &mut Vec::new())
+            .is_err());
+    }
+
+    #[test]
+    fn test_verify_with_inputs() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let id_key = Fr::rand(&mut rng);
+        let id_index = insert_member(&mut rln, id_key);
+        let epoch = Fr::rand(&mut rng);
+        let input_data = proof_input(id_key, id_index, epoch, Fr::zero(), b"rln signal");
+        let mut proof_data: Vec<u8> = Vec::new();
+        rln.generate_proof(input_data.as_slice(), &mut proof_data)
+            .unwrap();
+
+        let proof = &proof_data[5..261];
+        let public_inputs = RLNInputs::<Bn256>::read_public_inputs(&proof_data[261..]).unwrap();
+        let verify = |inputs: &[Fr]| {
+            rln.verify_with_inputs(proof, inputs[0], inputs[1], inputs[4], inputs[2], inputs[3])
+                .unwrap()
+        };
+        assert!(verify(&public_inputs));
+        for i in 0..public_inputs.len() {
+            let mut tampered = public_inputs.clone();
+            tampered[i].add_assign(&Fr::one());
+            assert!(!verify(&tampered));
+        }
+
+        assert!(rln
+            .verify_with_inputs(&proof_data[5..], epoch, epoch, epoch, epoch, epoch)
+            .is_err());
+    }
+
+    #[test]
+    fn test_circuit_size() {
+        let rlns: Vec<RLN<Bn256>> = (2..5)
+            .map(|merkle_depth| RLN::new(merkle_depth, message_limit(), None))
+            .collect();
+        let constraints: Vec<usize> = rlns.iter().map(|rln| rln.num_constraints()).collect();
+        // each level of the path adds the same number of constraints
+        assert!(constraints[0] < constraints[1]);
+        assert_eq!(
+            constraints[1] - constraints[0],
+            constraints[2] - constraints[1]
+        );
+
+        let (pk_size, vk_size) = rlns[0].param_sizes();
+        let mut circuit_parameters: Vec<u8> = Vec::new();
+        rlns[0]
+            .export_circuit_parameters(&mut circuit_parameters)
+            .unwrap();
+        assert_eq!(pk_size, circuit_parameters.len());
+        let mut verifying_key: Vec<u8> = Vec::new();
+        rlns[0].export_verifying_key(&mut verifying_key).unwrap();
+        assert_eq!(vk_size, verifying_key.len());
+        assert!(vk_size < pk_size);
+        assert!(pk_size < rlns[1].param_sizes().0);
+    }
+
+    #[test]
+    fn test_reset_tree() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let mut empty_root: Vec<u8> = Vec::new();
+        rln.get_root(&mut empty_root).unwrap();
+        for _ in 0..3 {
+            insert_member(&mut rln, Fr::rand(&mut rng));
+        }
+
+        rln.reset_tree();
+        assert_eq!(rln.len(), 0);
+        let mut root_data: Vec<u8> = Vec::new();
+        rln.get_root(&mut root_data).unwrap();
+        assert_eq!(empty_root, root_data);
+
+        let id_key = Fr::rand(&mut rng);
+        assert_eq!(insert_member(&mut rln, id_key), 0);
+        let epoch = Fr::rand(&mut rng);
+        let input_data = proof_input(id_key, 0, epoch, Fr::zero(), b"rln signal");
+        let mut proof_data: Vec<u8> = Vec::new();
+        rln.generate_proof(input_data.as_slice(), &mut proof_data)
+            .unwrap();
+        let input_data = verify_input(&proof_data, b"rln signal");
+        assert!(rln.verify(input_data.as_slice()).unwrap());
+    }
+
+    #[test]
+    fn test_hash_message() {
+        let rln = rln();
+        let hash = |message: &[u8]| {
+            let mut hash_data: Vec<u8> = Vec::new();
+            rln.hash_message(message, &mut hash_data).unwrap();
+            hash_data
+        };
+        let message: Vec<u8> = (0..100u8).collect();
+        assert_eq!(hash(&message), hash(&message));
+        assert_ne!(hash(&message), hash(&message[..99]));
+        // trailing zero bytes are not lost to chunk padding
+        assert_ne!(
+            hash(&message[..64]),
+            hash(&[&message[..64], &[0u8]].concat())
+        );
+
+        let zero = Fr::zero();
+        assert_eq!(hash(&[]), fr_data(rln.hasher().hash_n(vec![zero], 1)[0]));
+    }
+
+    #[test]
+    fn test_key_gen_secret() {
+        let rln = rln();
+        let (secret, public_key) = rln.key_gen_secret();
+        assert_eq!(public_key, rln.hasher().hash(vec![*secret.expose()]));
+
+        let mut rng = rng();
+        let mut key_pair_data: Vec<u8> = Vec::new();
+        rln.key_gen_with_rng(&mut rng, &mut key_pair_data).unwrap();
+        let key_pair = read_fr::<_, Bn256>(key_pair_data.as_slice(), 2).unwrap();
+        assert_eq!(key_pair[1], rln.hasher().hash(vec![key_pair[0]]));
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn test_secret_key_zeroize() {
+        use std::mem::{size_of, MaybeUninit};
+        let mut secret = MaybeUninit::new(SecretKey::<Bn256>::new(Fr::from_str("1001").unwrap()));
+        // best effort, the memory of the dropped key is inspected in place
+        let bytes = unsafe {
+            std::ptr::drop_in_place(secret.as_mut_ptr());
+            std::slice::from_raw_parts(secret.as_ptr() as *const u8, size_of::<SecretKey<Bn256>>())
+        };
+        assert!(bytes.iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn test_verify_and_check_nullifier() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let id_key = Fr::rand(&mut rng);
+        let id_index = insert_member(&mut rln, id_key);
+        let epoch = Fr::rand(&mut rng);
+        let signal = b"rln signal";
+        let input_data = proof_input(id_key, id_index, epoch, Fr::zero(), signal);
+        let mut proof_data: Vec<u8> = Vec::new();
+        rln.generate_proof(input_data.as_slice(), &mut proof_data)
+            .unwrap();
+        let input_data = verify_input(&proof_data, signal);
+        let nullifier = &proof_data[proof_data.len() - 32..];
+        let other = fr_data(Fr::rand(&mut rng));
+
+        let outcome = rln
+            .verify_and_check_nullifier(input_data.as_slice(), &[other.as_slice()])
+            .unwrap();
+        assert_eq!(outcome, VerifyOutcome::Valid);
+        let outcome = rln
+            .verify_and_check_nullifier(input_data.as_slice(), &[other.as_slice(), nullifier])
+            .unwrap();
+        assert_eq!(outcome, VerifyOutcome::DuplicateNullifier);
+
+        // a tampered share_y
+        let mut tampered = input_data.clone();
+        tampered[5 + 256 + 3 * 32] ^= 1;
+        let outcome = rln
+            .verify_and_check_nullifier(tampered.as_slice(), &[nullifier])
+            .unwrap();
+        assert_eq!(outcome, VerifyOutcome::Invalid);
+    }
+
+    #[test]
+    fn test_proof_to_evm_calldata() {
+        use bellman::pairing::bn256::{Fq, G1Affine, G2Affine};
+        let proof = Proof::<Bn256> {
+            a: G1Affine::one(),
+            b: G2Affine::one(),
+            c: G1Affine::one(),
+        };
+        let public_inputs: Vec<Fr> = (1..6)
+            .map(|i| Fr::from_str(&i.to_string()).unwrap())
+            .collect();
+        let mut proof_data: Vec<u8> = Vec::new();
+        write_proof_header(&mut proof_data).unwrap();
+        write_uncompressed_proof(proof, &mut proof_data).unwrap();
+        for input in public_inputs.iter() {
+            input.into_repr().write_le(&mut proof_data).unwrap();
+        }
+
+        let mut calldata: Vec<u8> = Vec::new();
+        proof_to_evm_calldata(&proof_data, &mut calldata).unwrap();
+        assert_eq!(calldata.len(), 13 * 32);
+
+        // generators of bn254 as in EIP-197
+        let words = [
+            "1",
+            "2",
+            "11559732032986387107991004021392285783925812861821192530917403151452391805634",
+            "10857046999023057135944570762232829481370756359578518086990519993285655852781",
+            "4082367875863433681332203403145435568316851327593401208105741076214120093531",
+            "8495653923123431417604973247489272438418190587263600148770280649306958101930",
+            "1",
+            "2",
+        ];
+        let mut expected: Vec<u8> = Vec::new();
+        for word in words.iter() {
+            Fq::from_str(word)
+                .unwrap()
+                .into_repr()
+                .write_be(&mut expected)
+                .unwrap();
+        }
+        for input in public_inputs.iter() {
+            input.into_repr().write_be(&mut expected).unwrap();
+        }
+        assert_eq!(calldata, expected);
+        assert_eq!(calldata[31], 1);
+        assert_eq!(calldata[63], 2);
+
+        proof_data.push(0);
+        assert!(proof_to_evm_calldata(&proof_data, &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_poseidon_width() {
+        let mut rng = rng();
+        let binary = rln();
+        let poseidon_params = PoseidonParams::<Bn256>::new(8, 55, 5, None, None, None);
+        // a width 5 poseidon hashes 4 children per node, a tree of half the depth holds as many members
+        let merkle_depth = merkle_depth() / 2;
+        let mut rln = RLN::<Bn256>::new_empty(
+            merkle_depth,
+            message_limit(),
+            Some(poseidon_params.clone()),
+            &mut rng,
+        )
+        .unwrap();
+        assert_eq!(binary.capacity(), rln.capacity());
+        assert!(rln.num_constraints() < binary.num_constraints());
+        let deep = RLN::<Bn256>::new_empty(
+            merkle_depth * 2,
+            message_limit(),
+            Some(poseidon_params.clone()),
+            &mut rng,
+        )
+        .unwrap();
+        assert_eq!(1 << (4 * merkle_depth), deep.capacity());
+        assert!(deep.num_constraints() > binary.num_constraints());
+
+        // members at every position among their siblings are proven
+        let id_keys: Vec<Fr> = (0..6).map(|_| Fr::rand(&mut rng)).collect();
+        for id_key in id_keys.iter() {
+            insert_member(&mut rln, *id_key);
+        }
+        let signal = b"rln signal";
+        for (id_index, id_key) in id_keys.iter().enumerate() {
+            let input_data = proof_input(*id_key, id_index, Fr::rand(&mut rng), Fr::zero(), signal);
+            let mut proof_data: Vec<u8> = Vec::new();
+            rln.generate_proof(input_data.as_slice(), &mut proof_data)
+                .unwrap();
+            assert!(rln
+                .verify(verify_input(&proof_data, signal).as_slice())
+                .unwrap());
+        }
+        let input_data = proof_input(id_keys[0], 1, Fr::rand(&mut rng), Fr::zero(), signal);
+        assert!(!rln.check_witness(input_data.as_slice()).unwrap());
+
+        // each level of the authentication path holds 3 siblings
+        let mut proof_data: Vec<u8> = Vec::new();
+        rln.get_merkle_proof(5, &mut proof_data).unwrap();
+        assert_eq!(merkle_depth * 3 * 33, proof_data.len());
+        let mut input_data: Vec<u8> = Vec::new();
+        rln.get_root(&mut input_data).unwrap();
+        input_data.extend(fr_data(rln.hasher().hash(vec![id_keys[5]])));
+        input_data.extend(proof_data.iter());
+        assert!(rln.verify_merkle_proof(input_data.as_slice()).unwrap());
+
+        // parameters of width 3 are rejected for width 5
+        let mut circuit_parameters: Vec<u8> = Vec::new();
+        binary
+            .export_circuit_parameters(&mut circuit_parameters)
+            .unwrap();
+        let err = RLN::<Bn256>::new_with_raw_params(
+            merkle_depth * 2,
+            message_limit(),
+            circuit_parameters.as_slice(),
+            Some(poseidon_params),
+        )
+        .unwrap_err();
+        assert!(matches!(&err, RlnError::Io(e) if e.kind() == io::ErrorKind::InvalidData));
+
+        let narrow_params = PoseidonParams::<Bn256>::new(8, 55, 2, None, None, None);
+        let err =
+            RLN::<Bn256>::new_empty(merkle_depth, message_limit(), Some(narrow_params), &mut rng)
+                .err()
+                .unwrap();
+        assert!(matches!(&err, RlnError::Io(e) if e.kind() == io::ErrorKind::InvalidInput));
+    }
+
+    #[test]
+    fn test_insertion_proof() {
+        let mut rng = rng();
+        let mut rln = rln();
+        insert_member(&mut rln, Fr::rand(&mut rng));
+        let mut old_root: Vec<u8> = Vec::new();
+        rln.get_root(&mut old_root).unwrap();
+
+        let leaf = fr_data(Fr::rand(&mut rng));
+        let index = rln.update_next_member(leaf.as_slice()).unwrap();
+        let mut new_root: Vec<u8> = Vec::new();
+        rln.get_root(&mut new_root).unwrap();
+        let mut witness: Vec<u8> = Vec::new();
+        rln.prove_insertion(index, &old_root, &mut witness).unwrap();
+
+        assert!(rln
+            .verify_insertion(&old_root, &new_root, &leaf, &witness)
+            .unwrap());
+        let other_leaf = fr_data(Fr::rand(&mut rng));
+        assert!(!rln
+            .verify_insertion(&old_root, &new_root, &other_leaf, &witness)
+            .unwrap());
+        assert!(!rln
+            .verify_insertion(&new_root, &new_root, &leaf, &witness)
+            .unwrap());
+
+        // a later change invalidates the transition
+        insert_member(&mut rln, Fr::rand(&mut rng));
+        let err = rln
+            .prove_insertion(index, &old_root, &mut Vec::new())
+            .unwrap_err();
+        assert!(matches!(err, RlnError::RootMismatch));
+    }
+
+    #[test]
+    fn test_generate_proof_with_rng() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let id_key = Fr::rand(&mut rng);
+        let id_index = insert_member(&mut rln, id_key);
+        let epoch = Fr::rand(&mut rng);
+        let input_data = proof_input(id_key, id_index, epoch, Fr::zero(), b"rln signal");
+
+        let mut proofs: Vec<Vec<u8>> = Vec::new();
+        for _ in 0..2 {
+            let mut proof_data: Vec<u8> = Vec::new();
+            rln.generate_proof_with_rng(input_data.as_slice(), &mut self::rng(), &mut proof_data)
+                .unwrap();
+            let verify_data = verify_input(&proof_data, b"rln signal");
+            assert!(rln.verify(verify_data.as_slice()).unwrap());
+            proofs.push(proof_data);
+        }
+        assert_eq!(proofs[0], proofs[1]);
+
+        let mut proof_data: Vec<u8> = Vec::new();
+        rln.generate_proof_with_rng(input_data.as_slice(), &mut rng, &mut proof_data)
+            .unwrap();
+        assert_ne!(proofs[0], proof_data);
+    }
+
+    #[test]
+    fn test_contains() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let leaves: Vec<Vec<u8>> = (0..4).map(|_| fr_data(Fr::rand(&mut rng))).collect();
+        for leaf in leaves[..3].iter() {
+            rln.update_next_member(leaf.as_slice()).unwrap();
+        }
+        assert_eq!(rln.contains(&leaves[1]).unwrap(), Some(1));
+        assert_eq!(rln.contains(&leaves[3]).unwrap(), None);
+
+        rln.set_leaf(1, leaves[3].as_slice()).unwrap();
+        assert_eq!(rln.contains(&leaves[1]).unwrap(), None);
+        assert_eq!(rln.contains(&leaves[3]).unwrap(), Some(1));
+
+        rln.delete_member(2).unwrap();
+        assert_eq!(rln.contains(&leaves[2]).unwrap(), None);
+        assert_eq!(rln.contains(&fr_data(Fr::zero())).unwrap(), None);
+        assert!(rln.contains(&leaves[0][..31]).is_err());
+
+        // deleting one copy of a duplicate public key keeps the other
+        rln.set_leaf(2, leaves[0].as_slice()).unwrap();
+        assert_eq!(rln.contains(&leaves[0]).unwrap(), Some(0));
+        rln.delete_member(2).unwrap();
+        assert_eq!(rln.contains(&leaves[0]).unwrap(), Some(0));
+        rln.set_leaf(2, leaves[0].as_slice()).unwrap();
+        rln.delete_member(0).unwrap();
+        assert_eq!(rln.contains(&leaves[0]).unwrap(), Some(2));
+    }
+
+    #[test]
+    fn test_decode_public_inputs() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let id_key = Fr::rand(&mut rng);
+        let id_index = insert_member(&mut rln, id_key);
+        let epoch = Fr::rand(&mut rng);
+        let signal = b"rln signal";
+        let input_data = proof_input(id_key, id_index, epoch, Fr::zero(), signal);
+        let mut proof_data: Vec<u8> = Vec::new();
+        rln.generate_proof(input_data.as_slice(), &mut proof_data)
+            .unwrap();
+
+        // a broken proof point does not prevent decoding
+        proof_data[5] ^= 0xff;
+        let inputs = rln.decode_public_inputs(proof_data.as_slice()).unwrap();
+        let root = rln.tree.get_root();
+        assert_eq!(inputs.root, root);
+        assert_eq!(inputs.epoch, epoch);
+        assert_eq!(inputs.share_x, hash_to_field::<Bn256>(signal));
+        let mut nullifier_data: Vec<u8> = Vec::new();
+        let mut nullifier_input: Vec<u8> = Vec::new();
+        for e in [id_key, epoch, Fr::zero()].iter() {
+            e.into_repr().write_le(&mut nullifier_input).unwrap();
+        }
+        rln.compute_nullifier(nullifier_input.as_slice(), &mut nullifier_data)
+            .unwrap();
+        assert_eq!(fr_data(inputs.nullifier), nullifier_data);
+        // proof metadata is decoded alike
+        let metadata = ProofMetadata::<Bn256>::read(proof_data.as_slice()).unwrap();
+        assert_eq!(metadata.root, inputs.root);
+        assert_eq!(metadata.nullifier, inputs.nullifier);
+        assert_eq!(metadata.share_y, inputs.share_y);
+
+        assert!(rln
+            .decode_public_inputs(&proof_data[..proof_data.len() - 1])
+            .is_err());
+    }
+
+    #[test]
+    fn test_slasher() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let id_key = Fr::rand(&mut rng);
+        let id_index = insert_member(&mut rln, id_key);
+        let epochs = [Fr::rand(&mut rng), Fr::rand(&mut rng)];
+        let proof = |epoch: Fr, signal: &[u8]| {
+            let input_data = proof_input(id_key, id_index, epoch, Fr::zero(), signal);
+            let mut proof_data: Vec<u8> = Vec::new();
+            rln.generate_proof(input_data.as_slice(), &mut proof_data)
+                .unwrap();
+            proof_data
+        };
+
+        let mut slasher = Slasher::<Bn256>::new();
+        assert!(slasher
+            .observe(proof(epochs[0], b"signal 1").as_slice())
+            .unwrap()
+            .is_none());
+        // other epochs have other nullifiers
+        assert!(slasher
+            .observe(proof(epochs[1], b"signal 2").as_slice())
+            .unwrap()
+            .is_none());
+        // the same share does not reveal anything
+        assert!(slasher
+            .observe(proof(epochs[0], b"signal 1").as_slice())
+            .unwrap()
+            .is_none());
+
+        let recovered = slasher
+            .observe(proof(epochs[0], b"signal 2").as_slice())
+            .unwrap();
+        assert_eq!(recovered, Some(fr_data(id_key)));
+        assert!(slasher
+            .observe(proof(epochs[0], b"signal 3").as_slice())
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_field_endian() {
+        let value = Fr::from_str("258").unwrap();
+        for endian in [Endian::Little, Endian::Big].iter() {
+            let mut data: Vec<u8> = Vec::new();
+            serialize_field::<Bn256, _>(&value, *endian, &mut data).unwrap();
+            assert_eq!(deserialize_field::<Bn256>(&data, *endian).unwrap(), value);
+        }
+        let mut le_data: Vec<u8> = Vec::new();
+        serialize_field::<Bn256, _>(&value, Endian::Little, &mut le_data).unwrap();
+        let mut be_data: Vec<u8> = Vec::new();
+        serialize_field::<Bn256, _>(&value, Endian::Big, &mut be_data).unwrap();
+        assert_ne!(le_data, be_data);
+        assert_eq!(&le_data[..2], &[2, 1]);
+        assert_eq!(&be_data[30..], &[1, 2]);
+        assert!(deserialize_field::<Bn256>(&be_data[1..], Endian::Big).is_err());
+
+        let mut rng = rng();
+        let mut rln = rln();
+        let id_key = Fr::rand(&mut rng);
+        let id_index = insert_member(&mut rln, id_key);
+        let epoch = Fr::rand(&mut rng);
+        let input_data = proof_input(id_key, id_index, epoch, Fr::zero(), b"rln signal");
+        let mut proof_data: Vec<u8> = Vec::new();
+        rln.generate_proof_endian(input_data.as_slice(), Endian::Big, &mut proof_data)
+            .unwrap();
+        let inputs_start = 5 + 256;
+        let root =
+            deserialize_field::<Bn256>(&proof_data[inputs_start..inputs_start + 32], Endian::Big)
+                .unwrap();
+        assert_eq!(root, rln.tree.get_root());
+        let verify_data = verify_input(&proof_data, b"rln signal");
+        assert!(rln
+            .verify_endian(verify_data.as_slice(), Endian::Big)
+            .unwrap());
+    }
+
+    #[test]
+    fn test_single_signer() {
+        let mut rng = rng();
+        let mut rln = RLN::<Bn256>::new(0, message_limit(), None);
+        assert_eq!(rln.capacity(), 1);
+        let id_key = Fr::rand(&mut rng);
+        let public_key = rln.hasher().hash(vec![id_key]);
+        assert_eq!(insert_member(&mut rln, id_key), 0);
+        assert_eq!(rln.tree.get_root(), public_key);
+        assert!(matches!(
+            rln.update_next_member(fr_data(public_key).as_slice()),
+            Err(RlnError::TreeFull)
+        ));
+
+        let epoch = Fr::rand(&mut rng);
+        let mut proof_data: Vec<u8> = Vec::new();
+        let input_data = proof_input(id_key, 1, epoch, Fr::zero(), b"rln signal");
+        let err = rln
+            .generate_proof(input_data.as_slice(), &mut proof_data)
+            .unwrap_err();
+        assert!(matches!(err, RlnError::IndexOutOfRange { index: 1, .. }));
+
+        let input_data = proof_input(id_key, 0, epoch, Fr::zero(), b"rln signal");
+        rln.generate_proof(input_data.as_slice(), &mut proof_data)
+            .unwrap();
+        let input_data = verify_input(&proof_data, b"rln signal");
+        assert!(rln.verify(input_data.as_slice()).unwrap());
+    }
+
+    #[test]
+    fn test_id_commitment() {
+        let rln = rln();
+        let mut key_pair_data: Vec<u8> = Vec::new();
+        rln.key_gen(&mut key_pair_data).unwrap();
+        let mut public_key: Vec<u8> = Vec::new();
+        rln.id_commitment(&key_pair_data[..32], &mut public_key)
+            .unwrap();
+        assert_eq!(public_key.as_slice(), &key_pair_data[32..]);
+
+        let mut output: Vec<u8> = Vec::new();
+        let err = rln.id_commitment(&[0xffu8; 32], &mut output).unwrap_err();
+        assert!(matches!(err, RlnError::NonCanonicalField));
+        let err = rln
+            .id_commitment(&key_pair_data[..31], &mut output)
+            .unwrap_err();
+        assert!(matches!(err, RlnError::Io(ref e) if e.kind() == io::ErrorKind::InvalidInput));
+    }
+
+    #[test]
+    fn test_verify_stream() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let id_key = Fr::rand(&mut rng);
+        let id_index = insert_member(&mut rln, id_key);
+
+        let mut stream: Vec<u8> = Vec::new();
+        for i in 0..3 {
+            let signal = format!("rln signal {}", i);
+            let input_data = proof_input(
+                id_key,
+                id_index,
+                Fr::rand(&mut rng),
+                Fr::zero(),
+                signal.as_bytes(),
+            );
+            let mut proof_data: Vec<u8> = Vec::new();
+            rln.generate_proof(input_data.as_slice(), &mut proof_data)
+                .unwrap();
+            let mut frame = verify_input(&proof_data, signal.as_bytes());
+            if i == 1 {
+                // tampered nullifier
+                let last = frame.len() - 8 - signal.len() - 32;
+                frame[last] ^= 1;
+            }
+            stream
+                .write_u64::<LittleEndian>(frame.len() as u64)
+                .unwrap();
+            stream.write_all(&frame).unwrap();
+        }
+
+        let results: Vec<bool> = rln
+            .verify_stream(io::Cursor::new(&stream))
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(vec![true, false, true], results);
+
+        // stream ending inside a frame
+        let mut results = rln.verify_stream(&stream[..stream.len() - 1]);
+        assert!(results.next().unwrap().unwrap());
+        assert!(!results.next().unwrap().unwrap());
+        let err = results.next().unwrap().unwrap_err();
+        assert!(matches!(err, RlnError::Io(ref e) if e.kind() == io::ErrorKind::UnexpectedEof));
+        assert!(results.next().is_none());
+    }
+
+    #[test]
+    fn test_clone_verifier() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let id_key = Fr::rand(&mut rng);
+        let id_index = insert_member(&mut rln, id_key);
+        let verifier = rln.clone_verifier();
+
+        let input_data = proof_input(
+            id_key,
+            id_index,
+            Fr::rand(&mut rng),
+            Fr::zero(),
+            b"rln signal",
+        );
+        let mut proof_data: Vec<u8> = Vec::new();
+        rln.generate_proof(input_data.as_slice(), &mut proof_data)
+            .unwrap();
+        let input_data = verify_input(&proof_data, b"rln signal");
+        assert!(verifier.verify(input_data.as_slice()).unwrap());
+
+        let mut vk_data: Vec<u8> = Vec::new();
+        rln.export_verifying_key(&mut vk_data).unwrap();
+        assert_eq!(verifier.size(), vk_data.len());
+        let (params_size, vk_size) = rln.param_sizes();
+        assert_eq!(verifier.size(), vk_size);
+        assert!(verifier.size() * 10 < params_size);
+    }
+
+    #[test]
+    fn test_hash_with_params() {
+        use sapling_crypto::circuit::num::AllocatedNum;
+        use sapling_crypto::circuit::test::TestConstraintSystem;
+
+        let mut rng = rng();
+        let rln = rln();
+        let inputs: Vec<Fr> = (0..2).map(|_| Fr::rand(&mut rng)).collect();
+        let mut input_data: Vec<u8> = Vec::new();
+        for input in inputs.iter() {
+            input_data.extend(fr_data(*input));
+        }
+
+        let mut cs = TestConstraintSystem::<Bn256>::new();
+        let allocated_inputs = inputs
+            .iter()
+            .enumerate()
+            .map(|(i, e)| {
+                AllocatedNum::alloc(cs.namespace(|| format!("input {}", i)), || Ok(*e)).unwrap()
+            })
+            .collect();
+        let in_circuit = PoseidonCircuit::new(rln.poseidon_params())
+            .alloc(cs.namespace(|| "hash"), allocated_inputs)
+            .unwrap()
+            .get_value()
+            .unwrap();
+        assert!(cs.is_satisfied());
+
+        let mut output_data: Vec<u8> = Vec::new();
+        rln.hash_with_params(
+            input_data.as_slice(),
+            2,
+            rln.poseidon_params(),
+            &mut output_data,
+        )
+        .unwrap();
+        assert_eq!(fr_data(in_circuit), output_data);
+
+        let poseidon_params = PoseidonParams::<Bn256>::builder()
+            .partial_rounds(57)
+            .build();
+        let mut other_output_data: Vec<u8> = Vec::new();
+        rln.hash_with_params(
+            input_data.as_slice(),
+            2,
+            poseidon_params,
+            &mut other_output_data,
+        )
+        .unwrap();
+        assert_ne!(output_data, other_output_data);
+
+        let narrow_params = PoseidonParams::<Bn256>::builder().width(2).build();
+        assert!(rln
+            .hash_with_params(input_data.as_slice(), 2, narrow_params, &mut output_data)
+            .is_err());
+
+        // inputs beyond the rate are rejected rather than dropped
+        input_data.extend(fr_data(Fr::rand(&mut rng)));
+        let err = rln
+            .hash_with_params(
+                input_data.as_slice(),
+                3,
+                rln.poseidon_params(),
+                &mut output_data,
+            )
+            .unwrap_err();
+        assert!(matches!(&err, RlnError::Io(e) if e.kind() == io::ErrorKind::InvalidInput));
+        let wide_params = PoseidonParams::<Bn256>::builder().width(4).build();
+        let mut wide_output_data: Vec<u8> = Vec::new();
+        rln.hash_with_params(input_data.as_slice(), 3, wide_params, &mut wide_output_data)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_generate_proof_identifier_check() {
+        let mut rng = rng();
+        let id_key = Fr::rand(&mut rng);
+        let rln_identifier = Fr::from_str("1").unwrap();
+        let mut app = rln().with_rln_identifier(rln_identifier);
+        let id_index = insert_member(&mut app, id_key);
+        let signal = b"rln signal";
+        let input_data = proof_input(id_key, id_index, Fr::rand(&mut rng), Fr::zero(), signal);
+
+        let mut mismatched = fr_data(Fr::from_str("2").unwrap());
+        mismatched.extend(&input_data);
+        let mut proof_data: Vec<u8> = Vec::new();
+        let err = app
+            .generate_proof(mismatched.as_slice(), &mut proof_data)
+            .unwrap_err();
+        assert!(matches!(err, RlnError::IdentifierMismatch));
+        assert!(proof_data.is_empty());
+
+        let mut matching = fr_data(rln_identifier);
+        matching.extend(&input_data);
+        app.generate_proof(matching.as_slice(), &mut proof_data)
+            .unwrap();
+        let mut verify_data = input_data[40..72].to_vec();
+        verify_data.extend(verify_input(&proof_data, signal));
+        assert!(app.verify(verify_data.as_slice()).unwrap());
+
+        // the identifier is not optional once set
+        let err = app
+            .generate_proof(input_data.as_slice(), &mut proof_data)
+            .unwrap_err();
+        assert!(matches!(err, RlnError::IdentifierMismatch));
+
+        // an instance without identifier takes none
+        let mut rln = rln();
+        insert_member(&mut rln, id_key);
+        let mut proof_data: Vec<u8> = Vec::new();
+        rln.generate_proof(input_data.as_slice(), &mut proof_data)
+            .unwrap();
+        assert!(rln
+            .verify(verify_input(&proof_data, signal).as_slice())
+            .unwrap());
+    }
+
+    #[test]
+    fn test_export_progress() {
+        let rln = rln();
+        let mut calls: Vec<(u64, u64)> = Vec::new();
+        let mut circuit_parameters: Vec<u8> = Vec::new();
+        rln.export_circuit_parameters_with_progress(&mut circuit_parameters, |written, total| {
+            calls.push((written, total))
+        })
+        .unwrap();
+        assert!(!calls.is_empty());
+        assert!(calls.windows(2).all(|w| w[0].0 <= w[1].0));
+        let (written, total) = *calls.last().unwrap();
+        assert_eq!(circuit_parameters.len() as u64, written);
+        assert_eq!(written, total);
+
+        let mut exported: Vec<u8> = Vec::new();
+        rln.export_circuit_parameters(&mut exported).unwrap();
+        assert_eq!(circuit_parameters, exported);
+    }
+
+    #[test]
+    fn test_validate_parameters() {
+        let rln = rln();
+        rln.validate_parameters().unwrap();
+
+        let shallow = RLN::<Bn256>::new(merkle_depth() - 1, message_limit(), None);
+        let mut circuit_parameters: Vec<u8> = Vec::new();
+        shallow
+            .circuit_parameters
+            .write(&mut circuit_parameters)
+            .unwrap();
+        let mismatched = RLN::<Bn256>::new_with_params(
+            merkle_depth(),
+            message_limit(),
+            Parameters::read(circuit_parameters.as_slice(), true).unwrap(),
+            rln.poseidon_params(),
+        );
+        let err = mismatched.validate_parameters().unwrap_err();
+        assert!(matches!(err, RlnError::Io(ref e) if e.kind() == io::ErrorKind::InvalidData));
+    }
+
+    #[test]
+    fn test_shamir_share() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let id_key = Fr::rand(&mut rng);
+        let id_index = insert_member(&mut rln, id_key);
+        let epoch = Fr::rand(&mut rng);
+        let message_id = Fr::one();
+
+        let mut shares: Vec<(Fr, Fr)> = Vec::new();
+        for signal in [&b"rln signal 1"[..], &b"rln signal 2"[..]].iter() {
+            let mut share_x_data: Vec<u8> = Vec::new();
+            let mut signal_data: Vec<u8> = Vec::new();
+            signal_data
+                .write_u64::<LittleEndian>(signal.len() as u64)
+                .unwrap();
+            signal_data.write_all(signal).unwrap();
+            rln.signal_to_field(signal_data.as_slice(), &mut share_x_data)
+                .unwrap();
+
+            let mut input_data = fr_data(id_key);
+            input_data.extend(fr_data(epoch));
+            input_data.extend(fr_data(message_id));
+            input_data.extend(&share_x_data);
+            let mut share_y_data: Vec<u8> = Vec::new();
+            rln.shamir_share(input_data.as_slice(), &mut share_y_data)
+                .unwrap();
+
+            // the share carried by a proof of the same inputs
+            let input_data = proof_input(id_key, id_index, epoch, message_id, signal);
+            let mut proof_data: Vec<u8> = Vec::new();
+            rln.generate_proof(input_data.as_slice(), &mut proof_data)
+                .unwrap();
+            let mut share_data: Vec<u8> = Vec::new();
+            rln.extract_share(proof_data.as_slice(), &mut share_data)
+                .unwrap();
+            assert_eq!(share_x_data, share_data[..32].to_vec());
+            assert_eq!(share_y_data, share_data[32..].to_vec());
+
+            let share = read_fr::<_, Bn256>(share_data.as_slice(), 2).unwrap();
+            shares.push((share[0], share[1]));
+        }
+        assert_eq!(id_key, recover_secret::<Bn256>(shares[0], shares[1]));
+    }
+
+    #[test]
+    fn test_proof_cache() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let id_key = Fr::rand(&mut rng);
+        let id_index = insert_member(&mut rln, id_key);
+        let input_data = proof_input(
+            id_key,
+            id_index,
+            Fr::rand(&mut rng),
+            Fr::zero(),
+            b"rln signal",
+        );
+        let prove = |rln: &RLN<Bn256>| {
+            let mut proof_data: Vec<u8> = Vec::new();
+            rln.generate_proof(input_data.as_slice(), &mut proof_data)
+                .unwrap();
+            proof_data
+        };
+
+        // proofs are randomized unless cached
+        assert_ne!(prove(&rln), prove(&rln));
+
+        rln.set_proof_cache_capacity(2);
+        let proof_data = prove(&rln);
+        assert_eq!(proof_data, prove(&rln));
+        let verify_data = verify_input(&proof_data, b"rln signal");
+        assert!(rln.verify(verify_data.as_slice()).unwrap());
+        // entries are keyed by a digest, the input with its secret is not retained
+        for key in rln.proof_cache.lock().unwrap().order.iter() {
+            assert_eq!(key.len(), 32);
+            assert_ne!(*key, fr_data(id_key));
+        }
+
+        // a new member changes the root
+        insert_member(&mut rln, Fr::rand(&mut rng));
+        let new_proof_data = prove(&rln);
+        assert_ne!(proof_data, new_proof_data);
+        assert_eq!(new_proof_data, prove(&rln));
+
+        rln.set_proof_cache_capacity(0);
+        assert_ne!(prove(&rln), prove(&rln));
+    }
+
+    #[test]
+    fn test_prove_and_verify() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let id_key = Fr::rand(&mut rng);
+        let id_index = insert_member(&mut rln, id_key);
+        let epoch = Fr::rand(&mut rng);
+
+        let input_data = proof_input(id_key, id_index, epoch, Fr::zero(), b"rln signal");
+        assert!(rln.prove_and_verify(&input_data).unwrap());
+
+        // not the key of the member at the index
+        let input_data = proof_input(
+            Fr::rand(&mut rng),
+            id_index,
+            epoch,
+            Fr::zero(),
+            b"rln signal",
+        );
+        assert!(!rln.prove_and_verify(&input_data).unwrap());
+
+        let input_data = proof_input(id_key, rln.capacity(), epoch, Fr::zero(), b"rln signal");
+        let err = rln.prove_and_verify(&input_data).unwrap_err();
+        assert!(matches!(err, RlnError::IndexOutOfRange { .. }));
+        assert!(rln.prove_and_verify(&input_data[..40]).is_err());
+    }
+
+    #[test]
+    fn test_empty_leaf() {
+        let mut rln = rln();
+        let mut empty_leaf_data: Vec<u8> = Vec::new();
+        rln.empty_leaf(&mut empty_leaf_data).unwrap();
+        assert_eq!(fr_data(Fr::zero()), empty_leaf_data);
+
+        let empty_leaf = read_fr::<_, Bn256>(empty_leaf_data.as_slice(), 1).unwrap()[0];
+        let hasher = rln.hasher();
+        let root = (0..merkle_depth()).fold(empty_leaf, |node, _| hasher.hash(vec![node, node]));
+        let mut root_data: Vec<u8> = Vec::new();
+        rln.get_root(&mut root_data).unwrap();
+        assert_eq!(fr_data(root), root_data);
+
+        // a deleted member is the empty leaf
+        insert_member(&mut rln, Fr::one());
+        rln.delete_member(0).unwrap();
+        let mut leaf_data: Vec<u8> = Vec::new();
+        rln.get_leaf(0, &mut leaf_data).unwrap();
+        assert_eq!(empty_leaf_data, leaf_data);
+        let mut root_data: Vec<u8> = Vec::new();
+        rln.get_root(&mut root_data).unwrap();
+        assert_eq!(fr_data(root), root_data);
+    }
+
+    #[test]
+    fn test_from_commitments() {
+        let mut rng = rng();
+        let merkle_depth = 10;
+        let mut rln = RLN::<Bn256>::new(merkle_depth, message_limit(), None);
+        let mut circuit_parameters: Vec<u8> = Vec::new();
+        rln.export_circuit_parameters(&mut circuit_parameters)
+            .unwrap();
+        let commitments: Vec<u8> = (0..1000)
+            .flat_map(|_| fr_data(Fr::rand(&mut rng)))
+            .collect();
+
+        let before = crate::merkle::hash_count();
+        for commitment in commitments.chunks(32) {
+            rln.update_next_member(commitment).unwrap();
+        }
+        let sequential_hashes = crate::merkle::hash_count() - before;
+
+        let built = RLN::<Bn256>::from_commitments(
+            merkle_depth,
+            message_limit(),
+            &circuit_parameters,
+            &commitments,
+            1000,
+        )
+        .unwrap();
+        // the tree construction of `from_commitments` without loading the parameters
+        let mut fresh = RLN::<Bn256>::new_with_raw_params(
+            merkle_depth,
+            message_limit(),
+            &circuit_parameters,
+            None,
+        )
+        .unwrap();
+        let before = crate::merkle::hash_count();
+        fresh
+            .update_next_batch(commitments.as_slice(), 1000)
+            .unwrap();
+        let bulk_hashes = crate::merkle::hash_count() - before;
+
+        let mut root: Vec<u8> = Vec::new();
+        rln.get_root(&mut root).unwrap();
+        let mut built_root: Vec<u8> = Vec::new();
+        built.get_root(&mut built_root).unwrap();
+        assert_eq!(root, built_root);
+        assert_eq!(1000, built.len());
+        assert_eq!(1000 * merkle_depth, sequential_hashes);
+        // each node above the members is hashed once
+        assert!(bulk_hashes < 1 << merkle_depth);
+
+        assert!(RLN::<Bn256>::from_commitments(
+            merkle_depth,
+            message_limit(),
+            &circuit_parameters,
+            &commitments,
+            usize::MAX,
+        )
+        .is_err());
+
+        assert!(RLN::<Bn256>::from_commitments(
+            merkle_depth,
+            message_limit(),
+            &circuit_parameters,
+            &commitments[..100],
+            1000,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_compute_root() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let commitments: Vec<u8> = (0..5).flat_map(|_| fr_data(Fr::rand(&mut rng))).collect();
+        rln.update_next_batch(commitments.as_slice(), 5).unwrap();
+        let mut root: Vec<u8> = Vec::new();
+        rln.get_root(&mut root).unwrap();
+        assert_eq!(
+            root,
+            compute_root::<Bn256>(merkle_depth(), None, &commitments, 5).unwrap()
+        );
+        assert_eq!(
+            root,
+            compute_root::<Bn256>(merkle_depth(), Some(rln.poseidon_params()), &commitments, 5)
+                .unwrap()
+        );
+
+        assert!(compute_root::<Bn256>(merkle_depth(), None, &commitments, 4).is_err());
+        assert!(compute_root::<Bn256>(merkle_depth(), None, &commitments, 6).is_err());
+        // more members than leaves
+        let commitments: Vec<u8> = (0..17).flat_map(|_| fr_data(Fr::rand(&mut rng))).collect();
+        assert!(compute_root::<Bn256>(merkle_depth(), None, &commitments, 17).is_err());
+    }
+
+    #[test]
+    fn test_signal_hash() {
+        let rln = rln();
+        let signal_hash = |parts: &[&[u8]]| {
+            let mut result_data: Vec<u8> = Vec::new();
+            rln.signal_hash(parts, &mut result_data).unwrap();
+            result_data
+        };
+        assert_eq!(signal_hash(&[b"a", b"bc"]), signal_hash(&[b"a", b"bc"]));
+        assert_ne!(signal_hash(&[b"a", b"bc"]), signal_hash(&[b"ab", b"c"]));
+        assert_ne!(signal_hash(&[b"abc"]), signal_hash(&[b"abc", b""]));
+        assert_ne!(signal_hash(&[]), signal_hash(&[b""]));
+
+        let mut message_hash: Vec<u8> = Vec::new();
+        rln.hash_message(b"abc", &mut message_hash).unwrap();
+        assert_ne!(message_hash, signal_hash(&[b"abc"]));
+    }
+
+    #[test]
+    fn test_proof_for_empty_slot() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let id_key = Fr::rand(&mut rng);
+        let epoch = Fr::rand(&mut rng);
+        let input_data = proof_input(id_key, 0, epoch, Fr::zero(), b"rln signal");
+        let mut proof_data: Vec<u8> = Vec::new();
+        let err = rln
+            .generate_proof(input_data.as_slice(), &mut proof_data)
+            .unwrap_err();
+        assert!(matches!(err, RlnError::Io(ref e) if e.kind() == io::ErrorKind::NotFound));
+
+        // a slot below the cursor that was skipped
+        let public_key = rln.hasher().hash(vec![id_key]);
+        rln.set_leaf(2, fr_data(public_key).as_slice()).unwrap();
+        let input_data = proof_input(id_key, 1, epoch, Fr::zero(), b"rln signal");
+        let err = rln
+            .generate_proof(input_data.as_slice(), &mut proof_data)
+            .unwrap_err();
+        assert!(matches!(err, RlnError::Io(ref e) if e.kind() == io::ErrorKind::NotFound));
+        assert!(proof_data.is_empty());
+
+        let input_data = proof_input(id_key, 2, epoch, Fr::zero(), b"rln signal");
+        rln.generate_proof(input_data.as_slice(), &mut proof_data)
+            .unwrap();
+        let input_data = verify_input(&proof_data, b"rln signal");
+        assert!(rln.verify(input_data.as_slice()).unwrap());
+    }
+
+    #[test]
+    fn test_multi_app() {
+        let mut rng = rng();
+        let mut multi = RLNMulti::new(rln());
+        let app_a = fr_data(Fr::from_str("1").unwrap());
+        let app_b = fr_data(Fr::from_str("2").unwrap());
+        multi.add_app(&app_a).unwrap();
+        multi.add_app(&app_b).unwrap();
+        assert!(multi.add_app(&app_a).is_err());
+
+        // the same member in both apps, so both trees have the same root
+        let id_key = Fr::rand(&mut rng);
+        let public_key = fr_data(multi.rln.hasher().hash(vec![id_key]));
+        let id_index = multi.update_next(&app_a, public_key.as_slice()).unwrap();
+        assert_eq!(
+            id_index,
+            multi.update_next(&app_b, public_key.as_slice()).unwrap()
+        );
+        let mut root_a: Vec<u8> = Vec::new();
+        multi.get_root(&app_a, &mut root_a).unwrap();
+        let mut root_b: Vec<u8> = Vec::new();
+        multi.get_root(&app_b, &mut root_b).unwrap();
+        assert_eq!(root_a, root_b);
+
+        let epoch = Fr::rand(&mut rng);
+        let signal = b"rln signal";
+        let input_data = proof_input(id_key, id_index, epoch, Fr::zero(), signal);
+        let mut proof_data: Vec<u8> = Vec::new();
+        multi
+            .generate_proof(&app_a, input_data.as_slice(), &mut proof_data)
+            .unwrap();
+        let mut verify_data = fr_data(epoch);
+        verify_data.extend(verify_input(&proof_data, signal));
+        assert!(multi.verify(&app_a, verify_data.as_slice()).unwrap());
+        let err = multi.verify(&app_b, verify_data.as_slice()).unwrap_err();
+        assert!(matches!(err, RlnError::IdentifierMismatch));
+
+        // another member only in app b
+        multi
+            .update_next(&app_b, fr_data(Fr::rand(&mut rng)).as_slice())
+            .unwrap();
+        let err = multi.verify(&app_b, verify_data.as_slice()).unwrap_err();
+        assert!(matches!(err, RlnError::RootMismatch));
+        assert!(multi.verify(&app_a, verify_data.as_slice()).unwrap());
+
+        let unknown = fr_data(Fr::from_str("3").unwrap());
+        assert!(multi
+            .generate_proof(&unknown, input_data.as_slice(), &mut proof_data)
+            .is_err());
+        assert_eq!(0, multi.rln.len());
+    }
+
+    #[test]
+    fn test_parameters_digest() {
+        let rln = rln();
+        let mut circuit_parameters: Vec<u8> = Vec::new();
+        rln.export_circuit_parameters(&mut circuit_parameters)
+            .unwrap();
+        let load = |data: &[u8], unchecked: bool, skip_digest: bool| {
+            RLN::<Bn256>::builder()
+                .merkle_depth(merkle_depth())
+                .message_limit(message_limit())
+                .params(data)
+                .unchecked(unchecked)
+                .skip_digest(skip_digest)
+                .build()
+        };
+        assert!(load(circuit_parameters.as_slice(), false, false).is_ok());
+
+        // the low byte of the last coordinate of the last point, still a valid encoding
+        let mut corrupted = circuit_parameters.clone();
+        let i = corrupted.len() - 33;
+        corrupted[i] ^= 1;
+        let err = load(corrupted.as_slice(), true, false).err().unwrap();
+        assert!(matches!(&err, RlnError::Io(e) if e.kind() == io::ErrorKind::InvalidData));
+        assert!(err.to_string().contains("digest mismatch"));
+        assert!(load(corrupted.as_slice(), false, false).is_err());
+        assert!(load(corrupted.as_slice(), true, true).is_ok());
+
+        let mut corrupted = circuit_parameters.clone();
+        let i = corrupted.len() - 1;
+        corrupted[i] ^= 1;
+        assert!(load(corrupted.as_slice(), true, false).is_err());
+
+        // parameters exported without a digest are still accepted
+        let legacy = &circuit_parameters[..circuit_parameters.len() - 32];
+        assert!(load(legacy, false, false).is_ok());
+        let truncated = &circuit_parameters[..circuit_parameters.len() - 16];
+        assert!(load(truncated, false, false).is_err());
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn test_proof_timings() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let id_key = Fr::rand(&mut rng);
+        let id_index = insert_member(&mut rln, id_key);
+        let epoch = Fr::rand(&mut rng);
+        let input_data = proof_input(id_key, id_index, epoch, Fr::zero(), b"rln signal");
+
+        let mut timings = ProofTimings::default();
+        let mut proof_data: Vec<u8> = Vec::new();
+        let now = std::time::Instant::now();
+        rln.generate_proof_with_timings(input_data.as_slice(), &mut proof_data, &mut timings)
+            .unwrap();
+        let total_ns = now.elapsed().as_nanos() as u64;
+        assert!(rln
+            .verify(verify_input(&proof_data, b"rln signal").as_slice())
+            .unwrap());
+
+        assert!(timings.witness_ns > 0);
+        assert!(timings.prove_ns > 0);
+        let measured_ns = timings.witness_ns + timings.prove_ns;
+        assert!(measured_ns <= total_ns);
+        // only the call itself is not measured
+        assert!(total_ns - measured_ns < total_ns / 10);
+    }
+
+    #[test]
+    fn test_snapshot_restore() {
+        let mut rng = rng();
+        let mut rln = rln().with_rln_identifier(Fr::rand(&mut rng));
+        let id_key = Fr::rand(&mut rng);
+        for _ in 0..3 {
+            insert_member(&mut rln, Fr::rand(&mut rng));
+        }
+        let id_index = insert_member(&mut rln, id_key);
+        rln.delete_member(1).unwrap();
+
+        let mut snapshot: Vec<u8> = Vec::new();
+        rln.snapshot(&mut snapshot).unwrap();
+        assert_eq!(16 + 1 + 32 + 8 + 4 * 32, snapshot.len());
+
+        let mut circuit_parameters: Vec<u8> = Vec::new();
+        rln.export_circuit_parameters(&mut circuit_parameters)
+            .unwrap();
+        let restored =
+            RLN::<Bn256>::restore(circuit_parameters.as_slice(), snapshot.as_slice()).unwrap();
+        assert_eq!(rln.tree.get_root(), restored.tree.get_root());
+        assert_eq!(rln.tree.current_index, restored.tree.current_index);
+        assert_eq!(rln.message_limit, restored.message_limit);
+        assert_eq!(rln.rln_identifier(), restored.rln_identifier());
+
+        let signal = b"rln signal";
+        let epoch = Fr::rand(&mut rng);
+        let mut input_data = fr_data(restored.rln_identifier().unwrap());
+        input_data.extend(proof_input(id_key, id_index, epoch, Fr::zero(), signal));
+        let mut proof_data: Vec<u8> = Vec::new();
+        restored
+            .generate_proof(input_data.as_slice(), &mut proof_data)
+            .unwrap();
+        let mut verify_data = fr_data(epoch);
+        verify_data.extend(verify_input(&proof_data, signal));
+        assert!(rln.verify(verify_data.as_slice()).unwrap());
+
+        // parameters of another depth
+        let other = RLN::<Bn256>::new(merkle_depth() + 1, message_limit(), None);
+        let mut other_parameters: Vec<u8> = Vec::new();
+        other
+            .export_circuit_parameters(&mut other_parameters)
+            .unwrap();
+        let err = RLN::<Bn256>::restore(other_parameters.as_slice(), snapshot.as_slice())
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("merkle depth mismatch"));
+    }
+
+    #[test]
+    fn test_check_witness() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let id_key = Fr::rand(&mut rng);
+        let id_index = insert_member(&mut rln, id_key);
+        let epoch = Fr::rand(&mut rng);
+
+        let input_data = proof_input(id_key, id_index, epoch, Fr::zero(), b"rln signal");
+        assert!(rln.check_witness(input_data.as_slice()).unwrap());
+
+        // a key that is not the one of the member at the index
+        let input_data = proof_input(
+            Fr::rand(&mut rng),
+            id_index,
+            epoch,
+            Fr::zero(),
+            b"rln signal",
+        );
+        assert!(!rln.check_witness(input_data.as_slice()).unwrap());
+
+        let mut message_id = Fr::one();
+        message_id.double();
+        let input_data = proof_input(id_key, id_index, epoch, message_id, b"rln signal");
+        assert!(rln.check_witness(input_data.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_proofs_for_epochs() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let id_key = Fr::rand(&mut rng);
+        let id_index = insert_member(&mut rln, id_key);
+        let signal = b"rln signal";
+
+        let mut input_data: Vec<u8> = Vec::new();
+        id_key.into_repr().write_le(&mut input_data).unwrap();
+        input_data
+            .write_u64::<LittleEndian>(id_index as u64)
+            .unwrap();
+        Fr::zero().into_repr().write_le(&mut input_data).unwrap();
+        hash_to_field::<Bn256>(signal)
+            .into_repr()
+            .write_le(&mut input_data)
+            .unwrap();
+        for _ in 0..3 {
+            Fr::rand(&mut rng)
+                .into_repr()
+                .write_le(&mut input_data)
+                .unwrap();
+        }
+        let mut proofs_data: Vec<u8> = Vec::new();
+        rln.generate_proofs_for_epochs(input_data.as_slice(), 3, &mut proofs_data)
+            .unwrap();
+
+        let mut reader = proofs_data.as_slice();
+        let mut nullifiers: Vec<Vec<u8>> = Vec::new();
+        while let Some(proof_data) = read_frame(&mut reader).unwrap() {
+            assert!(rln
+                .verify(verify_input(&proof_data, signal).as_slice())
+                .unwrap());
+            nullifiers.push(proof_data[proof_data.len() - 32..].to_vec());
+        }
+        assert_eq!(3, nullifiers.len());
+        assert_ne!(nullifiers[0], nullifiers[1]);
+        assert_ne!(nullifiers[1], nullifiers[2]);
+        assert_ne!(nullifiers[0], nullifiers[2]);
+    }
+
+    #[test]
+    fn test_verify_malformed_or_invalid() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let id_key = Fr::rand(&mut rng);
+        let id_index = insert_member(&mut rln, id_key);
+        let signal = b"rln signal";
+        let input_data = proof_input(id_key, id_index, Fr::rand(&mut rng), Fr::zero(), signal);
+        let mut proof_data: Vec<u8> = Vec::new();
+        rln.generate_proof(input_data.as_slice(), &mut proof_data)
+            .unwrap();
+
+        // the y coordinate of `a` negated, still a point on the curve
+        let mut proof = read_uncompressed_proof::<_, Bn256>(&proof_data[5..261]).unwrap();
+        proof.a.negate();
+        let mut tampered = proof_data[..5].to_vec();
+        write_uncompressed_proof(proof, &mut tampered).unwrap();
+        tampered.extend_from_slice(&proof_data[261..]);
+        assert_eq!(proof_data.len(), tampered.len());
+        assert!(!rln
+            .verify(verify_input(&tampered, signal).as_slice())
+            .unwrap());
+
+        // a y coordinate off the curve
+        let mut tampered = proof_data.clone();
+        tampered[5 + 63] ^= 1;
+        let err = io::Error::from(
+            rln.verify(verify_input(&tampered, signal).as_slice())
+                .unwrap_err(),
+        );
+        assert_eq!(io::ErrorKind::InvalidData, err.kind());
+
+        let truncated = verify_input(&proof_data, signal);
+        let err = io::Error::from(rln.verify(&truncated[..truncated.len() - 1]).unwrap_err());
+        assert_eq!(io::ErrorKind::UnexpectedEof, err.kind());
+    }
+
+    #[test]
+    fn test_signal_hash_keccak() {
+        // uint256(keccak256(abi.encodePacked(signal))) % SNARK_SCALAR_FIELD
+        let vectors: [(&[u8], &str); 2] = [
+            (
+                b"",
+                "1924180730567573949438414972962865885128629851683618892617351438379423999084",
+            ),
+            (
+                b"abc",
+                "13398160249016090740558721491792534793121512351235850635913704876345442266180",
+            ),
+        ];
+        for (signal, expected) in vectors.iter() {
+            assert_eq!(
+                Fr::from_str(expected).unwrap(),
+                signal_hash_keccak::<Bn256>(signal)
+            );
+        }
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_new_mmap() {
+        let mut rng = rng();
+        let rln = rln();
+        let mut circuit_parameters: Vec<u8> = Vec::new();
+        rln.export_circuit_parameters(&mut circuit_parameters)
+            .unwrap();
+        let path = std::env::temp_dir().join(format!("rln_mmap_params_{}", std::process::id()));
+        std::fs::write(&path, &circuit_parameters).unwrap();
+        let restored = RLN::<Bn256>::new_mmap(merkle_depth(), message_limit(), &path);
+        std::fs::remove_file(&path).unwrap();
+        let mut restored = restored.unwrap();
+        let mut mapped_parameters: Vec<u8> = Vec::new();
+        restored
+            .export_circuit_parameters(&mut mapped_parameters)
+            .unwrap();
+        assert_eq!(mapped_parameters, circuit_parameters);
+
+        let id_key = Fr::rand(&mut rng);
+        let id_index = insert_member(&mut restored, id_key);
+        let signal = b"rln signal";
+        let input_data = proof_input(id_key, id_index, Fr::rand(&mut rng), Fr::zero(), signal);
+        let mut proof_data: Vec<u8> = Vec::new();
+        restored
+            .generate_proof(input_data.as_slice(), &mut proof_data)
+            .unwrap();
+        assert!(rln
+            .verify(verify_input(&proof_data, signal).as_slice())
+            .unwrap());
+
+        let missing = std::env::temp_dir().join("rln_mmap_missing_params");
+        let err = RLN::<Bn256>::new_mmap(merkle_depth(), message_limit(), &missing)
+            .err()
+            .unwrap();
+        assert!(matches!(&err, RlnError::Io(e) if e.kind() == io::ErrorKind::NotFound));
+    }
+
+    #[test]
+    fn test_nullifier_log() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let id_key = Fr::rand(&mut rng);
+        let id_index = insert_member(&mut rln, id_key);
+        let epoch = Fr::rand(&mut rng);
+        // [ nullifier<32> | share_x<32> | share_y<32> ] of a proof
+        let record = |signal: &[u8]| {
+            let input_data = proof_input(id_key, id_index, epoch, Fr::zero(), signal);
+            let mut proof_data: Vec<u8> = Vec::new();
+            rln.generate_proof(input_data.as_slice(), &mut proof_data)
+                .unwrap();
+            let public_inputs = &proof_data[5 + 256..];
+            let mut record = public_inputs[4 * 32..].to_vec();
+            record.extend_from_slice(&public_inputs[2 * 32..4 * 32]);
+            record
+        };
+
+        let path = std::env::temp_dir().join(format!("rln_nullifier_log_{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let (mut log, recovered) = NullifierLog::<Bn256>::open(&path).unwrap();
+        assert!(recovered.is_empty());
+        assert!(log.append(&record(b"signal 1")).unwrap().is_none());
+        assert!(log.append(&record(b"signal 1")).unwrap().is_none());
+        drop(log);
+
+        // restarted
+        let (mut log, recovered) = NullifierLog::<Bn256>::open(&path).unwrap();
+        assert!(recovered.is_empty());
+        let recovered = log.append(&record(b"signal 2")).unwrap();
+        assert_eq!(recovered, Some(fr_data(id_key)));
+        drop(log);
+
+        // the key recovered before the restart is reported again
+        let (mut log, recovered) = NullifierLog::<Bn256>::open(&path).unwrap();
+        assert_eq!(recovered, vec![fr_data(id_key)]);
+        assert!(log.append(&record(b"signal 3")).unwrap().is_none());
+        let err = log.append(&[0xffu8; 96]).unwrap_err();
+        assert!(matches!(err, RlnError::NonCanonicalField));
+        drop(log);
+        assert_eq!(4 * 96, std::fs::metadata(&path).unwrap().len() as usize);
+
+        // a write interrupted by a crash
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&record(b"signal 4")[..10]).unwrap();
+        drop(file);
+        let (mut log, recovered) = NullifierLog::<Bn256>::open(&path).unwrap();
+        assert_eq!(recovered, vec![fr_data(id_key)]);
+        assert_eq!(4 * 96, std::fs::metadata(&path).unwrap().len() as usize);
+        assert!(log.append(&record(b"signal 4")).unwrap().is_none());
+        drop(log);
+        let (_, recovered) = NullifierLog::<Bn256>::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(recovered, vec![fr_data(id_key)]);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parallel_witness() {
+        let mut rng = rng();
+        let mut rln = rln();
+        let id_key = Fr::rand(&mut rng);
+        let id_index = insert_member(&mut rln, id_key);
+        let epoch = Fr::rand(&mut rng);
+        let share_x = Fr::rand(&mut rng);
+
+        let witnesses = vec![
+            rln.sequential_witness(id_key, id_index, epoch, Fr::zero(), share_x)
+                .unwrap(),
+            rln.parallel_witness(id_key, id_index, epoch, Fr::zero(), share_x)
+                .unwrap(),
+        ];
+        assert_eq!(witnesses[0].public_inputs(), witnesses[1].public_inputs());
+        assert_eq!(witnesses[0].auth_path, witnesses[1].auth_path);
+
+        // with the same randomness both witnesses give the same proof
+        let mut proofs = Vec::new();
+        for inputs in witnesses.iter() {
+            let circuit = RLNCircuit {
+                inputs: inputs.clone(),
+                hasher: PoseidonCircuit::new(rln.poseidon_params.clone()),
+                message_limit: rln.message_limit,
+            };
+            let proof =
+                create_random_proof(circuit, &rln.circuit_parameters, &mut self::rng()).unwrap();
+            assert!(
+                check_proof(&rln.prepared_verifying_key, &proof, &inputs.public_inputs()).unwrap()
+            );
+            proofs.push(proof);
+        }
+        assert!(proofs[0] == proofs[1]);
+    }
+}
@@ -0,0 +1,44 @@
+use crate::poseidon::Poseidon as PoseidonHasher;
+use bellman::pairing::ff::{PrimeField, PrimeFieldRepr};
+use bellman::pairing::Engine;
+use std::io::{self, Write};
+
+/// identity secret of a member
+/// * with the `zeroize` feature the secret is scrubbed from memory on drop,
+///   copies taken with `expose` are not
+pub struct SecretKey<E: Engine>(E::Fr);
+
+impl<E: Engine> SecretKey<E> {
+    pub fn new(id_key: E::Fr) -> Self {
+        SecretKey(id_key)
+    }
+
+    pub fn expose(&self) -> &E::Fr {
+        &self.0
+    }
+
+    /// the public key of the member, `public_key = hash(id_key)`
+    pub fn public_key(&self, hasher: &PoseidonHasher<E>) -> E::Fr {
+        hasher.hash(vec![self.0])
+    }
+
+    /// writes the secret as [ id_key<32> ]
+    pub fn write<W: Write>(&self, writer: W) -> io::Result<()> {
+        self.0.into_repr().write_le(writer)
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<E: Engine> Drop for SecretKey<E> {
+    fn drop(&mut self) {
+        use zeroize_crate::Zeroize;
+        // field elements are plain limbs and all zero bytes is the zero element
+        let bytes = unsafe {
+            std::slice::from_raw_parts_mut(
+                &mut self.0 as *mut E::Fr as *mut u8,
+                std::mem::size_of::<E::Fr>(),
+            )
+        };
+        bytes.zeroize();
+    }
+}
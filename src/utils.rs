@@ -4,14 +4,27 @@ use bellman::pairing::{CurveAffine, EncodedPoint, Engine};
 use byteorder::{LittleEndian, ReadBytesExt};
 use std::io::{self, Error, ErrorKind, Read, Write};
 
+use crate::error::non_canonical_field;
 use crate::hash_to_field::hash_to_field;
 
-pub fn read_signal_hash<R: Read, E: Engine>(mut reader: R) -> io::Result<E::Fr> {
-    let n = reader.read_u64::<LittleEndian>()?;
-    let mut buf: Vec<u8> = vec![0; n as usize];
-    reader.read(&mut buf[..])?;
+pub fn read_signal_hash<R: Read, E: Engine>(reader: R) -> io::Result<E::Fr> {
+    let signal = read_signal(reader)?;
+    Ok(hash_to_field::<E>(&signal[..]))
+}
 
-    Ok(hash_to_field::<E>(&buf[..]))
+/// reads a signal serialized as [ signal_len<8> | signal<var> ]
+/// * the signal grows as it is read, a length beyond the input fails with `UnexpectedEof`
+pub fn read_signal<R: Read>(mut reader: R) -> io::Result<Vec<u8>> {
+    let signal_len = reader.read_u64::<LittleEndian>()?;
+    let mut signal: Vec<u8> = Vec::new();
+    reader.take(signal_len).read_to_end(&mut signal)?;
+    if signal.len() as u64 != signal_len {
+        return Err(Error::new(
+            ErrorKind::UnexpectedEof,
+            "signal is shorter than its length",
+        ));
+    }
+    Ok(signal)
 }
 
 pub fn read_fr<R: Read, E: Engine>(mut reader: R, n: usize) -> io::Result<Vec<E::Fr>> {
@@ -19,13 +32,63 @@ pub fn read_fr<R: Read, E: Engine>(mut reader: R, n: usize) -> io::Result<Vec<E:
     let mut buf = <E::Fr as PrimeField>::Repr::default();
     for _ in 0..n {
         buf.read_le(&mut reader)?;
-        let input =
-            E::Fr::from_repr(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let input = E::Fr::from_repr(buf).map_err(|_| non_canonical_field())?;
         out.push(input);
     }
     Ok(out)
 }
 
+/// reads a single field element from the whole of `reader`
+/// * fails with `InvalidInput` unless the input is exactly the field byte length
+pub fn read_single_fr<R: Read, E: Engine>(mut reader: R) -> io::Result<E::Fr> {
+    let mut data: Vec<u8> = Vec::new();
+    reader.read_to_end(&mut data)?;
+    let len = <E::Fr as PrimeField>::Repr::default().as_ref().len() * 8;
+    if data.len() != len {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "expected a field element of {} bytes, got {} bytes",
+                len,
+                data.len()
+            ),
+        ));
+    }
+    Ok(read_fr::<_, E>(data.as_slice(), 1)?[0])
+}
+
+/// magic bytes at the start of serialized proof data
+pub const PROOF_MAGIC: [u8; 4] = *b"RLNP";
+/// version of the proof data layout
+pub const PROOF_VERSION: u8 = 1;
+
+/// writes the proof data header serialized as [ magic<4> | version<1> ]
+pub fn write_proof_header<W: Write>(mut writer: W) -> io::Result<()> {
+    writer.write_all(&PROOF_MAGIC)?;
+    writer.write_all(&[PROOF_VERSION])?;
+    Ok(())
+}
+
+/// reads the proof data header and rejects unknown formats or versions
+pub fn read_proof_header<R: Read>(mut reader: R) -> io::Result<()> {
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if magic != PROOF_MAGIC {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "unrecognized proof format",
+        ));
+    }
+    let version = reader.read_u8()?;
+    if version != PROOF_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unsupported proof version {}", version),
+        ));
+    }
+    Ok(())
+}
+
 pub fn write_uncompressed_proof<W: Write, E: Engine>(
     proof: Proof<E>,
     mut writer: W,
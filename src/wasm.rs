@@ -1,3 +1,4 @@
+use crate::error::RlnError;
 use crate::public::RLN;
 
 use std::io::{self, Error, ErrorKind, Read, Write};
@@ -17,6 +18,11 @@ pub fn set_panic_hook() {
     console_error_panic_hook::set_once();
 }
 
+fn js_error(e: RlnError) -> JsValue {
+    e.to_string().into()
+}
+
+/// wraps `public::RLN`, inputs and outputs are serialized as in `public::RLN`
 #[wasm_bindgen]
 pub struct RLNWasm {
     api: RLN<Bn256>,
@@ -25,65 +31,79 @@ pub struct RLNWasm {
 #[wasm_bindgen]
 impl RLNWasm {
     #[wasm_bindgen]
-    pub fn new(merkle_depth: usize) -> RLNWasm {
+    pub fn new(merkle_depth: usize, message_limit: u64) -> RLNWasm {
         set_panic_hook();
         RLNWasm {
-            api: RLN::<Bn256>::new(merkle_depth, None),
+            api: RLN::<Bn256>::new(merkle_depth, message_limit, None),
         }
     }
 
     #[wasm_bindgen]
     pub fn new_with_raw_params(
         merkle_depth: usize,
+        message_limit: u64,
         raw_circuit_parameters: &[u8],
     ) -> Result<RLNWasm, JsValue> {
         set_panic_hook();
-        let api = match RLN::new_with_raw_params(merkle_depth, raw_circuit_parameters, None) {
-            Ok(api) => api,
-            Err(e) => return Err(e.to_string().into()),
-        };
+        let api =
+            RLN::new_with_raw_params(merkle_depth, message_limit, raw_circuit_parameters, None)
+                .map_err(js_error)?;
         Ok(RLNWasm { api })
     }
 
+    #[wasm_bindgen]
+    pub fn update_next_member(&mut self, input: &[u8]) -> Result<usize, JsValue> {
+        self.api.update_next_member(input).map_err(js_error)
+    }
+
+    #[wasm_bindgen]
+    pub fn delete_member(&mut self, index: usize) -> Result<(), JsValue> {
+        self.api.delete_member(index).map_err(js_error)
+    }
+
+    #[wasm_bindgen]
+    pub fn get_root(&self) -> Result<Vec<u8>, JsValue> {
+        let mut output: Vec<u8> = Vec::new();
+        self.api.get_root(&mut output).map_err(js_error)?;
+        Ok(output)
+    }
+
+    #[wasm_bindgen]
+    pub fn key_gen(&self) -> Result<Vec<u8>, JsValue> {
+        let mut output: Vec<u8> = Vec::new();
+        self.api.key_gen(&mut output).map_err(js_error)?;
+        Ok(output)
+    }
+
     #[wasm_bindgen]
     pub fn generate_proof(&self, input: &[u8]) -> Result<Vec<u8>, JsValue> {
-        let proof = match self.api.generate_proof(input) {
-            Ok(proof) => proof,
-            Err(e) => return Err(e.to_string().into()),
-        };
-        Ok(proof)
+        let mut output: Vec<u8> = Vec::new();
+        self.api
+            .generate_proof(input, &mut output)
+            .map_err(js_error)?;
+        Ok(output)
     }
 
     #[wasm_bindgen]
-    pub fn verify(
-        &self,
-        uncompresed_proof: &[u8],
-        raw_public_inputs: &[u8],
-    ) -> Result<bool, JsValue> {
-        let success = match self.api.verify(uncompresed_proof, raw_public_inputs) {
-            Ok(success) => success,
-            Err(e) => return Err(e.to_string().into()),
-        };
-        Ok(success)
+    pub fn verify(&self, input: &[u8]) -> Result<bool, JsValue> {
+        self.api.verify(input).map_err(js_error)
     }
 
     #[wasm_bindgen]
     pub fn export_verifier_key(&self) -> Result<Vec<u8>, JsValue> {
         let mut output: Vec<u8> = Vec::new();
-        match self.api.export_verifier_key(&mut output) {
-            Ok(_) => (),
-            Err(e) => return Err(e.to_string().into()),
-        };
+        self.api
+            .export_verifying_key(&mut output)
+            .map_err(js_error)?;
         Ok(output)
     }
 
     #[wasm_bindgen]
     pub fn export_circuit_parameters(&self) -> Result<Vec<u8>, JsValue> {
         let mut output: Vec<u8> = Vec::new();
-        match self.api.export_circuit_parameters(&mut output) {
-            Ok(_) => (),
-            Err(e) => return Err(e.to_string().into()),
-        };
+        self.api
+            .export_circuit_parameters(&mut output)
+            .map_err(js_error)?;
         Ok(output)
     }
 }
@@ -91,42 +111,44 @@ impl RLNWasm {
 #[cfg(test)]
 mod test {
 
-    use crate::circuit::bench;
     use wasm_bindgen_test::*;
 
-    use crate::circuit::poseidon::PoseidonCircuit;
-    use crate::circuit::rln::{RLNCircuit, RLNInputs};
-    use crate::merkle::MerkleTree;
-    use crate::poseidon::{Poseidon as PoseidonHasher, PoseidonParams};
-    use bellman::groth16::{generate_random_parameters, Parameters, Proof};
-    use bellman::pairing::bn256::{Bn256, Fr};
+    use bellman::pairing::bn256::Fr;
     use bellman::pairing::ff::{Field, PrimeField, PrimeFieldRepr};
+    use byteorder::{LittleEndian, WriteBytesExt};
     use rand::{Rand, SeedableRng, XorShiftRng};
+    use std::io::Write;
 
     #[wasm_bindgen_test]
     fn test_rln_wasm() {
+        let mut rng = XorShiftRng::from_seed([0x3dbe6258, 0x8d313d76, 0x3237db17, 0xe5bc0654]);
         let merkle_depth = 3usize;
-        let poseidon_params = PoseidonParams::<Bn256>::new(8, 55, 3, None, None, None);
-        let rln_test = bench::RLNTest::<Bn256>::new(merkle_depth, Some(poseidon_params));
-
-        let rln_wasm = super::RLNWasm::new(merkle_depth);
-
-        let mut raw_inputs: Vec<u8> = Vec::new();
-        let inputs = rln_test.valid_inputs();
-        inputs.write(&mut raw_inputs);
-
-        // let now = Instant::now();
-        let proof = rln_wasm.generate_proof(raw_inputs.as_slice()).unwrap();
-        // let prover_time = now.elapsed().as_millis() as f64 / 1000.0;
-
-        let mut raw_public_inputs: Vec<u8> = Vec::new();
-        inputs.write_public_inputs(&mut raw_public_inputs);
-
-        assert_eq!(
-            rln_wasm
-                .verify(proof.as_slice(), raw_public_inputs.as_slice())
-                .unwrap(),
-            true
-        );
+        let mut rln_wasm = super::RLNWasm::new(merkle_depth, 1);
+
+        let keypair = rln_wasm.key_gen().unwrap();
+        rln_wasm.update_next_member(&keypair[32..]).unwrap();
+
+        let signal = b"rln signal";
+        let mut input: Vec<u8> = Vec::new();
+        input.write_all(&keypair[..32]).unwrap();
+        input.write_u64::<LittleEndian>(0).unwrap();
+        Fr::rand(&mut rng).into_repr().write_le(&mut input).unwrap();
+        Fr::zero().into_repr().write_le(&mut input).unwrap();
+        input
+            .write_u64::<LittleEndian>(signal.len() as u64)
+            .unwrap();
+        input.write_all(signal).unwrap();
+
+        let mut proof = rln_wasm.generate_proof(input.as_slice()).unwrap();
+        proof
+            .write_u64::<LittleEndian>(signal.len() as u64)
+            .unwrap();
+        proof.write_all(signal).unwrap();
+        assert!(rln_wasm.verify(proof.as_slice()).unwrap());
+
+        let parameters = rln_wasm.export_circuit_parameters().unwrap();
+        let restored =
+            super::RLNWasm::new_with_raw_params(merkle_depth, 1, parameters.as_slice()).unwrap();
+        assert!(restored.verify(proof.as_slice()).unwrap());
     }
 }
@@ -0,0 +1,14 @@
+#![cfg(feature = "bench")]
+
+use rln::circuit::bench::RLNTest;
+use rln::poseidon::PoseidonParams;
+use sapling_crypto::bellman::pairing::bn256::Bn256;
+
+#[test]
+fn test_rln_test_with_bench_feature() {
+    let poseidon_params = PoseidonParams::<Bn256>::new(8, 55, 3, None, None, None);
+    let rln_test = RLNTest::<Bn256>::new(3, Some(poseidon_params));
+    assert!(rln_test.synthesize() > 0);
+    let result = rln_test.run_prover_bench();
+    assert!(result.prover_key_size > 0);
+}